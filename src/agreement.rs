@@ -1,88 +1,39 @@
-use ring::{agreement, error, rand};
-use std::sync::Mutex;
-use untrusted;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use exonum::crypto;
+use group;
 
 /// Agreement.
 
-/// KeyPair struct used to store public and private keys for ephemeral key creation.
-pub struct KeyPair {
-    pub secret: agreement::EphemeralPrivateKey,
-    pub public: Vec<u8>,
-}
-
-/// EphemeralKeys struct used to store public output key and ephemeral key.
+/// KeyPair struct used to store Ristretto public and secret keys.
 #[derive(Clone, Debug)]
-pub struct EphemeralKeys {
-    pub public_out_key: Vec<u8>,
-    pub ephemeral_key: Vec<u8>,
-}
-
-/// Singleton for EphemeralKeys struct.
-lazy_static! {
-    static ref EPHEMERAL_KEYS: Mutex<EphemeralKeys> = Mutex::new(EphemeralKeys {
-        public_out_key: vec![],
-        ephemeral_key: vec![],
-    });
-}
-
-/// Initializes EphemeralKeys singleton for service.
-pub fn init_ephemeral(peer_public: &[u8]) {
-    let key_pair = generate_key_pair();
-    let ephemeral = generate_ephemeral(peer_public, key_pair.secret);
-
-    EPHEMERAL_KEYS.lock().unwrap().public_out_key = ephemeral.public_out_key;
-    EPHEMERAL_KEYS.lock().unwrap().ephemeral_key = ephemeral.ephemeral_key;
-}
-
-/// Returns EphemeralKeys struct.
-pub fn get_ephemeral() -> EphemeralKeys {
-    let keys = EPHEMERAL_KEYS.lock().unwrap();
-    EphemeralKeys {
-        public_out_key: keys.public_out_key.clone(),
-        ephemeral_key: keys.ephemeral_key.clone(),
-    }
+pub struct KeyPair {
+    pub secret: Scalar,
+    pub public: RistrettoPoint,
 }
 
 /// Generates new KeyPair.
 pub fn generate_key_pair() -> KeyPair {
-    let rng = rand::SystemRandom::new();
-    let private_key = agreement::EphemeralPrivateKey::generate(&agreement::X25519, &rng).unwrap();
-
-    let mut public_key = [0u8; agreement::PUBLIC_KEY_MAX_LEN];
-    let public_key = &mut public_key[..private_key.public_key_len()];
-    let res = private_key.compute_public_key(public_key);
-    if res.is_err() {
-        panic!("agreement::generate_key_pair: failed to compute public key");
-    }
+    let secret = group::random_scalar();
 
     KeyPair {
-        secret: private_key,
-        public: public_key.to_vec(),
+        secret,
+        public: &secret * &RISTRETTO_BASEPOINT_TABLE,
     }
 }
 
-/// Generates new EphemeralKeys.
-pub fn generate_ephemeral(
-    peer_public: &[u8],
-    secret_key: agreement::EphemeralPrivateKey,
-) -> EphemeralKeys {
-    let mut public_key = [0u8; agreement::PUBLIC_KEY_MAX_LEN];
-    let public_key = &mut public_key[..secret_key.public_key_len()];
-    let res = secret_key.compute_public_key(public_key);
-    if res.is_err() {
-        panic!("agreement::generate_ephemeral: failed to compute public key");
-    }
-
-    let peer_key = untrusted::Input::from(peer_public);
-    let key_alg = &agreement::X25519;
-
-    let res =
-        agreement::agree_ephemeral(secret_key, key_alg, peer_key, error::Unspecified, |kdf| {
-            Ok(kdf.to_vec())
-        }).unwrap();
+/// Computes shared key from own secret key and peer public key.
+///
+/// Both sides get the same key: `agree(a, B) == agree(b, A)`.
+pub fn agree(secret: &Scalar, peer_public: &RistrettoPoint) -> Vec<u8> {
+    derive_key(&(secret * peer_public))
+}
 
-    EphemeralKeys {
-        public_out_key: public_key.to_vec(),
-        ephemeral_key: res,
-    }
+/// Derives symmetric key from the shared group element.
+///
+/// The shared element may also be reconstructed from decryption shares,
+/// see `threshold::combine`.
+pub fn derive_key(shared: &RistrettoPoint) -> Vec<u8> {
+    crypto::hash(shared.compress().as_bytes()).as_ref().to_vec()
 }
@@ -1,17 +1,24 @@
-use agreement;
-use cipher;
 use exonum::{
     api::{self, ServiceApiBuilder, ServiceApiState},
-    blockchain::{Schema, Transaction},
-    crypto::{Hash, PublicKey},
+    blockchain::{BlockProof, Schema, Transaction},
+    crypto::{self, Hash, PublicKey},
+    helpers::Height,
     messages::Message,
     node::TransactionSend,
+    storage::MapProof,
 };
 use schema::{
-    Candidate, CandidateResult, DecryptedCandidateResult, EncryptedVote, VoteResult,
-    VoteServiceSchema, Voter,
+    ApprovalResult, Ballot, Candidate, Ciphertext, DecryptedCandidateResult, DecryptionShare,
+    DelegatedWeight, Election, ElectionPhase, EncryptedVote, Mix, PairwiseResult,
+    ReferendumResult, RejectedVote, ReplacedVote, Round, ScoreResult, StvRound, TokenIssuance,
+    VoteServiceSchema, Voter, WeightChange, ELECTION_HASHES_TABLE_INDEX,
 };
-use transactions::{TxAddVote, VoteTransactions};
+use transactions::{
+    TxAddAnonymousVote, TxAddApprovalVote, TxAddBallot, TxAddMixVote, TxAddRankedVote,
+    TxAddReferendumVote, TxAddRingVote, TxAddScoreVote, TxAddVote, TxCommitVote,
+    VoteTransactions,
+};
+use SERVICE_ID;
 
 /// REST API.
 
@@ -19,24 +26,49 @@ use transactions::{TxAddVote, VoteTransactions};
 #[derive(Debug, Clone)]
 pub struct VoteServiceApi;
 
+/// The structure describes the query parameters for the endpoints
+/// which return data of the whole election.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct ElectionQuery {
+    pub election_id: u64,
+}
+
 /// The structure describes the query parameters for the `get_candidate` endpoint.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct CandidateQuery {
+    pub election_id: u64,
     pub pub_key: PublicKey,
 }
 
 /// The structure describes the query parameters for the `get_voter` endpoint.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct VoterQuery {
+    pub election_id: u64,
     pub pub_key: PublicKey,
 }
 
 /// The structure describes the query parameters for the `get_block` endpoint.
 #[derive(Debug, Serialize, Deserialize, Clone, Copy)]
 pub struct BlockQuery {
+    pub election_id: u64,
     pub pub_key: PublicKey,
 }
 
+/// Proof of the encrypted vote inclusion into the blockchain state.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoteProof {
+    /// Latest block and precommits of the validators for it.
+    pub block_proof: BlockProof,
+    /// Proof of the election hashes table root in the block state hash.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Proof of the election hash in the election hashes table.
+    pub to_election: MapProof<Hash, Hash>,
+    /// Root hashes of the election tables, see `VoteServiceSchema::election_state`.
+    pub election_state: Vec<Hash>,
+    /// Proof of the vote (or its absence) in the votes table of the election.
+    pub to_vote: MapProof<Hash, EncryptedVote>,
+}
+
 /// The structure returned by REST API.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionResponse {
@@ -45,20 +77,54 @@ pub struct TransactionResponse {
 
 /// REST API implementation.
 impl VoteServiceApi {
+    /// Endpoint for getting an election.
+    pub fn get_election(state: &ServiceApiState, query: ElectionQuery) -> api::Result<Election> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        schema
+            .election(query.election_id)
+            .ok_or_else(|| api::Error::NotFound("Election not found".to_string()))
+    }
+
+    /// Endpoint for getting the current phase of an election.
+    pub fn get_election_phase(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<ElectionPhase> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let election = schema
+            .election(query.election_id)
+            .ok_or_else(|| api::Error::NotFound("Election not found".to_string()))?;
+        Ok(election.phase(schema.current_height()))
+    }
+
+    /// Endpoint for getting all elections.
+    pub fn get_elections(state: &ServiceApiState, _query: ()) -> api::Result<Vec<Election>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.elections();
+        let elections = idx.values().collect();
+        Ok(elections)
+    }
+
     /// Endpoint for getting a candidate.
     pub fn get_candidate(state: &ServiceApiState, query: CandidateQuery) -> api::Result<Candidate> {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
         schema
-            .candidate(&query.pub_key)
+            .candidate(query.election_id, &query.pub_key)
             .ok_or_else(|| api::Error::NotFound("Candidate not found".to_string()))
     }
 
-    /// Endpoint for getting all candidates.
-    pub fn get_candidates(state: &ServiceApiState, _query: ()) -> api::Result<Vec<Candidate>> {
+    /// Endpoint for getting all candidates of the election.
+    pub fn get_candidates(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<Candidate>> {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
-        let idx = schema.candidates();
+        let idx = schema.candidates(query.election_id);
         let candidates = idx.values().collect();
         Ok(candidates)
     }
@@ -68,71 +134,315 @@ impl VoteServiceApi {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
         schema
-            .voter(&query.pub_key)
+            .voter(query.election_id, &query.pub_key)
             .ok_or_else(|| api::Error::NotFound("Voter not found".to_string()))
     }
 
-    /// Endpoint for getting all voters.
-    pub fn get_voters(state: &ServiceApiState, _query: ()) -> api::Result<Vec<Voter>> {
+    /// Endpoint for getting all voters of the election.
+    pub fn get_voters(state: &ServiceApiState, query: ElectionQuery) -> api::Result<Vec<Voter>> {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
-        let idx = schema.voters();
+        let idx = schema.voters(query.election_id);
         let voters = idx.values().collect();
         Ok(voters)
     }
 
-    /// Endpoint for getting all encrypted votes.
-    pub fn get_votes(state: &ServiceApiState, _query: ()) -> api::Result<Vec<EncryptedVote>> {
+    /// Endpoint for getting all voters of the election, whose registration was revoked.
+    pub fn get_revoked_voters(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<Voter>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.revoked_voters(query.election_id);
+        let voters = idx.values().collect();
+        Ok(voters)
+    }
+
+    /// Endpoint for getting all changes of voter weights in the election.
+    pub fn get_weight_changes(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<WeightChange>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.weight_changes(query.election_id);
+        let changes = idx.iter().collect();
+        Ok(changes)
+    }
+
+    /// Endpoint for getting all votes of the election, which were transferred to proxies,
+    /// as pairs of the voter and the proxy.
+    pub fn get_vote_proxies(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<(PublicKey, PublicKey)>> {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
-        let idx = schema.votes();
+        let idx = schema.vote_delegations(query.election_id);
+        let delegations = idx.iter().collect();
+        Ok(delegations)
+    }
+
+    /// Endpoint for getting the voting token issuance of a voter in the anonymous election.
+    ///
+    /// The voter reads the commitment of the token issuer and then the response from it.
+    pub fn get_token_issuance(
+        state: &ServiceApiState,
+        query: VoterQuery,
+    ) -> api::Result<TokenIssuance> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        schema
+            .token_issuance(query.election_id, &query.pub_key)
+            .ok_or_else(|| api::Error::NotFound("Token issuance not found".to_string()))
+    }
+
+    /// Endpoint for getting all encrypted votes of the election.
+    pub fn get_votes(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<EncryptedVote>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.votes(query.election_id);
         let votes = idx.values().collect();
         Ok(votes)
     }
 
-    /// Endpoint for getting all encrypted vote results and service public key.
-    pub fn get_results(state: &ServiceApiState, _query: ()) -> api::Result<VoteResult> {
-        let service_public_key = agreement::get_ephemeral().public_out_key;
-        let service_public_key = match PublicKey::from_slice(&service_public_key) {
-            Some(val) => val,
-            None => panic!("api::get_results: failed to get ephemeral key"),
-        };
+    /// Endpoint for getting all votes of the election, which were replaced by re-voting.
+    pub fn get_replaced_votes(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<ReplacedVote>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.replaced_votes(query.election_id);
+        let votes = idx.iter().collect();
+        Ok(votes)
+    }
+
+    /// Endpoint for getting all votes of the election, which were not counted on tally.
+    pub fn get_rejected_votes(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<RejectedVote>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.rejected_votes(query.election_id);
+        let votes = idx.iter().collect();
+        Ok(votes)
+    }
 
+    /// Endpoint for getting all ballots of the homomorphic election.
+    pub fn get_ballots(state: &ServiceApiState, query: ElectionQuery) -> api::Result<Vec<Ballot>> {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
-        let idx = schema.vote_results();
-        let candidates = idx.values().collect();
-        let results = VoteResult::new(&service_public_key, candidates);
+        let idx = schema.ballots(query.election_id);
+        let ballots = idx.values().collect();
+        Ok(ballots)
+    }
 
-        Ok(results)
+    /// Endpoint for getting encrypted totals of candidates of the homomorphic election,
+    /// in order of the candidates table.
+    pub fn get_encrypted_totals(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<Ciphertext>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.encrypted_totals(query.election_id);
+        let totals = idx.values().collect();
+        Ok(totals)
     }
 
-    /// Endpoint for getting all decrypted vote results.
+    /// Endpoint for getting all votes of the mixnet election, as they were cast.
+    pub fn get_mix_votes(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<Ciphertext>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.mix_votes(query.election_id);
+        let votes = idx.values().collect();
+        Ok(votes)
+    }
+
+    /// Endpoint for getting all shuffles of votes of the mixnet election, in order of stages.
+    /// Shuffle proofs can be checked offline with `mixnet::verify_shuffle`.
+    pub fn get_mixes(state: &ServiceApiState, query: ElectionQuery) -> api::Result<Vec<Mix>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.mixes(query.election_id);
+        let mixes = idx.iter().collect();
+        Ok(mixes)
+    }
+
+    /// Endpoint for getting the vote commitment of a voter in the commit-reveal election.
+    pub fn get_commitment(state: &ServiceApiState, query: VoterQuery) -> api::Result<Hash> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        schema
+            .commitment(query.election_id, &query.pub_key)
+            .ok_or_else(|| api::Error::NotFound("Commitment not found".to_string()))
+    }
+
+    /// Endpoint for getting ids of the voters of the commit-reveal election,
+    /// which committed to their votes but did not reveal them.
+    pub fn get_unrevealed(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<PublicKey>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        Ok(schema.unrevealed(query.election_id))
+    }
+
+    /// Endpoint for getting an encrypted vote of the voter with the proof of its inclusion
+    /// into the blockchain state. The proof can be checked with `proofs::verify_vote_proof`.
+    pub fn get_vote_proof(state: &ServiceApiState, query: VoterQuery) -> api::Result<VoteProof> {
+        let snapshot = state.snapshot();
+        let general_schema = Schema::new(&snapshot);
+        let schema = VoteServiceSchema::new(&snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .ok_or_else(|| api::Error::NotFound("Block not found".to_string()))?;
+
+        let to_table =
+            general_schema.get_proof_to_service_table(SERVICE_ID, ELECTION_HASHES_TABLE_INDEX);
+        let to_election = schema
+            .election_hashes()
+            .get_proof(crypto::CryptoHash::hash(&query.election_id));
+        let election_state = schema.election_state(query.election_id);
+        let to_vote = schema
+            .votes(query.election_id)
+            .get_proof(crypto::CryptoHash::hash(&query.pub_key));
+
+        Ok(VoteProof {
+            block_proof,
+            to_table,
+            to_election,
+            election_state,
+            to_vote,
+        })
+    }
+
+    /// Endpoint for getting all decryption shares published by validators for the election.
+    pub fn get_decryption_shares(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<DecryptionShare>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.decryption_shares(query.election_id);
+        let shares = idx.values().collect();
+        Ok(shares)
+    }
+
+    /// Endpoint for getting all decrypted vote results of the election.
+    ///
+    /// Results are available only after the election is tallied, i.e. once
+    /// threshold of validators published decryption shares.
     pub fn get_results_decrypted(
         state: &ServiceApiState,
-        _query: (),
+        query: ElectionQuery,
     ) -> api::Result<Vec<DecryptedCandidateResult>> {
         let snapshot = state.snapshot();
         let schema = VoteServiceSchema::new(snapshot);
-        let idx = schema.vote_results();
-        let results: Vec<CandidateResult> = idx.values().collect();
+        let election = schema
+            .election(query.election_id)
+            .ok_or_else(|| api::Error::NotFound("Election not found".to_string()))?;
+        if election.phase(schema.current_height()) != ElectionPhase::Tallied {
+            Err(api::Error::BadRequest("Election is not tallied".to_string()))?
+        }
 
-        let mut dec_results = vec![];
-        for res in results.iter() {
-            let mut dec_res_votes = vec![];
+        let idx = schema.decrypted_results(query.election_id);
+        let results = idx.values().collect();
+        Ok(results)
+    }
 
-            for vote in res.votes().iter() {
-                let dec_vote = cipher::decrypt_vote(vote);
-                dec_res_votes.push(dec_vote);
-            }
+    /// Endpoint for getting instant-runoff rounds of the tallied ranked election.
+    pub fn get_rounds(state: &ServiceApiState, query: ElectionQuery) -> api::Result<Vec<Round>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.rounds(query.election_id);
+        let rounds = idx.iter().collect();
+        Ok(rounds)
+    }
 
-            let votes_num = dec_res_votes.len() as u64;
-            let mut dec_res =
-                DecryptedCandidateResult::new(res.candidate(), dec_res_votes, votes_num);
-            dec_results.push(dec_res);
-        }
+    /// Endpoint for getting single transferable vote rounds of the tallied election.
+    pub fn get_stv_rounds(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<StvRound>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.stv_rounds(query.election_id);
+        let rounds = idx.iter().collect();
+        Ok(rounds)
+    }
 
-        Ok(dec_results)
+    /// Endpoint for getting pairwise preferences matrix of the tallied ranked election
+    /// together with its Schulze and ranked pairs winners.
+    pub fn get_pairwise_results(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<PairwiseResult>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.pairwise_results(query.election_id);
+        let results = idx.values().collect();
+        Ok(results)
+    }
+
+    /// Endpoint for getting result of the tallied referendum.
+    pub fn get_referendum_result(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<ReferendumResult> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        schema
+            .referendum_result(query.election_id)
+            .ok_or_else(|| api::Error::NotFound("Referendum result not found".to_string()))
+    }
+
+    /// Endpoint for getting approvals of candidates of the tallied approval election.
+    pub fn get_approval_results(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<ApprovalResult>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.approval_results(query.election_id);
+        let results = idx.values().collect();
+        Ok(results)
+    }
+
+    /// Endpoint for getting weights delegated to voters of the tallied election.
+    pub fn get_delegated_weights(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<DelegatedWeight>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.delegated_weights(query.election_id);
+        let weights = idx.values().collect();
+        Ok(weights)
+    }
+
+    /// Endpoint for getting scores of candidates of the tallied score election.
+    pub fn get_score_results(
+        state: &ServiceApiState,
+        query: ElectionQuery,
+    ) -> api::Result<Vec<ScoreResult>> {
+        let snapshot = state.snapshot();
+        let schema = VoteServiceSchema::new(snapshot);
+        let idx = schema.score_results(query.election_id);
+        let results = idx.values().collect();
+        Ok(results)
     }
 
     /// Endpoint for getting a block height.
@@ -142,14 +452,34 @@ impl VoteServiceApi {
 
         let transactions = ex_schema.transactions();
         for raw_mes in transactions.values() {
-            let parsed = TxAddVote::from_raw(raw_mes.clone());
-            if parsed.is_ok() {
-                let mes = parsed.unwrap();
-                if mes.voter_id().clone() == query.pub_key {
-                    let locations = ex_schema.transactions_locations();
-                    let loc = locations.get(&raw_mes.hash()).unwrap();
-                    return Ok(loc.block_height().0);
-                }
+            let voter = if let Ok(mes) = TxAddVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxAddBallot::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxAddAnonymousVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.pub_key()))
+            } else if let Ok(mes) = TxAddRingVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.pub_key()))
+            } else if let Ok(mes) = TxAddMixVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxCommitVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxAddRankedVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxAddApprovalVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxAddScoreVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else if let Ok(mes) = TxAddReferendumVote::from_raw(raw_mes.clone()) {
+                Some((mes.election_id(), *mes.voter_id()))
+            } else {
+                None
+            };
+
+            if voter == Some((query.election_id, query.pub_key)) {
+                let locations = ex_schema.transactions_locations();
+                let loc = locations.get(&raw_mes.hash()).unwrap();
+                return Ok(loc.block_height().0);
             }
         }
 
@@ -173,16 +503,62 @@ impl VoteServiceApi {
     pub fn wire(builder: &mut ServiceApiBuilder) {
         builder
             .public_scope()
+            .endpoint("v1/election", Self::get_election)
+            .endpoint("v1/election/phase", Self::get_election_phase)
+            .endpoint("v1/elections", Self::get_elections)
             .endpoint("v1/candidate", Self::get_candidate)
             .endpoint("v1/candidates", Self::get_candidates)
             .endpoint("v1/voter", Self::get_voter)
             .endpoint("v1/voters", Self::get_voters)
+            .endpoint("v1/voters/weight_changes", Self::get_weight_changes)
+            .endpoint("v1/voters/revoked", Self::get_revoked_voters)
+            .endpoint("v1/voters/proxies", Self::get_vote_proxies)
+            .endpoint("v1/token", Self::get_token_issuance)
             .endpoint("v1/votes", Self::get_votes)
-            .endpoint("v1/results", Self::get_results)
+            .endpoint("v1/votes/proof", Self::get_vote_proof)
+            .endpoint("v1/votes/replaced", Self::get_replaced_votes)
+            .endpoint("v1/votes/rejected", Self::get_rejected_votes)
+            .endpoint("v1/ballots", Self::get_ballots)
+            .endpoint("v1/totals", Self::get_encrypted_totals)
+            .endpoint("v1/mix_votes", Self::get_mix_votes)
+            .endpoint("v1/mixes", Self::get_mixes)
+            .endpoint("v1/commitment", Self::get_commitment)
+            .endpoint("v1/unrevealed", Self::get_unrevealed)
             .endpoint("v1/results_dec", Self::get_results_decrypted)
+            .endpoint("v1/decryption_shares", Self::get_decryption_shares)
+            .endpoint("v1/results/rounds", Self::get_rounds)
+            .endpoint("v1/results/stv", Self::get_stv_rounds)
+            .endpoint("v1/results/pairwise", Self::get_pairwise_results)
+            .endpoint("v1/results/approval", Self::get_approval_results)
+            .endpoint("v1/results/score", Self::get_score_results)
+            .endpoint("v1/results/referendum", Self::get_referendum_result)
+            .endpoint("v1/results/delegated", Self::get_delegated_weights)
             .endpoint("v1/block", Self::get_block)
+            .endpoint_mut("v1/elections", Self::post_transaction)
+            .endpoint_mut("v1/elections/tally", Self::post_transaction)
+            .endpoint_mut("v1/decryption_shares", Self::post_transaction)
             .endpoint_mut("v1/candidates", Self::post_transaction)
+            .endpoint_mut("v1/candidates/withdraw", Self::post_transaction)
+            .endpoint_mut("v1/candidates/info", Self::post_transaction)
             .endpoint_mut("v1/voters", Self::post_transaction)
-            .endpoint_mut("v1/votes", Self::post_transaction);
+            .endpoint_mut("v1/voters/weight", Self::post_transaction)
+            .endpoint_mut("v1/voters/revoke", Self::post_transaction)
+            .endpoint_mut("v1/voters/delegate", Self::post_transaction)
+            .endpoint_mut("v1/voters/proxy", Self::post_transaction)
+            .endpoint_mut("v1/tokens/commitment", Self::post_transaction)
+            .endpoint_mut("v1/tokens/request", Self::post_transaction)
+            .endpoint_mut("v1/tokens/issue", Self::post_transaction)
+            .endpoint_mut("v1/votes", Self::post_transaction)
+            .endpoint_mut("v1/votes/anonymous", Self::post_transaction)
+            .endpoint_mut("v1/votes/ring", Self::post_transaction)
+            .endpoint_mut("v1/votes/mix", Self::post_transaction)
+            .endpoint_mut("v1/mixes", Self::post_transaction)
+            .endpoint_mut("v1/votes/commit", Self::post_transaction)
+            .endpoint_mut("v1/votes/reveal", Self::post_transaction)
+            .endpoint_mut("v1/votes/ranked", Self::post_transaction)
+            .endpoint_mut("v1/votes/approval", Self::post_transaction)
+            .endpoint_mut("v1/votes/score", Self::post_transaction)
+            .endpoint_mut("v1/votes/referendum", Self::post_transaction)
+            .endpoint_mut("v1/ballots", Self::post_transaction);
     }
 }
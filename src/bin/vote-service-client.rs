@@ -0,0 +1,12 @@
+extern crate exonum;
+extern crate vote_service;
+
+use exonum::helpers::fabric::{ClapBackend, CollectedCommand};
+use vote_service::cmd::ImportRoster;
+
+/// Client commands, which work with the public API of a running node.
+fn main() {
+    exonum::helpers::init_logger().unwrap();
+
+    ClapBackend::execute(&[CollectedCommand::new(Box::new(ImportRoster))]);
+}
@@ -0,0 +1,170 @@
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use exonum::crypto::{CryptoHash, Hash, PublicKey};
+use group;
+use sha2::Sha512;
+
+/// Blind Schnorr signatures, used to issue anonymous voting tokens.
+///
+/// The token issuer publishes a commitment `R = k * G` for a registered voter.
+/// The voter blinds it as `R' = R + a * G + b * X`, where `X` is the issuer key,
+/// and sends the blinded challenge `c = H(X, R', m) + b`, where `m` is the one-time key
+/// the vote will be cast from. The issuer responds with `s = k + c * x`, and the voter
+/// unblinds it as `s' = s + a`. The token `(R', s')` satisfies `s' * G == R' + H(X, R', m) * X`,
+/// while the issuer can not link it to the request it has signed.
+///
+/// Blind Schnorr signatures are not secure with concurrent signing sessions: a voter
+/// who gets several commitments before sending challenges can forge an extra token
+/// (the ROS attack). The contract therefore lets the issuer have only one open session
+/// per election, see `TokenIssuance::is_open`.
+
+/// Domain separation tag of token challenges.
+const TOKEN_TAG: &[u8] = b"voteservice.token";
+
+/// Secret state of the voter between requesting a token and unblinding it.
+#[derive(Clone, Debug)]
+pub struct TokenBlinding {
+    /// Blinded commitment `R'`, it becomes a part of the token.
+    pub commitment: RistrettoPoint,
+    /// Blinding factor of the response.
+    pub alpha: Scalar,
+}
+
+/// Unblinded voting token, ready to be sent in `TxAddAnonymousVote`.
+#[derive(Clone, Debug)]
+pub struct Token {
+    /// Blinded commitment `R'`.
+    pub commitment: PublicKey,
+    /// Unblinded response `s'`.
+    pub response: Hash,
+}
+
+/// Returns public key of the token issuer with the specified secret key.
+pub fn issuer_key(issuer_secret: &Scalar) -> PublicKey {
+    group::point_to_key(&(issuer_secret * &RISTRETTO_BASEPOINT_TABLE))
+}
+
+/// Computes commitment of the token issuer for the voter.
+///
+/// Runs on the issuer side.
+pub fn commit(issuer_secret: &Scalar, election_id: u64, voter_id: &PublicKey) -> PublicKey {
+    let nonce = nonce(issuer_secret, election_id, voter_id);
+    group::point_to_key(&(&nonce * &RISTRETTO_BASEPOINT_TABLE))
+}
+
+/// Signs the blinded challenge of the voter.
+///
+/// Runs on the issuer side. The nonce of the commitment is derived deterministically,
+/// so the issuer must sign only the challenge stored on the blockchain: signing two
+/// different challenges for the same voter reveals the issuer secret key.
+///
+/// Returns `None` if the challenge is not a canonical scalar.
+pub fn respond(
+    issuer_secret: &Scalar,
+    election_id: u64,
+    voter_id: &PublicKey,
+    challenge: &Hash,
+) -> Option<Hash> {
+    let challenge = group::hash_to_scalar(challenge)?;
+    let nonce = nonce(issuer_secret, election_id, voter_id);
+    Some(group::scalar_to_hash(&(nonce + challenge * issuer_secret)))
+}
+
+/// Checks the response of the issuer to the blinded challenge.
+pub fn verify_response(
+    issuer_key: &RistrettoPoint,
+    commitment: &PublicKey,
+    challenge: &Hash,
+    response: &Hash,
+) -> bool {
+    let values = (
+        group::key_to_point(commitment),
+        group::hash_to_scalar(challenge),
+        group::hash_to_scalar(response),
+    );
+    match values {
+        (Some(commitment), Some(challenge), Some(response)) => {
+            &response * &RISTRETTO_BASEPOINT_TABLE == commitment + challenge * issuer_key
+        }
+        _ => false,
+    }
+}
+
+/// Blinds the commitment of the issuer for a token bound to the one-time key of the vote.
+///
+/// Runs on the client side. Returns the blinding, which must be kept secret until
+/// the token is unblinded, and the blinded challenge to be sent in `TxRequestToken`,
+/// or `None` if the keys are not valid group elements.
+pub fn blind(
+    issuer_key: &PublicKey,
+    commitment: &PublicKey,
+    election_id: u64,
+    ballot_key: &PublicKey,
+) -> Option<(TokenBlinding, Hash)> {
+    let issuer_key = group::key_to_point(issuer_key)?;
+    let commitment = group::key_to_point(commitment)?;
+
+    let alpha = group::random_scalar();
+    let beta = group::random_scalar();
+    let blinded = commitment + &alpha * &RISTRETTO_BASEPOINT_TABLE + beta * issuer_key;
+    let challenge = token_challenge(&issuer_key, &blinded, election_id, ballot_key) + beta;
+
+    let blinding = TokenBlinding {
+        commitment: blinded,
+        alpha,
+    };
+    Some((blinding, group::scalar_to_hash(&challenge)))
+}
+
+/// Unblinds the response of the issuer.
+///
+/// Runs on the client side. Returns `None` if the response is not a canonical scalar.
+pub fn unblind(blinding: &TokenBlinding, response: &Hash) -> Option<Token> {
+    let response = group::hash_to_scalar(response)? + blinding.alpha;
+    Some(Token {
+        commitment: group::point_to_key(&blinding.commitment),
+        response: group::scalar_to_hash(&response),
+    })
+}
+
+/// Checks the token presented with the vote cast from the one-time key.
+pub fn verify_token(
+    issuer_key: &RistrettoPoint,
+    election_id: u64,
+    ballot_key: &PublicKey,
+    commitment: &PublicKey,
+    response: &Hash,
+) -> bool {
+    let (commitment, response) = match (
+        group::key_to_point(commitment),
+        group::hash_to_scalar(response),
+    ) {
+        (Some(commitment), Some(response)) => (commitment, response),
+        _ => return false,
+    };
+
+    let challenge = token_challenge(issuer_key, &commitment, election_id, ballot_key);
+    &response * &RISTRETTO_BASEPOINT_TABLE == commitment + challenge * issuer_key
+}
+
+/// Computes challenge of the token bound to the election and the one-time key of the vote.
+fn token_challenge(
+    issuer_key: &RistrettoPoint,
+    commitment: &RistrettoPoint,
+    election_id: u64,
+    ballot_key: &PublicKey,
+) -> Scalar {
+    let mut tag = TOKEN_TAG.to_vec();
+    tag.extend_from_slice(election_id.hash().as_ref());
+    tag.extend_from_slice(ballot_key.as_ref());
+    group::challenge(&tag, &[issuer_key, commitment])
+}
+
+/// Derives nonce of the issuer commitment for the voter.
+fn nonce(issuer_secret: &Scalar, election_id: u64, voter_id: &PublicKey) -> Scalar {
+    let mut bytes = issuer_secret.to_bytes().to_vec();
+    bytes.extend_from_slice(election_id.hash().as_ref());
+    bytes.extend_from_slice(voter_id.as_ref());
+    Scalar::hash_from_bytes::<Sha512>(&bytes)
+}
@@ -1,39 +1,171 @@
 use agreement;
 use byteorder::{ByteOrder, LittleEndian};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use exonum::crypto::PublicKey;
 use exonum::storage::StorageValue;
+use group;
 use ring::aead;
-use schema::{EncryptedVote, Vote};
+use schema::{ApprovalVote, EncryptedVote, RankedVote, ReferendumVote, ScoreVote, Vote};
 use std::borrow::Cow;
 
-/// Encrypts vote with service ephemeral key.
-pub fn encrypt_vote(vote: &Vote) -> EncryptedVote {
-    let key = agreement::get_ephemeral().ephemeral_key;
+/// Encrypts vote against the election public key.
+///
+/// Runs on the client side: a one-time key pair is generated for every vote,
+/// and its public part is stored in `EncryptedVote` to allow decryption.
+pub fn encrypt_vote(vote: &Vote, election_key: &PublicKey) -> EncryptedVote {
+    encrypt(&vote.clone().into_bytes(), election_key)
+}
+
+/// Encrypts vote against the election public key with the specified one-time key pair,
+/// which is used by the proof of the vote choice, see `zkp::encrypt_vote`.
+pub fn encrypt_vote_with_key(
+    vote: &Vote,
+    key_pair: &agreement::KeyPair,
+    election_key: &RistrettoPoint,
+) -> EncryptedVote {
+    encrypt_with_key(&vote.clone().into_bytes(), key_pair, election_key)
+}
+
+/// Decrypts vote with the shared element: one-time key of the vote
+/// multiplied by the election secret key.
+///
+/// The shared element is reconstructed from decryption shares of validators,
+/// see `threshold::combine`. Returns `None` if it does not match the vote
+/// or the decrypted vote is malformed.
+///
+/// The plaintext is made by the voter, so its layout is checked before it is
+/// deserialized: `from_bytes` panics on segments out of the buffer.
+pub fn decrypt_vote(vote: &EncryptedVote, shared: &RistrettoPoint) -> Option<Vote> {
+    let raw = decrypt(vote, shared)?;
+    if raw.len() < 2 * KEY_SIZE {
+        return None;
+    }
+    Some(Vote::from_bytes(Cow::Owned(raw)))
+}
+
+/// Encrypts ranked vote against the election public key, see `encrypt_vote`.
+pub fn encrypt_ranked_vote(vote: &RankedVote, election_key: &PublicKey) -> EncryptedVote {
+    encrypt(&vote.clone().into_bytes(), election_key)
+}
+
+/// Decrypts ranked vote with the shared element, see `decrypt_vote`.
+pub fn decrypt_ranked_vote(vote: &EncryptedVote, shared: &RistrettoPoint) -> Option<RankedVote> {
+    let raw = decrypt(vote, shared)?;
+    segment(&raw, KEY_SIZE, KEY_SIZE)?;
+    Some(RankedVote::from_bytes(Cow::Owned(raw)))
+}
+
+/// Encrypts approval vote against the election public key, see `encrypt_vote`.
+pub fn encrypt_approval_vote(vote: &ApprovalVote, election_key: &PublicKey) -> EncryptedVote {
+    encrypt(&vote.clone().into_bytes(), election_key)
+}
+
+/// Decrypts approval vote with the shared element, see `decrypt_vote`.
+pub fn decrypt_approval_vote(
+    vote: &EncryptedVote,
+    shared: &RistrettoPoint,
+) -> Option<ApprovalVote> {
+    let raw = decrypt(vote, shared)?;
+    segment(&raw, KEY_SIZE, KEY_SIZE)?;
+    Some(ApprovalVote::from_bytes(Cow::Owned(raw)))
+}
+
+/// Encrypts score vote against the election public key, see `encrypt_vote`.
+pub fn encrypt_score_vote(vote: &ScoreVote, election_key: &PublicKey) -> EncryptedVote {
+    encrypt(&vote.clone().into_bytes(), election_key)
+}
+
+/// Decrypts score vote with the shared element, see `decrypt_vote`.
+pub fn decrypt_score_vote(vote: &EncryptedVote, shared: &RistrettoPoint) -> Option<ScoreVote> {
+    let raw = decrypt(vote, shared)?;
+    let (offset, count) = segment(&raw, KEY_SIZE, SEGMENT_SIZE)?;
+    for idx in 0..count {
+        // Every score is a nested struct referenced by its own segment.
+        let (_, len) = segment(&raw, offset + idx * SEGMENT_SIZE, 1)?;
+        if len < KEY_SIZE + 1 {
+            return None;
+        }
+    }
+    Some(ScoreVote::from_bytes(Cow::Owned(raw)))
+}
+
+/// Encrypts referendum vote against the election public key, see `encrypt_vote`.
+pub fn encrypt_referendum_vote(vote: &ReferendumVote, election_key: &PublicKey) -> EncryptedVote {
+    encrypt(&vote.clone().into_bytes(), election_key)
+}
+
+/// Decrypts referendum vote with the shared element, see `decrypt_vote`.
+pub fn decrypt_referendum_vote(
+    vote: &EncryptedVote,
+    shared: &RistrettoPoint,
+) -> Option<ReferendumVote> {
+    let raw = decrypt(vote, shared)?;
+    if raw.len() < KEY_SIZE + 1 {
+        return None;
+    }
+    Some(ReferendumVote::from_bytes(Cow::Owned(raw)))
+}
+
+/// Size of a serialized key.
+const KEY_SIZE: usize = 32;
+
+/// Size of a serialized segment: `u32` offset from the start of the struct
+/// and `u32` number of items.
+const SEGMENT_SIZE: usize = 8;
+
+/// Reads the segment at the specified position of the serialized struct and checks
+/// that its items of the specified size are within the struct.
+///
+/// Returns offset and number of the items, or `None` if the segment is out of the struct.
+fn segment(raw: &[u8], pos: usize, item_size: usize) -> Option<(usize, usize)> {
+    let bytes = raw.get(pos..pos + SEGMENT_SIZE)?;
+    let offset = LittleEndian::read_u32(&bytes[..4]) as usize;
+    let count = LittleEndian::read_u32(&bytes[4..]) as usize;
+    let end = count.checked_mul(item_size)?.checked_add(offset)?;
+    if end > raw.len() {
+        return None;
+    }
+    Some((offset, count))
+}
+
+/// Encrypts serialized vote with a key agreed with the election public key.
+fn encrypt(raw: &[u8], election_key: &PublicKey) -> EncryptedVote {
+    let election_key =
+        group::key_to_point(election_key).expect("cipher::encrypt_vote: invalid election key");
+    encrypt_with_key(raw, &agreement::generate_key_pair(), &election_key)
+}
+
+/// Encrypts serialized vote with a key agreed between the one-time key pair
+/// and the election public key.
+fn encrypt_with_key(
+    raw: &[u8],
+    key_pair: &agreement::KeyPair,
+    election_key: &RistrettoPoint,
+) -> EncryptedVote {
+    let key = agreement::agree(&key_pair.secret, election_key);
     let mut enc = CipherChaChaPoly::default();
     enc.set(&key);
 
-    let raw = vote.clone().into_bytes();
-    let mut res = [0u8; 128];
-    let enc_size = enc.encrypt(0, &[], &raw, &mut res);
-    let res = &res[..enc_size];
+    let mut res = vec![0u8; raw.len() + TAGLEN];
+    let enc_size = enc.encrypt(0, &[], raw, &mut res);
+    res.truncate(enc_size);
 
-    let enc_vote = EncryptedVote::new(res.to_vec());
-
-    enc_vote
+    let pub_key = group::point_to_key(&key_pair.public);
+    EncryptedVote::new(&pub_key, res)
 }
 
-/// Decrypts vote with service ephemeral key.
-pub fn decrypt_vote(vote: &EncryptedVote) -> Vote {
-    let key = agreement::get_ephemeral().ephemeral_key;
+/// Decrypts serialized vote with the shared element.
+fn decrypt(vote: &EncryptedVote, shared: &RistrettoPoint) -> Option<Vec<u8>> {
+    let key = agreement::derive_key(shared);
     let mut dec = CipherChaChaPoly::default();
     dec.set(&key);
 
-    let mut dec_output = [0u8; 128];
-    let dec_size = dec.decrypt(0, &[], &vote.data(), &mut dec_output).unwrap();
-    let dec_output = &dec_output[..dec_size];
-
-    let vote = Vote::from_bytes(Cow::Borrowed(dec_output));
+    let data = vote.data();
+    let mut dec_output = vec![0u8; data.len()];
+    let dec_size = dec.decrypt(0, &[], &data, &mut dec_output).ok()?;
+    dec_output.truncate(dec_size);
 
-    vote
+    Some(dec_output)
 }
 
 pub const TAGLEN: usize = 16;
@@ -1,11 +1,27 @@
 use failure;
+use reqwest::Client;
+use serde_json;
 use toml::Value;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::Duration;
 
 use config::VoteServiceConfig;
-use exonum::helpers::fabric::{keys, Argument, CommandExtension, Context};
+use errors::Error;
+use exonum::crypto::{self, Hash, PublicKey, SecretKey};
+use exonum::encoding::serialize::{encode_hex, FromHex};
+use exonum::helpers::fabric::{
+    keys, Argument, CollectedCommand, Command, CommandExtension, CommandName, Context, Feedback,
+};
+use exonum::messages::Message;
 use exonum::node::NodeConfig;
+use transactions::{TxCreateCandidate, TxCreateVoter};
 
 /// Configuration commands.
 
@@ -14,31 +30,93 @@ pub struct GenerateCommonConfig;
 /// Implementation of common config generation for service.
 impl CommandExtension for GenerateCommonConfig {
     fn args(&self) -> Vec<Argument> {
-        vec![Argument::new_named(
-            "AUTHOR_PUBLIC_KEY",
-            false,
-            "Public key of vote author",
-            None,
-            "author-public-key",
-            false,
-        )]
+        vec![
+            Argument::new_named(
+                "ELECTION_PUBLIC_KEY",
+                false,
+                "Joint election public key of validators",
+                None,
+                "election-public-key",
+                false,
+            ),
+            Argument::new_named(
+                "THRESHOLD",
+                false,
+                "Number of validators, which decryption shares are needed to tally results",
+                None,
+                "threshold",
+                false,
+            ),
+            Argument::new_named(
+                "VERIFICATION_KEYS",
+                false,
+                "Verification keys of the validators key shares, in order of validators",
+                None,
+                "verification-key",
+                true,
+            ),
+            Argument::new_named(
+                "ADMINISTRATORS",
+                false,
+                "Public keys of administrators, which manage elections, at least one",
+                None,
+                "administrator",
+                true,
+            ),
+            Argument::new_named(
+                "TOKEN_ISSUER_KEY",
+                false,
+                "Public key of the issuer of voting tokens for anonymous elections",
+                None,
+                "token-issuer-key",
+                false,
+            ),
+        ]
     }
 
     fn execute(&self, mut context: Context) -> Result<Context, failure::Error> {
-        let author_public_key = context
-            .arg::<String>("AUTHOR_PUBLIC_KEY")
-            .expect("AUTHOR_PUBLIC_KEY not found");
+        let election_public_key = context
+            .arg::<String>("ELECTION_PUBLIC_KEY")
+            .expect("ELECTION_PUBLIC_KEY not found");
+        let threshold = context.arg::<u16>("THRESHOLD").expect("THRESHOLD not found");
+        let verification_keys = context
+            .arg_multiple::<String>("VERIFICATION_KEYS")
+            .unwrap_or_default();
+        let administrators = context
+            .arg_multiple::<String>("ADMINISTRATORS")
+            .unwrap_or_default();
+        if administrators.is_empty() {
+            bail!("At least one administrator is required to create elections");
+        }
+        let token_issuer_key = context.arg::<String>("TOKEN_ISSUER_KEY").ok();
 
         let mut values: BTreeMap<String, Value> = context
             .get(keys::SERVICES_CONFIG)
             .expect("Expected services_config in context");
 
         values.extend(
-            vec![(
-                "author_public_key".to_owned(),
-                Value::try_from(author_public_key).unwrap(),
-            )].into_iter(),
+            vec![
+                (
+                    "election_public_key".to_owned(),
+                    Value::try_from(election_public_key).unwrap(),
+                ),
+                ("threshold".to_owned(), Value::try_from(threshold).unwrap()),
+                (
+                    "verification_keys".to_owned(),
+                    Value::try_from(verification_keys).unwrap(),
+                ),
+                (
+                    "administrators".to_owned(),
+                    Value::try_from(administrators).unwrap(),
+                ),
+            ].into_iter(),
         );
+        if let Some(token_issuer_key) = token_issuer_key {
+            values.insert(
+                "token_issuer_key".to_owned(),
+                Value::try_from(token_issuer_key).unwrap(),
+            );
+        }
 
         context.set(keys::SERVICES_CONFIG, values);
         Ok(context)
@@ -50,27 +128,535 @@ pub struct Finalize;
 /// Implementation of finalization for config generation.
 impl CommandExtension for Finalize {
     fn args(&self) -> Vec<Argument> {
-        vec![]
+        vec![Argument::new_named(
+            "KEY_SHARES",
+            false,
+            "Key share of the validator, allows the node to publish decryption shares",
+            None,
+            "key-share",
+            true,
+        )]
     }
 
     fn execute(&self, mut context: Context) -> Result<Context, failure::Error> {
+        let key_shares = context
+            .arg_multiple::<String>("KEY_SHARES")
+            .unwrap_or_default();
         let mut node_config: NodeConfig = context.get(keys::NODE_CONFIG).unwrap();
         let common_config = context.get(keys::COMMON_CONFIG).unwrap();
 
-        let author_public_key = if let Some(author_public_key) =
-            common_config.services_config.get("author_public_key")
+        let election_public_key = if let Some(election_public_key) =
+            common_config.services_config.get("election_public_key")
+        {
+            Value::try_into(election_public_key.clone()).unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        let threshold = if let Some(threshold) = common_config.services_config.get("threshold") {
+            Value::try_into(threshold.clone()).unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        let verification_keys = if let Some(verification_keys) =
+            common_config.services_config.get("verification_keys")
+        {
+            Value::try_into(verification_keys.clone()).unwrap_or_default()
+        } else {
+            Default::default()
+        };
+
+        let administrators: Vec<PublicKey> = if let Some(administrators) =
+            common_config.services_config.get("administrators")
+        {
+            Value::try_into(administrators.clone()).unwrap_or_default()
+        } else {
+            Default::default()
+        };
+        if administrators.is_empty() {
+            bail!("Common config has no administrators, elections could never be created");
+        }
+
+        let token_issuer_key = if let Some(token_issuer_key) =
+            common_config.services_config.get("token_issuer_key")
         {
-            Value::try_into(author_public_key.clone()).unwrap_or_default()
+            Value::try_into(token_issuer_key.clone()).unwrap_or_default()
         } else {
             Default::default()
         };
 
         node_config.services_configs.insert(
             "voteservice_service".to_owned(),
-            Value::try_from(VoteServiceConfig { author_public_key })
+            Value::try_from(VoteServiceConfig {
+                election_public_key,
+                threshold,
+                verification_keys,
+                administrators,
+                token_issuer_key,
+                key_shares,
+            })
                 .expect("Failed to serialize voteservice config"),
         );
         context.set(keys::NODE_CONFIG, node_config);
         Ok(context)
     }
 }
+
+/// Name of the roster import command.
+pub const IMPORT_ROSTER: &str = "import-roster";
+
+/// Default number of registration transactions, which are sent before waiting for them.
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Number of checks of a sent batch, one per second, before its transactions are reported
+/// as not committed.
+const COMMIT_CHECKS: usize = 60;
+
+/// Timeout in seconds of every request to the node.
+const HTTP_TIMEOUT_SECS: u64 = 10;
+
+/// Environment variable with the secret key of the election authority, which is used
+/// if the key file is not given.
+pub const SECRET_KEY_VAR: &str = "VOTESERVICE_SECRET_KEY";
+
+/// Kind of the roster: registrants and the endpoint their transactions are sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RosterKind {
+    /// Voters, registered by `TxCreateVoter`.
+    Voters,
+    /// Candidates, registered by `TxCreateCandidate`.
+    Candidates,
+}
+
+impl RosterKind {
+    /// Returns service endpoint of the registration transactions.
+    fn endpoint(self) -> &'static str {
+        match self {
+            RosterKind::Voters => "v1/voters",
+            RosterKind::Candidates => "v1/candidates",
+        }
+    }
+}
+
+/// Row of the roster.
+///
+/// `info` is used only for candidates and `weight` only for voters. Registrants without
+/// a key get a generated one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub name: String,
+    #[serde(default)]
+    pub info: String,
+    #[serde(default = "default_weight")]
+    pub weight: u64,
+    #[serde(default)]
+    pub pub_key: Option<PublicKey>,
+}
+
+fn default_weight() -> u64 {
+    1
+}
+
+/// Parses the roster in JSON format: an array of entries.
+pub fn parse_json_roster(data: &str) -> Result<Vec<RosterEntry>, failure::Error> {
+    Ok(serde_json::from_str(data)?)
+}
+
+/// Parses the roster in CSV format.
+///
+/// The first line is a header with `name` and any of `info`, `weight` and `pub_key` columns.
+/// Fields are separated by commas and can not contain them; empty `weight` and `pub_key`
+/// fields take the default weight and a generated key.
+pub fn parse_csv_roster(data: &str) -> Result<Vec<RosterEntry>, failure::Error> {
+    let mut lines = data.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = match lines.next() {
+        Some(header) => header.split(',').map(str::trim).collect(),
+        None => return Ok(vec![]),
+    };
+    let column = |name: &str| header.iter().position(|&field| field == name);
+    let name_column = column("name").ok_or_else(|| format_err!("No name column in roster"))?;
+    let (info_column, weight_column, key_column) =
+        (column("info"), column("weight"), column("pub_key"));
+
+    let mut entries = vec![];
+    for (row, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        if fields.len() != header.len() {
+            bail!("Row {}: expected {} fields", row + 1, header.len());
+        }
+
+        let field = |column: Option<usize>| column.map_or("", |pos| fields[pos]);
+        let weight = match field(weight_column) {
+            "" => default_weight(),
+            weight => weight
+                .parse()
+                .map_err(|_| format_err!("Row {}: invalid weight", row + 1))?,
+        };
+        let pub_key = match field(key_column) {
+            "" => None,
+            key => Some(
+                PublicKey::from_hex(key)
+                    .map_err(|_| format_err!("Row {}: invalid public key", row + 1))?,
+            ),
+        };
+        entries.push(RosterEntry {
+            name: fields[name_column].to_owned(),
+            info: field(info_column).to_owned(),
+            weight,
+            pub_key,
+        });
+    }
+    Ok(entries)
+}
+
+/// Imports voters or candidates of the election from a roster.
+///
+/// Registration transactions are signed with the key of the election authority and sent
+/// to the public API of the node in batches. After every batch the command waits for
+/// the transactions to be committed and reports every row as CSV: the name, public key
+/// and the status, which is `ok` or the contract error.
+///
+/// Registrants without a key in the roster get a generated key pair. Their secret keys
+/// are never printed: they are written to the new file given by `--keys-out`, which is
+/// readable only by its owner, and the import does not start without it.
+///
+/// The secret key of the authority is read from the file given by `--secret-key-file`
+/// or from `VOTESERVICE_SECRET_KEY`, so it does not get into the shell history
+/// or the process list.
+///
+/// The node builder has a fixed set of commands, so the command is run by the separate
+/// `vote-service-client` binary:
+///
+/// ```sh
+/// ./vote-service-client import-roster voters.csv --kind voters --election-id 1 \
+///     --node 127.0.0.1:8200 --public-key <authority public key> \
+///     --secret-key-file <file with authority secret key> --keys-out voter-keys.csv \
+///     [--batch-size 100]
+/// ```
+pub struct ImportRoster;
+
+impl Command for ImportRoster {
+    fn args(&self) -> Vec<Argument> {
+        vec![
+            Argument::new_positional("ROSTER", true, "Roster file, JSON or CSV"),
+            Argument::new_named(
+                "KIND",
+                true,
+                "Kind of the roster, voters or candidates",
+                None,
+                "kind",
+                false,
+            ),
+            Argument::new_named(
+                "ELECTION_ID",
+                true,
+                "Id of the election",
+                None,
+                "election-id",
+                false,
+            ),
+            Argument::new_named(
+                "NODE",
+                true,
+                "Address of the public API of the node",
+                None,
+                "node",
+                false,
+            ),
+            Argument::new_named(
+                "PUBLIC_KEY",
+                true,
+                "Public key of the election authority",
+                None,
+                "public-key",
+                false,
+            ),
+            Argument::new_named(
+                "SECRET_KEY_FILE",
+                false,
+                "File with the secret key of the election authority",
+                None,
+                "secret-key-file",
+                false,
+            ),
+            Argument::new_named(
+                "KEYS_OUT",
+                false,
+                "New file for generated secret keys of the registrants",
+                None,
+                "keys-out",
+                false,
+            ),
+            Argument::new_named(
+                "BATCH_SIZE",
+                false,
+                "Number of transactions sent before waiting for them",
+                None,
+                "batch-size",
+                false,
+            ),
+        ]
+    }
+
+    fn name(&self) -> CommandName {
+        IMPORT_ROSTER
+    }
+
+    fn about(&self) -> &str {
+        "Imports voters or candidates of the election from a roster"
+    }
+
+    fn execute(
+        &self,
+        _commands: &HashMap<CommandName, CollectedCommand>,
+        context: Context,
+        exts: &dyn Fn(Context) -> Context,
+    ) -> Feedback {
+        let context = exts(context);
+        if let Err(err) = RosterImport::from_context(&context).and_then(|import| import.run()) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        Feedback::None
+    }
+}
+
+/// Parsed arguments of `ImportRoster`.
+#[derive(Debug)]
+struct RosterImport {
+    roster: String,
+    kind: RosterKind,
+    election_id: u64,
+    node: String,
+    public_key: PublicKey,
+    secret_key: SecretKey,
+    keys_out: Option<String>,
+    batch_size: usize,
+    client: Client,
+}
+
+impl RosterImport {
+    /// Parses arguments of the command.
+    fn from_context(context: &Context) -> Result<Self, failure::Error> {
+        let kind = match context.arg::<String>("KIND")?.as_str() {
+            "voters" => RosterKind::Voters,
+            "candidates" => RosterKind::Candidates,
+            kind => bail!("Unknown roster kind {}", kind),
+        };
+        let batch_size = context
+            .arg::<usize>("BATCH_SIZE")
+            .unwrap_or(DEFAULT_BATCH_SIZE);
+        if batch_size == 0 {
+            bail!("Batch size must be positive");
+        }
+        let secret_key = match context.arg::<String>("SECRET_KEY_FILE") {
+            Ok(path) => fs::read_to_string(path)?,
+            Err(_) => env::var(SECRET_KEY_VAR).map_err(|_| {
+                format_err!("Neither --secret-key-file nor {} is set", SECRET_KEY_VAR)
+            })?,
+        };
+        let client = Client::builder()
+            .timeout(Duration::from_secs(HTTP_TIMEOUT_SECS))
+            .build()?;
+
+        Ok(RosterImport {
+            roster: context.arg("ROSTER")?,
+            kind,
+            election_id: context.arg("ELECTION_ID")?,
+            node: context.arg("NODE")?,
+            public_key: PublicKey::from_hex(context.arg::<String>("PUBLIC_KEY")?)
+                .map_err(|_| format_err!("Invalid public key"))?,
+            secret_key: SecretKey::from_hex(secret_key.trim())
+                .map_err(|_| format_err!("Invalid secret key"))?,
+            keys_out: context.arg("KEYS_OUT").ok(),
+            batch_size,
+            client,
+        })
+    }
+
+    /// Reads the roster, sends registration transactions and reports the rows.
+    fn run(&self) -> Result<(), failure::Error> {
+        let data = fs::read_to_string(&self.roster)?;
+        let is_json = Path::new(&self.roster)
+            .extension()
+            .map_or(false, |extension| extension == "json");
+        let entries = if is_json {
+            parse_json_roster(&data)?
+        } else {
+            parse_csv_roster(&data)?
+        };
+
+        let mut keys_file = match self.keys_out {
+            Some(ref path) => Some(create_keys_file(path)?),
+            None if entries.iter().any(|entry| entry.pub_key.is_none()) => {
+                bail!("Roster has rows without public keys, --keys-out is required")
+            }
+            None => None,
+        };
+        if let Some(ref mut file) = keys_file {
+            writeln!(file, "row,name,pub_key,secret_key")?;
+        }
+
+        println!("row,name,pub_key,status");
+        for (batch_num, batch) in entries.chunks(self.batch_size).enumerate() {
+            let mut sent = vec![];
+            for (pos, entry) in batch.iter().enumerate() {
+                let row = batch_num * self.batch_size + pos + 1;
+                let pub_key = match (entry.pub_key, keys_file.as_mut()) {
+                    (Some(pub_key), _) => pub_key,
+                    (None, Some(file)) => {
+                        let (public, secret) = crypto::gen_keypair();
+                        writeln!(
+                            file,
+                            "{},{},{},{}",
+                            row,
+                            entry.name,
+                            encode_hex(&public),
+                            encode_hex(&secret)
+                        )?;
+                        public
+                    }
+                    (None, None) => unreachable!("Keys file is checked above"),
+                };
+                let status = self.send(entry, &pub_key);
+                sent.push((row, entry, pub_key, status));
+            }
+            if let Some(ref mut file) = keys_file {
+                file.sync_all()?;
+            }
+
+            let tx_hashes: Vec<_> = sent.iter().map(|tx| tx.3.clone()).collect();
+            let statuses = self.wait_for_commit(&tx_hashes);
+            for ((row, entry, pub_key, _), status) in sent.iter().zip(statuses) {
+                println!(
+                    "{},{},{},{}",
+                    row,
+                    entry.name,
+                    encode_hex(pub_key),
+                    status.err().unwrap_or_else(|| "ok".to_owned())
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Signs the registration transaction of the row and sends it to the node.
+    fn send(&self, entry: &RosterEntry, pub_key: &PublicKey) -> Result<Hash, String> {
+        let url = format!(
+            "http://{}/api/services/voteservice/{}",
+            self.node,
+            self.kind.endpoint()
+        );
+        let (tx_hash, request) = match self.kind {
+            RosterKind::Voters => {
+                let tx = TxCreateVoter::new(
+                    &self.public_key,
+                    pub_key,
+                    self.election_id,
+                    &entry.name,
+                    entry.weight,
+                    &self.secret_key,
+                );
+                (tx.hash(), self.client.post(&url).json(&tx))
+            }
+            RosterKind::Candidates => {
+                let tx = TxCreateCandidate::new(
+                    &self.public_key,
+                    pub_key,
+                    self.election_id,
+                    &entry.name,
+                    &entry.info,
+                    &self.secret_key,
+                );
+                (tx.hash(), self.client.post(&url).json(&tx))
+            }
+        };
+
+        match request.send() {
+            Ok(ref response) if response.status().is_success() => Ok(tx_hash),
+            Ok(mut response) => Err(format!(
+                "rejected by node ({}): {}",
+                response.status(),
+                response.text().unwrap_or_default()
+            )),
+            Err(err) => Err(format!("not sent: {}", err)),
+        }
+    }
+
+    /// Waits until the sent transactions are committed and returns their statuses.
+    /// Transactions, which were not sent, keep their errors.
+    fn wait_for_commit(&self, sent: &[Result<Hash, String>]) -> Vec<Result<(), String>> {
+        let mut statuses: Vec<Option<Result<(), String>>> = sent
+            .iter()
+            .map(|tx| tx.as_ref().err().map(|err| Err(err.clone())))
+            .collect();
+
+        for _ in 0..COMMIT_CHECKS {
+            thread::sleep(Duration::from_secs(1));
+            for (tx, status) in sent.iter().zip(statuses.iter_mut()) {
+                if status.is_none() {
+                    if let Ok(tx_hash) = tx {
+                        *status = self.commit_status(tx_hash);
+                    }
+                }
+            }
+
+            if statuses.iter().all(Option::is_some) {
+                break;
+            }
+        }
+        statuses
+            .into_iter()
+            .map(|status| status.unwrap_or_else(|| Err("not committed".to_owned())))
+            .collect()
+    }
+
+    /// Returns status of the committed transaction, or `None` if it is not committed yet.
+    fn commit_status(&self, tx_hash: &Hash) -> Option<Result<(), String>> {
+        let url = format!("http://{}/api/explorer/v1/transactions", self.node);
+        let info: serde_json::Value = self
+            .client
+            .get(&url)
+            .query(&[("hash", encode_hex(tx_hash))])
+            .send()
+            .and_then(|mut response| response.json())
+            .ok()?;
+        if info["type"] != "committed" {
+            return None;
+        }
+
+        let status = &info["status"];
+        let description = status["description"].as_str().unwrap_or_default();
+        Some(match status["type"].as_str() {
+            Some("success") => Ok(()),
+            Some("error") => match status["code"].as_u64() {
+                Some(code) => match Error::from_code(code as u8) {
+                    Some(error) => Err(format!("{:?} ({}): {}", error, code, description)),
+                    None => Err(format!("error {}: {}", code, description)),
+                },
+                None => Err(format!("error: {}", description)),
+            },
+            _ => Err(format!("failed: {}", status)),
+        })
+    }
+}
+
+/// Creates the new file for generated secret keys, readable and writable only by its owner.
+/// An existing file is not overwritten, so keys of a previous import are not lost.
+#[cfg(unix)]
+fn create_keys_file(path: &str) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+/// Creates the new file for generated secret keys.
+#[cfg(not(unix))]
+fn create_keys_file(path: &str) -> io::Result<File> {
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
@@ -1,16 +1,62 @@
-use exonum::crypto::PublicKey;
+use exonum::{
+    blockchain::Schema,
+    crypto::PublicKey,
+    storage::Snapshot,
+};
+use serde_json;
 
 /// VoteServiceConfig used to store service configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoteServiceConfig {
-    /// PublicKey to create ephemeral key for vote encryption.
-    pub author_public_key: Option<PublicKey>,
+    /// Joint election public key of validators, stored with every election created
+    /// while it is actual; clients encrypt their votes against the key of the election.
+    pub election_public_key: Option<PublicKey>,
+    /// Number of validators, which decryption shares are needed to tally results.
+    #[serde(default)]
+    pub threshold: u16,
+    /// Verification keys of the validators key shares, in order of validators.
+    #[serde(default)]
+    pub verification_keys: Vec<PublicKey>,
+    /// Public keys of administrators, which can create elections
+    /// and register candidates and voters in any election.
+    /// At least one administrator is required, see `VoteService::new`.
+    #[serde(default)]
+    pub administrators: Vec<PublicKey>,
+    /// Public key of the issuer of blind-signed voting tokens for anonymous elections.
+    #[serde(default)]
+    pub token_issuer_key: Option<PublicKey>,
+    /// Hex-encoded key shares of the validator, used to publish decryption shares.
+    /// Shares of previous election keys are kept to decrypt elections created with them.
+    ///
+    /// Node-local setting: it is never written to the blockchain configuration.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub key_shares: Vec<String>,
 }
 
 impl Default for VoteServiceConfig {
     fn default() -> Self {
         Self {
-            author_public_key: None,
+            election_public_key: None,
+            threshold: 0,
+            verification_keys: Vec::new(),
+            administrators: Vec::new(),
+            token_issuer_key: None,
+            key_shares: Vec::new(),
         }
     }
 }
+
+impl VoteServiceConfig {
+    /// Returns service configuration from the actual blockchain configuration.
+    ///
+    /// Unlike the node-local config, it is the same on every validator.
+    pub fn actual(snapshot: &dyn Snapshot) -> Self {
+        let schema = Schema::new(snapshot);
+        schema
+            .actual_configuration()
+            .services
+            .get("voteservice")
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+}
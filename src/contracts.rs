@@ -1,44 +1,441 @@
+use blind;
 use cipher;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use elgamal;
 use errors::Error;
 use exonum::{
     blockchain::{ExecutionResult, Transaction},
-    crypto::CryptoHash,
+    crypto::{CryptoHash, Hash, PublicKey},
     messages::Message,
-    storage::Fork,
+    storage::{Fork, Snapshot},
 };
-use schema::{Candidate, CandidateResult, Vote, VoteServiceSchema, Voter};
-use transactions::{TxAddVote, TxCreateCandidate, TxCreateVoter};
+use group;
+use liquid;
+use lsag;
+use mixnet;
+use ranked;
+use roll;
+use schema::{
+    self, ApprovalResult, Ballot, Candidate, CandidateScore, Ciphertext, DecryptedCandidateResult,
+    DecryptionShare, DelegatedWeight, Election, ElectionMode, ElectionPhase, EncryptedVote,
+    Majority, Mix, PairwiseResult, PartialDecryption, ReferendumChoice, ReferendumResult,
+    RejectedVote, RejectionReason, ReplacedVote, Reveal, ScoreResult, TokenIssuance, Vote,
+    VoteServiceSchema, Voter, WeightChange,
+};
+use threshold;
+use transactions::{
+    TxAddAnonymousVote, TxAddApprovalVote, TxAddBallot, TxAddMixVote, TxAddRankedVote,
+    TxAddReferendumVote, TxAddRingVote, TxAddScoreVote, TxAddVote, TxAuthorizeDelegate,
+    TxCommitVote, TxCreateCandidate, TxCreateElection, TxCreateVoter, TxDecryptionShare,
+    TxIssueToken, TxRequestToken, TxRevealVote, TxRevokeVoter, TxSetVoterWeight, TxShuffle,
+    TxTallyElection, TxTokenCommitment, TxTransferVote, TxUpdateCandidate, TxWithdrawCandidate,
+};
+use zkp;
 
 /// Contracts.
 
-impl Transaction for TxCreateCandidate {
+impl Transaction for TxCreateElection {
     /// Verifies integrity of the transaction by checking the transaction
     /// signature.
     fn verify(&self) -> bool {
-        self.verify_signature(self.pub_key())
+        self.verify_signature(self.author())
     }
 
-    /// If candidate with specified public key is not created, then creates
-    /// a new candidate with the specified public key, name and info,
-    /// and a new candidate result struct.
+    /// If the author is a service administrator and election with specified id
+    /// is not created, then creates a new election with the specified author,
+    /// title, description, voting period, ballot mode, number of seats, maximal score,
+    /// end of the reveal period, pass rule, re-voting option and root of the eligibility roll.
+    /// Election key and verification keys actual at the moment are stored
+    /// with the election, so later rotation of the keys does not affect it.
     /// Otherwise, does nothing.
     fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        if self.start_height() >= self.end_height() {
+            Err(Error::InvalidElectionPeriod)?
+        }
+
+        let mode = match ElectionMode::from_u8(self.mode()) {
+            Some(mode) => mode,
+            None => Err(Error::UnknownElectionMode)?,
+        };
+
+        let is_valid_reveal_period = if mode == ElectionMode::CommitReveal {
+            self.reveal_end_height() > self.end_height()
+        } else {
+            self.reveal_end_height() == 0
+        };
+        if !is_valid_reveal_period {
+            Err(Error::InvalidRevealPeriod)?
+        }
+
+        if self.seats() == 0 || (self.seats() > 1 && mode != ElectionMode::SingleTransferable) {
+            Err(Error::InvalidSeats)?
+        }
+
+        if (self.max_score() == 0) == (mode == ElectionMode::Score) {
+            Err(Error::InvalidMaxScore)?
+        }
+
+        let is_valid_pass_rule = if mode == ElectionMode::Referendum {
+            Majority::from_u8(self.majority()).is_some() && self.quorum() <= 100
+        } else {
+            self.majority() == 0 && self.quorum() == 0
+        };
+        if !is_valid_pass_rule {
+            Err(Error::InvalidPassRule)?
+        }
+
+        if self.revoting() && mode != ElectionMode::Encrypted {
+            Err(Error::InvalidRevoting)?
+        }
+
+        if *self.roll_root() != Hash::zero() && mode != ElectionMode::Encrypted {
+            Err(Error::InvalidRollRoot)?
+        }
+
         let mut schema = VoteServiceSchema::new(view);
-        if schema.candidate(self.pub_key()).is_none() {
-            let candidate = Candidate::new(self.pub_key(), self.name(), self.info());
+        if !schema.is_administrator(self.author()) {
+            Err(Error::Unauthorized)?
+        }
+
+        let config = schema.config();
+        let (election_key, verification_keys) = if !mode.is_encrypted() {
+            (PublicKey::zero(), Vec::new())
+        } else if let Some(key) = config.election_public_key {
+            (key, config.verification_keys)
+        } else {
+            Err(Error::ElectionKeyNotFound)?
+        };
+
+        if schema.election(self.id()).is_none() {
+            let election = Election::new(
+                self.id(),
+                self.author(),
+                self.title(),
+                self.description(),
+                self.start_height(),
+                self.end_height(),
+                self.reveal_end_height(),
+                self.mode(),
+                self.seats(),
+                self.max_score(),
+                self.majority(),
+                self.quorum(),
+                self.revoting(),
+                self.roll_root(),
+                &election_key,
+                verification_keys,
+                false,
+            );
             println!(
-                "TxCreateCandidate::execute: Create the candidate: {:?}",
-                candidate
+                "TxCreateElection::execute: Create the election: {:?}",
+                election
+            );
+            schema.elections_mut().put(&self.id().hash(), election);
+            schema.update_election_hash(self.id());
+            Ok(())
+        } else {
+            Err(Error::ElectionAlreadyExists)?
+        }
+    }
+}
+
+impl Transaction for TxTallyElection {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    /// If voting of the election is over, transaction is signed by the election
+    /// author, votes of a mixnet election are mixed and at least threshold
+    /// of validators published decryption shares, then decrypts the votes
+    /// (or encrypted totals of candidates in homomorphic mode, or mixed votes
+    /// in mixnet mode), stores results of the election and marks it as tallied.
+    /// Delegations of votes in encrypted mode are resolved, delegated weights are stored.
+    /// Votes for withdrawn candidates are void, ranked ballots pass to the next preference.
+    /// Ranked votes are tallied by instant runoff or single transferable vote,
+    /// rounds of the tally and pairwise preferences with Condorcet winners
+    /// are stored as well. Approval and score votes are
+    /// also stored in their own results tables, result of a referendum states
+    /// whether its motion passed. Ranked, approval, score and referendum votes
+    /// which are not counted are stored with the reason.
+    /// Votes of a commit-reveal election are not encrypted, so only revealed
+    /// votes are counted, without decryption shares, once the reveal period is over.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.author() != self.author() {
+            Err(Error::NotElectionAuthor)?
+        }
+
+        if election.phase(schema.current_height()) != ElectionPhase::Closed {
+            Err(Error::ElectionNotClosed)?
+        }
+
+        if !schema.is_mixing_complete(&election) {
+            Err(Error::MixingNotComplete)?
+        }
+
+        // Votes for withdrawn candidates are void.
+        let withdrawn = withdrawn_candidates(&schema, election_id);
+        let mode = election.election_mode();
+        let mut rejected = vec![];
+        let results = if mode == Some(ElectionMode::CommitReveal) {
+            count_reveals(&schema, election_id)
+        } else {
+            let shared = combine_decryption_shares(&schema, &election)?;
+            match mode {
+                Some(ElectionMode::Homomorphic) => decrypt_totals(&schema, election_id, &shared)?,
+                Some(ElectionMode::Mixnet) => decrypt_mixed(&schema, election_id, &shared),
+                Some(ElectionMode::Ranked) => {
+                    let (results, ballots) =
+                        decrypt_ranked(&schema, election_id, &shared, &mut rejected);
+                    let candidates: Vec<PublicKey> = results
+                        .iter()
+                        .map(|result| *result.candidate())
+                        .collect();
+                    store_pairwise_results(&mut schema, election_id, &candidates, &ballots);
+                    for round in ranked::instant_runoff(&candidates, &ballots) {
+                        println!("TxTallyElection::execute: Store round: {:?}", round);
+                        schema.rounds_mut(election_id).push(round);
+                    }
+                    results
+                }
+                Some(ElectionMode::SingleTransferable) => {
+                    let (results, ballots) =
+                        decrypt_ranked(&schema, election_id, &shared, &mut rejected);
+                    let candidates: Vec<PublicKey> = results
+                        .iter()
+                        .map(|result| *result.candidate())
+                        .collect();
+                    store_pairwise_results(&mut schema, election_id, &candidates, &ballots);
+                    let seats = election.seats();
+                    for round in ranked::single_transferable_vote(&candidates, &ballots, seats) {
+                        println!("TxTallyElection::execute: Store STV round: {:?}", round);
+                        schema.stv_rounds_mut(election_id).push(round);
+                    }
+                    results
+                }
+                Some(ElectionMode::Approval) => {
+                    let mut results = vec![];
+                    for result in decrypt_approvals(&schema, election_id, &shared, &mut rejected)
+                        .into_iter()
+                        .filter(|result| !withdrawn.contains(result.candidate()))
+                    {
+                        let candidate = *result.candidate();
+                        results.push(DecryptedCandidateResult::new(
+                            &candidate,
+                            vec![],
+                            result.approvals(),
+                        ));
+                        println!("TxTallyElection::execute: Store approvals: {:?}", result);
+                        schema.approval_results_mut(election_id).put(&candidate, result);
+                    }
+                    results
+                }
+                Some(ElectionMode::Score) => {
+                    let max_score = election.max_score();
+                    let mut results = vec![];
+                    let scores =
+                        decrypt_scores(&schema, election_id, &shared, max_score, &mut rejected);
+                    for result in scores
+                        .into_iter()
+                        .filter(|result| !withdrawn.contains(result.candidate()))
+                    {
+                        let candidate = *result.candidate();
+                        results.push(DecryptedCandidateResult::new(
+                            &candidate,
+                            vec![],
+                            result.total(),
+                        ));
+                        println!("TxTallyElection::execute: Store scores: {:?}", result);
+                        schema.score_results_mut(election_id).put(&candidate, result);
+                    }
+                    results
+                }
+                Some(ElectionMode::Referendum) => {
+                    let result = decrypt_referendum(&schema, &election, &shared, &mut rejected);
+                    println!("TxTallyElection::execute: Store referendum result: {:?}", result);
+                    schema.referendum_results_mut(election_id).push(result);
+                    vec![]
+                }
+                Some(ElectionMode::Encrypted) => {
+                    let mut results =
+                        decrypt_votes(&schema, election_id, &shared, true, &mut rejected)?;
+                    for delegated in count_delegations(&schema, election_id, &mut results)? {
+                        let delegate = *delegated.delegate();
+                        println!("TxTallyElection::execute: Store delegation: {:?}", delegated);
+                        schema
+                            .delegated_weights_mut(election_id)
+                            .put(&delegate, delegated);
+                    }
+                    results
+                }
+                _ => decrypt_votes(&schema, election_id, &shared, false, &mut rejected)?,
+            }
+        };
+        for rejected_vote in rejected {
+            println!("TxTallyElection::execute: Reject vote: {:?}", rejected_vote);
+            schema.rejected_votes_mut(election_id).push(rejected_vote);
+        }
+        for result in results
+            .into_iter()
+            .filter(|result| !withdrawn.contains(result.candidate()))
+        {
+            let candidate = *result.candidate();
+            println!(
+                "TxTallyElection::execute: Store decrypted result: {:?}",
+                result
             );
-            schema.candidates_mut().put(self.pub_key(), candidate);
+            schema
+                .decrypted_results_mut(election_id)
+                .put(&candidate, result);
+        }
+
+        println!(
+            "TxTallyElection::execute: Tally the election: {:?}",
+            election
+        );
+        schema
+            .elections_mut()
+            .put(&election_id.hash(), election.set_tallied());
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxDecryptionShare {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.validator())
+    }
+
+    /// If voting of the election with encrypted votes is over, votes of a mixnet
+    /// election are mixed, transaction is signed by a validator which has not
+    /// published decryption shares for the election yet and shares of all ciphertexts
+    /// of the election have valid proofs, then stores the shares.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.phase(schema.current_height()) != ElectionPhase::Closed {
+            Err(Error::ElectionNotClosed)?
+        }
+
+        match election.election_mode() {
+            Some(mode) if mode.is_encrypted() => {}
+            _ => Err(Error::WrongElectionMode)?,
+        }
+
+        if !schema.is_mixing_complete(&election) {
+            Err(Error::MixingNotComplete)?
+        }
+
+        let index = match schema.validator_index(self.validator()) {
+            Some(index) => index,
+            None => Err(Error::Unauthorized)?,
+        };
+
+        if schema.decryption_share(election_id, self.validator()).is_some() {
+            Err(Error::DecryptionShareAlreadyExists)?
+        }
+
+        let verification_key = match election
+            .verification_keys()
+            .get(index as usize - 1)
+            .and_then(group::key_to_point)
+        {
+            Some(key) => key,
+            None => Err(Error::InvalidDecryptionShare)?,
+        };
+
+        let targets = schema.decryption_targets(&election);
+        let partials = self.partials();
+        if partials.len() != targets.len() {
+            Err(Error::InvalidDecryptionShare)?
+        }
+
+        for ((ciphertext_id, ephemeral), partial) in targets.iter().zip(&partials) {
+            if partial.ciphertext_id() != ciphertext_id
+                || !threshold::verify_partial_decryption(&verification_key, ephemeral, partial)
+            {
+                Err(Error::InvalidDecryptionShare)?
+            }
+        }
+
+        let share = DecryptionShare::new(self.validator(), index, partials);
+        println!(
+            "TxDecryptionShare::execute: Add decryption share of validator {:?}",
+            self.validator()
+        );
+        schema
+            .decryption_shares_mut(election_id)
+            .put(self.validator(), share);
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxCreateCandidate {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.authority())
+    }
+
+    /// If the election is not a referendum, transaction is signed by the election
+    /// authority, registration of the election is open and candidate with specified
+    /// public key is not created
+    /// in the election, then creates a new candidate with the specified public key,
+    /// name and info, and a new candidate result struct.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
 
-            let candidate_res = CandidateResult::new(self.pub_key(), vec![], 0);
+        // Options of a referendum are built-in.
+        if election.election_mode() == Some(ElectionMode::Referendum) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        if election.phase(schema.current_height()) != ElectionPhase::Registration {
+            Err(Error::RegistrationClosed)?
+        }
+
+        if schema.candidate(election_id, self.pub_key()).is_none() {
+            let candidate = Candidate::new(self.pub_key(), self.name(), self.info(), false);
             println!(
-                "TxCreateCandidate::execute: Create Candidate result: {:?}",
-                candidate_res
+                "TxCreateCandidate::execute: Create the candidate: {:?}",
+                candidate
             );
-            schema.vote_results_mut().put(self.pub_key(), candidate_res);
+            schema
+                .candidates_mut(election_id)
+                .put(self.pub_key(), candidate);
 
+            schema.update_election_hash(election_id);
             Ok(())
         } else {
             Err(Error::CandidateAlreadyExists)?
@@ -46,22 +443,161 @@ impl Transaction for TxCreateCandidate {
     }
 }
 
+impl Transaction for TxWithdrawCandidate {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.authority())
+    }
+
+    /// If transaction is signed by the election authority, voting of the election
+    /// is not over and candidate with specified id exists and is not withdrawn,
+    /// then marks the candidate as withdrawn.
+    /// Votes for the candidate are encrypted, so they are accepted, but are void
+    /// on tally: ranked ballots pass to the next preference, in other modes results
+    /// of the candidate are not stored.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration | ElectionPhase::Voting => {}
+            ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+                Err(Error::VotingClosed)?
+            }
+        }
+
+        let candidate = match schema.candidate(election_id, self.candidate_id()) {
+            Some(candidate) => candidate,
+            None => Err(Error::CandidateNotFound)?,
+        };
+
+        if candidate.withdrawn() {
+            Err(Error::CandidateWithdrawn)?
+        }
+
+        let candidate = candidate.set_withdrawn();
+        println!(
+            "TxWithdrawCandidate::execute: Withdraw the candidate: {:?}",
+            candidate
+        );
+        schema
+            .candidates_mut(election_id)
+            .put(self.candidate_id(), candidate);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxUpdateCandidate {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.authority())
+    }
+
+    /// If transaction is signed by the election authority, voting of the election
+    /// is not over and candidate with specified id exists and is not withdrawn,
+    /// then replaces info about the candidate.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration | ElectionPhase::Voting => {}
+            ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+                Err(Error::VotingClosed)?
+            }
+        }
+
+        let candidate = match schema.candidate(election_id, self.candidate_id()) {
+            Some(candidate) => candidate,
+            None => Err(Error::CandidateNotFound)?,
+        };
+
+        if candidate.withdrawn() {
+            Err(Error::CandidateWithdrawn)?
+        }
+
+        let candidate = candidate.set_info(self.info());
+        println!(
+            "TxUpdateCandidate::execute: Update the candidate: {:?}",
+            candidate
+        );
+        schema
+            .candidates_mut(election_id)
+            .put(self.candidate_id(), candidate);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
 impl Transaction for TxCreateVoter {
     /// Verifies integrity of the transaction by checking the transaction
     /// signature.
     fn verify(&self) -> bool {
-        self.verify_signature(self.pub_key())
+        self.verify_signature(self.authority())
     }
 
-    /// If voter with specified public key is not created, then creates
-    /// a new voter with the specified public key and name.
+    /// If transaction is signed by the election authority, registration of the
+    /// election is open, weight is not zero (and is 1 unless the election is encrypted)
+    /// and voter with specified public key is neither created in the election nor revoked,
+    /// then creates a new voter with the specified public key, name and weight.
     /// Otherwise, does nothing.
     fn execute(&self, view: &mut Fork) -> ExecutionResult {
         let mut schema = VoteServiceSchema::new(view);
-        if schema.voter(self.pub_key()).is_none() {
-            let voter = Voter::new(self.pub_key(), self.name());
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        if election.phase(schema.current_height()) != ElectionPhase::Registration {
+            Err(Error::RegistrationClosed)?
+        }
+
+        if self.weight() == 0 {
+            Err(Error::InvalidWeight)?
+        }
+
+        check_weight(&election, self.weight())?;
+
+        if schema.revoked_voter(election_id, self.pub_key()).is_some() {
+            Err(Error::VoterRevoked)?
+        }
+
+        if schema.voter(election_id, self.pub_key()).is_none() {
+            let voter = Voter::new(self.pub_key(), self.name(), self.weight());
             println!("TxCreateVoter::execute: Create the voter: {:?}", voter);
-            schema.voters_mut().put(self.pub_key(), voter);
+            schema.voters_mut(election_id).put(self.pub_key(), voter);
+            schema.update_election_hash(election_id);
             Ok(())
         } else {
             Err(Error::VoterAlreadyExists)?
@@ -69,47 +605,1928 @@ impl Transaction for TxCreateVoter {
     }
 }
 
-impl Transaction for TxAddVote {
+impl Transaction for TxSetVoterWeight {
     /// Verifies integrity of the transaction by checking the transaction
     /// signature.
     fn verify(&self) -> bool {
-        self.verify_signature(self.pub_key())
+        self.verify_signature(self.authority())
+    }
+
+    /// If transaction is signed by the election authority, registration of the
+    /// election is open, weight is not zero (and is 1 unless the election is encrypted)
+    /// and voter with specified id exists, then changes weight of the voter and records
+    /// the change.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        // Weights are fixed once voting starts, so results of votes are not recounted.
+        if election.phase(schema.current_height()) != ElectionPhase::Registration {
+            Err(Error::RegistrationClosed)?
+        }
+
+        if self.weight() == 0 {
+            Err(Error::InvalidWeight)?
+        }
+
+        check_weight(&election, self.weight())?;
+
+        let voter = match schema.voter(election_id, self.voter_id()) {
+            Some(voter) => voter,
+            None => Err(Error::VoterNotFound)?,
+        };
+
+        let change = WeightChange::new(
+            self.voter_id(),
+            voter.weight(),
+            self.weight(),
+            schema.current_height(),
+        );
+        println!("TxSetVoterWeight::execute: Change voter weight: {:?}", change);
+        let voter = Voter::new(voter.pub_key(), voter.name(), self.weight());
+        schema.voters_mut(election_id).put(self.voter_id(), voter);
+        schema.weight_changes_mut(election_id).push(change);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxRevokeVoter {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.authority())
+    }
+
+    /// If transaction is signed by the election authority, registration of the
+    /// election is open and voter with specified id exists, then removes the voter
+    /// together with delegations of the voter and transfers of votes to the voter,
+    /// and keeps it in revoked voters. Voters who transferred their votes to the revoked
+    /// voter may transfer them again until voting is over.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        // No votes are cast before voting opens, so results are not affected.
+        if election.phase(schema.current_height()) != ElectionPhase::Registration {
+            Err(Error::RegistrationClosed)?
+        }
+
+        if schema.revoked_voter(election_id, self.voter_id()).is_some() {
+            Err(Error::VoterRevoked)?
+        }
+
+        let voter = match schema.voter(election_id, self.voter_id()) {
+            Some(voter) => voter,
+            None => Err(Error::VoterNotFound)?,
+        };
+
+        println!("TxRevokeVoter::execute: Revoke the voter: {:?}", voter);
+        schema.voters_mut(election_id).remove(self.voter_id());
+        schema.delegates_mut(election_id).remove(self.voter_id());
+        schema
+            .vote_delegations_mut(election_id)
+            .remove(self.voter_id());
+        let transferred: Vec<PublicKey> = schema
+            .vote_delegations(election_id)
+            .iter()
+            .filter(|(_, proxy)| proxy == self.voter_id())
+            .map(|(voter, _)| voter)
+            .collect();
+        for voter in &transferred {
+            println!("TxRevokeVoter::execute: Revoke transfer of vote of voter {:?}", voter);
+            schema.vote_delegations_mut(election_id).remove(voter);
+        }
+        schema
+            .revoked_voters_mut(election_id)
+            .put(self.voter_id(), voter);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAuthorizeDelegate {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.voter_id())
     }
 
-    /// If candidate and voter with specified public keys exist, then creates
-    /// a new encrypted vote with specified voter and candidate public keys.
-    /// Also updates vote results.
+    /// If voter with specified public key exists and voting of the election
+    /// is not over, then authorizes the delegate to cast the vote on voter's behalf.
+    /// Previously authorized delegate is replaced.
     /// Otherwise, does nothing.
     fn execute(&self, view: &mut Fork) -> ExecutionResult {
         let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
 
-        if schema.candidate(self.candidate_id()).is_none() {
-            Err(Error::CandidateNotFound)?
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration | ElectionPhase::Voting => {}
+            ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+                Err(Error::VotingClosed)?
+            }
         }
 
-        if schema.voter(self.voter_id()).is_none() {
+        if schema.voter(election_id, self.voter_id()).is_none() {
             Err(Error::VoterNotFound)?
         }
 
-        let voter_hash = self.voter_id().hash();
-        if schema.vote(&voter_hash).is_none() {
-            let vote = Vote::new(self.voter_id(), self.candidate_id());
-            let enc_vote = cipher::encrypt_vote(&vote);
-            println!("TxAddVote::execute: Add encrypted vote {:?}", enc_vote);
-            schema.votes_mut().put(&voter_hash, enc_vote.clone());
-
-            let result = match schema.candidate_result(self.candidate_id()) {
-                Some(res) => res,
-                None => Err(Error::CandidateResultNotFound)?,
-            };
-            let mut votes = result.votes();
-            votes.push(enc_vote);
-            let votes_num = votes.len() as u64;
-            let result = CandidateResult::new(self.candidate_id(), votes, votes_num);
-            schema.vote_results_mut().put(self.candidate_id(), result);
-            Ok(())
-        } else {
-            Err(Error::VoteAlreadyExists)?
+        println!(
+            "TxAuthorizeDelegate::execute: Authorize delegate {:?} for voter {:?}",
+            self.delegate(),
+            self.voter_id()
+        );
+        schema
+            .delegates_mut(election_id)
+            .put(self.voter_id(), *self.delegate());
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxTransferVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.voter_id())
+    }
+
+    /// If the election is in encrypted mode, its voting is not over, both voters
+    /// with specified ids exist and the transfer does not close a cycle of delegations,
+    /// then transfers the vote of the voter to the proxy. Previous transfer is replaced,
+    /// transfer to the voter itself revokes it.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        // Votes of other modes are not linked to the voters, so they can not be resolved.
+        if election.election_mode() != Some(ElectionMode::Encrypted) {
+            Err(Error::WrongElectionMode)?
         }
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration | ElectionPhase::Voting => {}
+            ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+                Err(Error::VotingClosed)?
+            }
+        }
+
+        if schema.voter(election_id, self.voter_id()).is_none()
+            || schema.voter(election_id, self.proxy_id()).is_none()
+        {
+            Err(Error::VoterNotFound)?
+        }
+
+        if self.proxy_id() == self.voter_id() {
+            println!(
+                "TxTransferVote::execute: Revoke transfer of vote of voter {:?}",
+                self.voter_id()
+            );
+            schema
+                .vote_delegations_mut(election_id)
+                .remove(self.voter_id());
+            schema.update_election_hash(election_id);
+            return Ok(());
+        }
+
+        let delegations: Vec<(PublicKey, PublicKey)> =
+            schema.vote_delegations(election_id).iter().collect();
+        if liquid::creates_cycle(self.voter_id(), self.proxy_id(), &delegations) {
+            Err(Error::DelegationCycle)?
+        }
+
+        println!(
+            "TxTransferVote::execute: Transfer vote of voter {:?} to {:?}",
+            self.voter_id(),
+            self.proxy_id()
+        );
+        schema
+            .vote_delegations_mut(election_id)
+            .put(self.voter_id(), *self.proxy_id());
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
     }
+
+    /// If voting of the election in encrypted mode is open, voter with specified id
+    /// exists (or the voter is proven to be in the eligibility roll of the election),
+    /// one-time key of the vote is valid and unused, transaction is signed by the voter
+    /// or the voter's delegate and its proofs show that the encrypted choice is one of
+    /// the candidates, then stores the encrypted vote carried by the transaction as is
+    /// together with the choice. On tally the decrypted vote must match the choice.
+    /// If re-voting is enabled for the election, the previous vote of the voter
+    /// is replaced: it is moved to the replaced votes.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::Encrypted) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        // Voters who are not registered prove eligibility with the roll of the election.
+        if *election.roll_root() != Hash::zero()
+            && schema.voter(election_id, self.voter_id()).is_none()
+        {
+            check_roll_voter(
+                &schema,
+                &election,
+                self.pub_key(),
+                self.voter_id(),
+                self.roll_index(),
+                &self.roll_path(),
+            )?;
+        } else {
+            check_voter(&schema, &election, self.pub_key(), self.voter_id())?;
+        }
+
+        check_vote_key(&schema, election_id, self.vote_key())?;
+
+        let election_key = match group::key_to_point(election.election_key()) {
+            Some(key) => key,
+            None => Err(Error::InvalidBallotProof)?,
+        };
+        let candidates: Vec<PublicKey> = schema.candidates(election_id).keys().collect();
+        if !zkp::verify_vote(
+            &election_key,
+            election_id,
+            self.voter_id(),
+            &candidates,
+            self.vote_key(),
+            self.choice(),
+            &self.choice_proofs(),
+        ) {
+            Err(Error::InvalidBallotProof)?
+        }
+
+        let voter_hash = self.voter_id().hash();
+        if let Some(old_vote) = schema.vote(election_id, &voter_hash) {
+            if !election.revoting() {
+                Err(Error::VoteAlreadyExists)?
+            }
+
+            replace_vote(&mut schema, election_id, self.voter_id(), old_vote);
+        }
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!("TxAddVote::execute: Add encrypted vote {:?}", enc_vote);
+        store_vote(&mut schema, election_id, &voter_hash, enc_vote);
+        let choice = Ciphertext::new(self.vote_key(), self.choice());
+        schema
+            .vote_choices_mut(election_id)
+            .put(&voter_hash, choice);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddBallot {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the election in homomorphic mode is open, voter with specified
+    /// id exists and has not voted yet, transaction is signed by the voter or
+    /// the voter's delegate, the ballot has a valid ciphertext for every candidate
+    /// and its proofs show that it is a vote for exactly one candidate, then stores
+    /// the ballot and adds its ciphertexts to encrypted totals of candidates.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::Homomorphic) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        check_voter(&schema, &election, self.pub_key(), self.voter_id())?;
+
+        let voter_hash = self.voter_id().hash();
+        if schema.ballot(election_id, &voter_hash).is_some() {
+            Err(Error::VoteAlreadyExists)?
+        }
+
+        let candidates: Vec<PublicKey> = schema.candidates(election_id).keys().collect();
+        let ciphertexts = self.ciphertexts();
+        if ciphertexts.len() != candidates.len() || !ciphertexts.iter().all(elgamal::is_valid) {
+            Err(Error::InvalidBallot)?
+        }
+
+        let election_key = match group::key_to_point(election.election_key()) {
+            Some(key) => key,
+            None => Err(Error::InvalidBallotProof)?,
+        };
+        let proofs = self.proofs();
+        if !zkp::verify_ballot(
+            &election_key,
+            election_id,
+            self.voter_id(),
+            &ciphertexts,
+            &proofs,
+            self.sum_challenge(),
+            self.sum_response(),
+        ) {
+            Err(Error::InvalidBallotProof)?
+        }
+
+        for (candidate, ciphertext) in candidates.iter().zip(&ciphertexts) {
+            let total = match schema.encrypted_total(election_id, candidate) {
+                Some(total) => elgamal::add(&total, ciphertext).ok_or(Error::InvalidBallot)?,
+                None => ciphertext.clone(),
+            };
+            schema
+                .encrypted_totals_mut(election_id)
+                .put(candidate, total);
+        }
+
+        let ballot = Ballot::new(
+            ciphertexts,
+            proofs,
+            self.sum_challenge(),
+            self.sum_response(),
+        );
+        println!("TxAddBallot::execute: Add ballot {:?}", ballot);
+        schema.ballots_mut(election_id).put(&voter_hash, ballot);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxTokenCommitment {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.authority())
+    }
+
+    /// If transaction is signed by the election authority, voting of the anonymous
+    /// election is not over, voter with specified id exists, no commitment is
+    /// published for the voter yet or the published one has expired unrequested
+    /// and no other signing session of the election is open,
+    /// then stores the commitment of the token issuer.
+    /// Sessions are run one at a time: concurrent blind Schnorr sessions are open
+    /// to the ROS attack, which forges more tokens than were issued.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        check_token_issuance(&schema, &election, self.voter_id())?;
+
+        let height = schema.current_height();
+        if let Some(issuance) = schema.token_issuance(election_id, self.voter_id()) {
+            if !issuance.is_expired(height) {
+                Err(Error::TokenCommitmentExists)?
+            }
+        }
+
+        if schema
+            .token_issuances(election_id)
+            .values()
+            .any(|issuance| issuance.is_open(height))
+        {
+            Err(Error::TokenSessionOpen)?
+        }
+
+        if group::key_to_point(self.commitment()).is_none() {
+            Err(Error::InvalidToken)?
+        }
+
+        let issuance =
+            TokenIssuance::new(self.commitment(), &Hash::zero(), &Hash::zero(), height);
+        println!(
+            "TxTokenCommitment::execute: Add token commitment for voter {:?}",
+            self.voter_id()
+        );
+        schema
+            .token_issuances_mut(election_id)
+            .put(self.voter_id(), issuance);
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxRequestToken {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the anonymous election is not over, voter with specified id exists,
+    /// transaction is signed by the voter or the voter's delegate and the token issuer
+    /// published a commitment for the voter, which is not requested yet
+    /// and is not expired, then stores the blinded challenge.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        check_token_issuance(&schema, &election, self.voter_id())?;
+
+        let signer = self.pub_key();
+        let voter_id = self.voter_id();
+        if signer != voter_id && schema.delegate(election_id, voter_id).as_ref() != Some(signer) {
+            Err(Error::SignerNotVoter)?
+        }
+
+        let issuance = match schema.token_issuance(election_id, voter_id) {
+            Some(issuance) => issuance,
+            None => Err(Error::TokenCommitmentNotFound)?,
+        };
+
+        if issuance.is_requested() {
+            Err(Error::TokenAlreadyRequested)?
+        }
+
+        if issuance.is_expired(schema.current_height()) {
+            Err(Error::TokenSessionExpired)?
+        }
+
+        if *self.challenge() == Hash::zero() || group::hash_to_scalar(self.challenge()).is_none() {
+            Err(Error::InvalidToken)?
+        }
+
+        let issuance = TokenIssuance::new(
+            issuance.commitment(),
+            self.challenge(),
+            &Hash::zero(),
+            issuance.height(),
+        );
+        println!(
+            "TxRequestToken::execute: Request token for voter {:?}",
+            voter_id
+        );
+        schema.token_issuances_mut(election_id).put(voter_id, issuance);
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxIssueToken {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.authority())
+    }
+
+    /// If transaction is signed by the election authority, voting of the anonymous
+    /// election is not over, the voter requested a token which is not issued yet
+    /// and the response is signed by the token issuer, then stores the response.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if !schema.is_election_authority(&election, self.authority()) {
+            Err(Error::Unauthorized)?
+        }
+
+        check_token_issuance(&schema, &election, self.voter_id())?;
+
+        let issuance = match schema.token_issuance(election_id, self.voter_id()) {
+            Some(issuance) => issuance,
+            None => Err(Error::TokenCommitmentNotFound)?,
+        };
+
+        if !issuance.is_requested() {
+            Err(Error::TokenNotRequested)?
+        }
+
+        if issuance.is_issued() {
+            Err(Error::TokenAlreadyIssued)?
+        }
+
+        let issuer_key = token_issuer_key(&schema).ok_or(Error::InvalidTokenResponse)?;
+        if !blind::verify_response(
+            &issuer_key,
+            issuance.commitment(),
+            issuance.challenge(),
+            self.response(),
+        ) {
+            Err(Error::InvalidTokenResponse)?
+        }
+
+        let issuance = TokenIssuance::new(
+            issuance.commitment(),
+            issuance.challenge(),
+            self.response(),
+            issuance.height(),
+        );
+        println!(
+            "TxIssueToken::execute: Issue token for voter {:?}",
+            self.voter_id()
+        );
+        schema
+            .token_issuances_mut(election_id)
+            .put(self.voter_id(), issuance);
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddAnonymousVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the anonymous election is open, one-time key of the vote is valid
+    /// and unused and the token is signed by the token issuer for the signer key
+    /// and is not spent yet, then stores the encrypted vote under the signer key and marks
+    /// the token as spent.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::Anonymous) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration => Err(Error::VotingNotOpen)?,
+            ElectionPhase::Voting => {}
+            ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+                Err(Error::VotingClosed)?
+            }
+        }
+
+        check_vote_key(&schema, election_id, self.vote_key())?;
+
+        let issuer_key = token_issuer_key(&schema).ok_or(Error::InvalidToken)?;
+        if !blind::verify_token(
+            &issuer_key,
+            election_id,
+            self.pub_key(),
+            self.token_commitment(),
+            self.token_response(),
+        ) {
+            Err(Error::InvalidToken)?
+        }
+
+        if schema
+            .spent_token(election_id, self.token_commitment())
+            .is_some()
+        {
+            Err(Error::TokenAlreadySpent)?
+        }
+
+        let vote_hash = self.pub_key().hash();
+        if schema.vote(election_id, &vote_hash).is_some() {
+            Err(Error::VoteAlreadyExists)?
+        }
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!(
+            "TxAddAnonymousVote::execute: Add encrypted vote {:?}",
+            enc_vote
+        );
+        store_vote(&mut schema, election_id, &vote_hash, enc_vote);
+        schema
+            .spent_tokens_mut(election_id)
+            .put(&self.token_commitment().hash(), *self.pub_key());
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddRingVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the ring signed election is open, one-time key of the vote is valid
+    /// and unused, the ring signature of the signer key is made by one of the registered voters
+    /// and its key image is not used yet, then stores the encrypted vote under
+    /// the signer key and the key image.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::RingSigned) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration => Err(Error::VotingNotOpen)?,
+            ElectionPhase::Voting => {}
+            ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+                Err(Error::VotingClosed)?
+            }
+        }
+
+        check_vote_key(&schema, election_id, self.vote_key())?;
+
+        // Voters can not be registered during voting, so the ring is fixed.
+        let ring: Vec<PublicKey> = schema.voters(election_id).keys().collect();
+        if !lsag::verify(
+            &ring,
+            election_id,
+            self.pub_key(),
+            self.key_image(),
+            self.ring_challenge(),
+            &self.ring_responses(),
+        ) {
+            Err(Error::InvalidRingSignature)?
+        }
+
+        if schema.key_image(election_id, self.key_image()).is_some() {
+            Err(Error::KeyImageAlreadyUsed)?
+        }
+
+        let vote_hash = self.pub_key().hash();
+        if schema.vote(election_id, &vote_hash).is_some() {
+            Err(Error::VoteAlreadyExists)?
+        }
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!("TxAddRingVote::execute: Add encrypted vote {:?}", enc_vote);
+        store_vote(&mut schema, election_id, &vote_hash, enc_vote);
+        schema
+            .key_images_mut(election_id)
+            .put(&self.key_image().hash(), *self.pub_key());
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddMixVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the mixnet election is open, voter with specified id exists
+    /// and has not voted yet, transaction is signed by the voter or the voter's
+    /// delegate, the ciphertext is valid and the voter proved knowledge of its randomness,
+    /// then stores the ciphertext.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::Mixnet) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        check_voter(&schema, &election, self.pub_key(), self.voter_id())?;
+
+        if schema.mix_vote(election_id, self.voter_id()).is_some() {
+            Err(Error::VoteAlreadyExists)?
+        }
+
+        let ciphertext = self.ciphertext();
+        if !elgamal::is_valid(&ciphertext) {
+            Err(Error::InvalidBallot)?
+        }
+
+        // Ciphertext copied from the vote of another voter has no valid proof.
+        if !mixnet::verify_vote(
+            self.voter_id(),
+            &ciphertext,
+            self.challenge(),
+            self.response(),
+        ) {
+            Err(Error::InvalidBallotProof)?
+        }
+
+        println!("TxAddMixVote::execute: Add mix vote {:?}", ciphertext);
+        schema
+            .mix_votes_mut(election_id)
+            .put(&self.voter_id().hash(), ciphertext);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxShuffle {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.validator())
+    }
+
+    /// If voting of the mixnet election is over, transaction is signed by a validator
+    /// which has not shuffled votes of the election yet, the shuffle is the next stage
+    /// of mixing and its proof shows that it is a re-encryption of a permutation
+    /// of the output of the previous stage, then stores the shuffle.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.phase(schema.current_height()) != ElectionPhase::Closed {
+            Err(Error::ElectionNotClosed)?
+        }
+
+        if election.election_mode() != Some(ElectionMode::Mixnet) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        if schema.validator_index(self.validator()).is_none() {
+            Err(Error::Unauthorized)?
+        }
+
+        let mixes_num = schema.mixes(election_id).len();
+        if schema
+            .mixes(election_id)
+            .iter()
+            .any(|mix| mix.validator() == self.validator())
+        {
+            Err(Error::MixAlreadyExists)?
+        }
+
+        if schema.is_mixing_complete(&election) || u64::from(self.stage()) != mixes_num {
+            Err(Error::WrongMixStage)?
+        }
+
+        let election_key = match group::key_to_point(election.election_key()) {
+            Some(key) => key,
+            None => Err(Error::InvalidShuffle)?,
+        };
+        let ciphertexts = self.ciphertexts();
+        let proof = self.proof();
+        if !mixnet::verify_shuffle(
+            &election_key,
+            election_id,
+            self.stage(),
+            &schema.mixed_votes(election_id),
+            &ciphertexts,
+            &proof,
+        ) {
+            Err(Error::InvalidShuffle)?
+        }
+
+        println!(
+            "TxShuffle::execute: Add mix {} of validator {:?}",
+            self.stage(),
+            self.validator()
+        );
+        schema
+            .mixes_mut(election_id)
+            .push(Mix::new(self.validator(), ciphertexts, proof));
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxCommitVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the commit-reveal election is open, voter with specified id exists
+    /// and has not committed to a vote yet and transaction is signed by the voter
+    /// or the voter's delegate, then stores the commitment.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::CommitReveal) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        check_voter(&schema, &election, self.pub_key(), self.voter_id())?;
+
+        if schema.commitment(election_id, self.voter_id()).is_some() {
+            Err(Error::VoteAlreadyExists)?
+        }
+
+        println!(
+            "TxCommitVote::execute: Add commitment {:?} of voter {:?}",
+            self.commitment(),
+            self.voter_id()
+        );
+        schema
+            .commitments_mut(election_id)
+            .put(self.voter_id(), *self.commitment());
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxRevealVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the commit-reveal election is over and the reveal period is not,
+    /// the voter committed to a vote and has not revealed it yet, transaction is signed
+    /// by the voter or the voter's delegate, candidate with specified id exists
+    /// and is not withdrawn and the candidate and the salt match the commitment
+    /// of the voter in the election, then stores the revealed vote.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+        let voter_id = self.voter_id();
+
+        let election = match schema.election(election_id) {
+            Some(election) => election,
+            None => Err(Error::ElectionNotFound)?,
+        };
+
+        if election.election_mode() != Some(ElectionMode::CommitReveal) {
+            Err(Error::WrongElectionMode)?
+        }
+
+        match election.phase(schema.current_height()) {
+            ElectionPhase::Registration | ElectionPhase::Voting => Err(Error::ElectionNotClosed)?,
+            ElectionPhase::Revealing => {}
+            ElectionPhase::Closed | ElectionPhase::Tallied => Err(Error::RevealClosed)?,
+        }
+
+        let commitment = match schema.commitment(election_id, voter_id) {
+            Some(commitment) => commitment,
+            None => Err(Error::CommitmentNotFound)?,
+        };
+
+        if self.pub_key() != voter_id
+            && schema.delegate(election_id, voter_id).as_ref() != Some(self.pub_key())
+        {
+            Err(Error::SignerNotVoter)?
+        }
+
+        if schema.reveal(election_id, voter_id).is_some() {
+            Err(Error::VoteAlreadyRevealed)?
+        }
+
+        let candidate = match schema.candidate(election_id, self.candidate_id()) {
+            Some(candidate) => candidate,
+            None => Err(Error::CandidateNotFound)?,
+        };
+
+        // Votes for withdrawn candidates are void, so they are not revealed.
+        if candidate.withdrawn() {
+            Err(Error::CandidateWithdrawn)?
+        }
+
+        let expected =
+            schema::vote_commitment(election_id, voter_id, self.candidate_id(), self.salt());
+        if expected != commitment {
+            Err(Error::InvalidReveal)?
+        }
+
+        let reveal = Reveal::new(self.candidate_id(), self.salt());
+        println!(
+            "TxRevealVote::execute: Reveal vote {:?} of voter {:?}",
+            reveal, voter_id
+        );
+        schema.reveals_mut(election_id).put(voter_id, reveal);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddRankedVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the ranked or single transferable vote election is open,
+    /// voter with specified id exists
+    /// and has not voted yet, one-time key of the vote is valid and unused, transaction
+    /// is signed by the voter or the voter's delegate and the length of the vote
+    /// fits a ranked ballot of the election, see `check_ballot`,
+    /// then stores the encrypted ranked vote carried by the transaction as is.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let modes = [ElectionMode::Ranked, ElectionMode::SingleTransferable];
+        let voter_hash = check_ballot(
+            &schema,
+            election_id,
+            &modes,
+            self.pub_key(),
+            self.voter_id(),
+            self.vote_key(),
+            &self.vote_data(),
+        )?;
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!("TxAddRankedVote::execute: Add ranked vote {:?}", enc_vote);
+        store_vote(&mut schema, election_id, &voter_hash, enc_vote);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddApprovalVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the approval election is open, voter with specified id exists
+    /// and has not voted yet, one-time key of the vote is valid and unused, transaction
+    /// is signed by the voter or the voter's delegate and the length of the vote
+    /// fits an approval ballot of the election, see `check_ballot`,
+    /// then stores the encrypted approval vote carried by the transaction as is.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let modes = [ElectionMode::Approval];
+        let voter_hash = check_ballot(
+            &schema,
+            election_id,
+            &modes,
+            self.pub_key(),
+            self.voter_id(),
+            self.vote_key(),
+            &self.vote_data(),
+        )?;
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!("TxAddApprovalVote::execute: Add approval vote {:?}", enc_vote);
+        store_vote(&mut schema, election_id, &voter_hash, enc_vote);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddScoreVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the score election is open, voter with specified id exists
+    /// and has not voted yet, one-time key of the vote is valid and unused, transaction
+    /// is signed by the voter or the voter's delegate and the length of the vote
+    /// fits a score ballot of the election, see `check_ballot`,
+    /// then stores the encrypted score vote carried by the transaction as is.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let modes = [ElectionMode::Score];
+        let voter_hash = check_ballot(
+            &schema,
+            election_id,
+            &modes,
+            self.pub_key(),
+            self.voter_id(),
+            self.vote_key(),
+            &self.vote_data(),
+        )?;
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!("TxAddScoreVote::execute: Add score vote {:?}", enc_vote);
+        store_vote(&mut schema, election_id, &voter_hash, enc_vote);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+impl Transaction for TxAddReferendumVote {
+    /// Verifies integrity of the transaction by checking the transaction
+    /// signature.
+    fn verify(&self) -> bool {
+        self.verify_signature(self.pub_key())
+    }
+
+    /// If voting of the referendum is open, voter with specified id exists
+    /// and has not voted yet, one-time key of the vote is valid and unused, transaction
+    /// is signed by the voter or the voter's delegate and the length of the vote
+    /// fits a referendum ballot of the election, see `check_ballot`,
+    /// then stores the encrypted referendum vote carried by the transaction as is.
+    /// Otherwise, does nothing.
+    fn execute(&self, view: &mut Fork) -> ExecutionResult {
+        let mut schema = VoteServiceSchema::new(view);
+        let election_id = self.election_id();
+
+        let modes = [ElectionMode::Referendum];
+        let voter_hash = check_ballot(
+            &schema,
+            election_id,
+            &modes,
+            self.pub_key(),
+            self.voter_id(),
+            self.vote_key(),
+            &self.vote_data(),
+        )?;
+
+        let enc_vote = EncryptedVote::new(self.vote_key(), self.vote_data());
+        println!("TxAddReferendumVote::execute: Add referendum vote {:?}", enc_vote);
+        store_vote(&mut schema, election_id, &voter_hash, enc_vote);
+
+        schema.update_election_hash(election_id);
+        Ok(())
+    }
+}
+
+/// Checks that one-time key of the encrypted vote is valid and is not used
+/// by another vote of the election.
+///
+/// Replaced votes keep their keys, so a vote can not be replayed after re-voting.
+fn check_vote_key<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    vote_key: &PublicKey,
+) -> Result<(), Error> {
+    if group::key_to_point(vote_key).is_none() {
+        Err(Error::InvalidVoteKey)?
+    }
+
+    if schema.vote_key(election_id, vote_key).is_some() {
+        Err(Error::VoteKeyAlreadyUsed)?
+    }
+
+    Ok(())
+}
+
+/// Stores the encrypted vote with the specified id and marks its one-time key as used.
+fn store_vote(
+    schema: &mut VoteServiceSchema<&mut Fork>,
+    election_id: u64,
+    vote_id: &Hash,
+    enc_vote: EncryptedVote,
+) {
+    schema
+        .vote_keys_mut(election_id)
+        .put(enc_vote.pub_key(), *vote_id);
+    schema.votes_mut(election_id).put(vote_id, enc_vote);
+}
+
+/// Keeps the previous vote of the voter in replaced votes of the election,
+/// the next vote overwrites it in the votes table.
+fn replace_vote(
+    schema: &mut VoteServiceSchema<&mut Fork>,
+    election_id: u64,
+    voter_id: &PublicKey,
+    old_vote: EncryptedVote,
+) {
+    let replaced = ReplacedVote::new(voter_id, old_vote, schema.current_height());
+    println!("replace_vote: Replace vote {:?}", replaced);
+    schema.replaced_votes_mut(election_id).push(replaced);
+}
+
+/// Checks that the election is anonymous, its voting is not over
+/// and voter with specified id exists.
+fn check_token_issuance<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election: &Election,
+    voter_id: &PublicKey,
+) -> Result<(), Error> {
+    if election.election_mode() != Some(ElectionMode::Anonymous) {
+        Err(Error::WrongElectionMode)?
+    }
+
+    match election.phase(schema.current_height()) {
+        ElectionPhase::Registration | ElectionPhase::Voting => {}
+        ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+            Err(Error::VotingClosed)?
+        }
+    }
+
+    if schema.voter(election.id(), voter_id).is_none() {
+        Err(Error::VoterNotFound)?
+    }
+
+    Ok(())
+}
+
+/// Returns public key of the token issuer from the service configuration.
+fn token_issuer_key<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+) -> Option<RistrettoPoint> {
+    schema
+        .config()
+        .token_issuer_key
+        .and_then(|key| group::key_to_point(&key))
+}
+
+/// Checks that the weight of the voter is applied on tally of the election: votes
+/// of other modes than encrypted are not weighted, so their voters must have weight 1.
+fn check_weight(election: &Election, weight: u64) -> Result<(), Error> {
+    if weight != 1 && election.election_mode() != Some(ElectionMode::Encrypted) {
+        Err(Error::WeightNotSupported)?
+    }
+
+    Ok(())
+}
+
+/// Checks that voting of the election is open, voter with specified id exists
+/// and the signer is the voter or the voter's delegate.
+fn check_voter<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election: &Election,
+    signer: &PublicKey,
+    voter_id: &PublicKey,
+) -> Result<(), Error> {
+    match election.phase(schema.current_height()) {
+        ElectionPhase::Registration => Err(Error::VotingNotOpen)?,
+        ElectionPhase::Voting => {}
+        ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+            Err(Error::VotingClosed)?
+        }
+    }
+
+    if schema.voter(election.id(), voter_id).is_none() {
+        Err(Error::VoterNotFound)?
+    }
+
+    if signer != voter_id && schema.delegate(election.id(), voter_id).as_ref() != Some(signer) {
+        Err(Error::SignerNotVoter)?
+    }
+
+    Ok(())
+}
+
+/// Checks a ballot of ranked, approval, score or referendum election, which is encrypted
+/// with its voter id and is checked against candidates only on tally: the election is
+/// of one of the specified modes, `check_voter` passes, one-time key of the vote is valid
+/// and unused, the voter has not voted yet and the length of the vote fits a ballot
+/// of the election.
+///
+/// Returns the hash of the voter id, which identifies the vote.
+fn check_ballot<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    modes: &[ElectionMode],
+    signer: &PublicKey,
+    voter_id: &PublicKey,
+    vote_key: &PublicKey,
+    vote_data: &[u8],
+) -> Result<Hash, Error> {
+    let election = match schema.election(election_id) {
+        Some(election) => election,
+        None => Err(Error::ElectionNotFound)?,
+    };
+
+    let mode = match election.election_mode() {
+        Some(mode) if modes.contains(&mode) => mode,
+        _ => Err(Error::WrongElectionMode)?,
+    };
+
+    check_voter(schema, &election, signer, voter_id)?;
+
+    check_vote_key(schema, election_id, vote_key)?;
+
+    let voter_hash = voter_id.hash();
+    if schema.vote(election_id, &voter_hash).is_some() {
+        Err(Error::VoteAlreadyExists)?
+    }
+
+    let candidates = schema.candidates(election_id).keys().count();
+    if !is_valid_ballot_size(mode, candidates, vote_data.len()) {
+        Err(Error::InvalidBallot)?
+    }
+
+    Ok(voter_hash)
+}
+
+/// Checks that encrypted ballot of the specified length can hold a ballot of the mode
+/// with no more entries than candidates of the election.
+///
+/// Serialized ballot is the voter id and a segment of entries: 32 bytes of a candidate id,
+/// or a segment and 33 bytes of candidate score; a referendum ballot is the voter id
+/// and the choice. Contents of the entries are checked on tally.
+fn is_valid_ballot_size(mode: ElectionMode, candidates: usize, len: usize) -> bool {
+    const HEADER_SIZE: usize = 32 + 8;
+
+    let plain_len = match len.checked_sub(cipher::TAGLEN) {
+        Some(plain_len) => plain_len,
+        None => return false,
+    };
+
+    let entry_size = match mode {
+        ElectionMode::Referendum => return plain_len == 32 + 1,
+        ElectionMode::Score => 8 + 32 + 1,
+        _ => 32,
+    };
+
+    match plain_len.checked_sub(HEADER_SIZE) {
+        Some(entries_len) => {
+            entries_len % entry_size == 0 && entries_len / entry_size <= candidates
+        }
+        None => false,
+    }
+}
+
+/// Checks that voting of the election is open, the voter is in the eligibility roll
+/// of the election and the transaction is signed by the voter.
+///
+/// Voters of the roll are not registered, so they can not authorize delegates.
+/// Revocation of a voter, who is registered and also listed in the roll, applies to the roll too.
+fn check_roll_voter<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election: &Election,
+    signer: &PublicKey,
+    voter_id: &PublicKey,
+    roll_index: u64,
+    roll_path: &[Hash],
+) -> Result<(), Error> {
+    match election.phase(schema.current_height()) {
+        ElectionPhase::Registration => Err(Error::VotingNotOpen)?,
+        ElectionPhase::Voting => {}
+        ElectionPhase::Revealing | ElectionPhase::Closed | ElectionPhase::Tallied => {
+            Err(Error::VotingClosed)?
+        }
+    }
+
+    if !roll::verify(election.roll_root(), voter_id, roll_index, roll_path) {
+        Err(Error::NotInRoll)?
+    }
+
+    if schema.revoked_voter(election.id(), voter_id).is_some() {
+        Err(Error::VoterRevoked)?
+    }
+
+    if signer != voter_id {
+        Err(Error::SignerNotVoter)?
+    }
+
+    Ok(())
+}
+
+/// Reconstructs shared elements of all ciphertexts of the election, see
+/// `VoteServiceSchema::decryption_targets`, from decryption shares of threshold of validators.
+fn combine_decryption_shares<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election: &Election,
+) -> Result<Vec<RistrettoPoint>, Error> {
+    let election_id = election.id();
+    let required = schema.config().threshold as usize;
+    let shares: Vec<DecryptionShare> = schema
+        .decryption_shares(election_id)
+        .values()
+        .take(required)
+        .collect();
+    if required == 0 || shares.len() < required {
+        Err(Error::NotEnoughDecryptionShares)?
+    }
+
+    let partials: Vec<(u16, Vec<PartialDecryption>)> = shares
+        .iter()
+        .map(|share| (share.index(), share.partials()))
+        .collect();
+    let targets = schema.decryption_targets(election);
+    let mut shared = Vec::with_capacity(targets.len());
+    for pos in 0..targets.len() {
+        // Shares are checked on publishing, so a missing one means the targets changed.
+        let points = partials
+            .iter()
+            .map(|(index, partials)| {
+                partials
+                    .get(pos)
+                    .and_then(|partial| group::key_to_point(partial.share()))
+                    .map(|point| (*index, point))
+            })
+            .collect::<Option<Vec<(u16, RistrettoPoint)>>>()
+            .ok_or(Error::InvalidDecryptionShare)?;
+        shared.push(threshold::combine(&points));
+    }
+
+    Ok(shared)
+}
+
+/// Decrypts votes of the election with shared elements, reconstructed from decryption shares.
+///
+/// Votes which can not be decrypted, are not cast by their voter, do not match their
+/// proven choice or choose an unknown or a withdrawn candidate are not counted and are
+/// added to `rejected`.
+/// If votes are weighted, every vote counts with weight of its voter.
+fn decrypt_votes<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    shared: &[RistrettoPoint],
+    weighted: bool,
+    rejected: &mut Vec<RejectedVote>,
+) -> Result<Vec<DecryptedCandidateResult>, Error> {
+    let votes: Vec<(Hash, EncryptedVote)> = schema.votes(election_id).iter().collect();
+    let mut results: Vec<(PublicKey, Vec<Vote>, u64)> = schema
+        .candidates(election_id)
+        .keys()
+        .map(|candidate| (candidate, vec![], 0))
+        .collect();
+    let withdrawn = withdrawn_candidates(schema, election_id);
+
+    for ((vote_id, enc_vote), shared) in votes.iter().zip(shared) {
+        let vote = match cipher::decrypt_vote(enc_vote, shared) {
+            Some(vote) => vote,
+            None => {
+                println!("decrypt_votes: Failed to decrypt vote {:?}", vote_id);
+                rejected.push(RejectedVote::new(vote_id, RejectionReason::Malformed as u8));
+                continue;
+            }
+        };
+
+        // Ciphertext copied from the vote of another voter is not counted.
+        if vote.from().hash() != *vote_id {
+            println!("decrypt_votes: Vote is not cast by its voter {:?}", vote);
+            rejected.push(RejectedVote::new(vote_id, RejectionReason::WrongVoter as u8));
+            continue;
+        }
+
+        // Choice proven by the vote must be the candidate of the decrypted vote.
+        if let Some(choice) = schema.vote_choice(election_id, vote_id) {
+            let point = group::key_to_point(choice.b()).map(|point| point - shared);
+            if point != Some(zkp::candidate_point(vote.to())) {
+                println!("decrypt_votes: Vote does not match its choice {:?}", vote);
+                rejected.push(RejectedVote::new(vote_id, RejectionReason::Malformed as u8));
+                continue;
+            }
+        }
+
+        let weight = if weighted {
+            schema
+                .voter(election_id, vote.from())
+                .map_or(1, |voter| voter.weight())
+        } else {
+            1
+        };
+        if withdrawn.contains(vote.to()) {
+            println!("decrypt_votes: Vote for withdrawn candidate {:?}", vote);
+            let reason = RejectionReason::CandidateWithdrawn as u8;
+            rejected.push(RejectedVote::new(vote_id, reason));
+            continue;
+        }
+        match results.iter_mut().find(|res| &res.0 == vote.to()) {
+            Some(result) => {
+                result.1.push(vote);
+                result.2 = result.2.checked_add(weight).ok_or(Error::WeightOverflow)?;
+            }
+            None => {
+                println!("decrypt_votes: Vote for unknown candidate {:?}", vote);
+                rejected.push(RejectedVote::new(vote_id, RejectionReason::InvalidChoice as u8));
+            }
+        }
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|(candidate, votes, votes_num)| {
+            DecryptedCandidateResult::new(&candidate, votes, votes_num)
+        })
+        .collect())
+}
+
+/// Resolves delegations of the voters who did not vote and adds their weights to results
+/// of the candidates chosen by their delegates. Returns delegated weights per delegate.
+///
+/// A direct vote overrides delegation, even if the vote is not counted.
+/// Fails if a sum of weights overflows.
+fn count_delegations<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    results: &mut Vec<DecryptedCandidateResult>,
+) -> Result<Vec<DelegatedWeight>, Error> {
+    let choices: Vec<(PublicKey, usize)> = results
+        .iter()
+        .enumerate()
+        .flat_map(|(pos, result)| {
+            result
+                .votes()
+                .into_iter()
+                .map(move |vote| (*vote.from(), pos))
+        })
+        .collect();
+    let voters: Vec<Voter> = schema.voters(election_id).values().collect();
+    let voted: Vec<PublicKey> = voters
+        .iter()
+        .map(|voter| *voter.pub_key())
+        .filter(|voter| schema.vote(election_id, &voter.hash()).is_some())
+        .collect();
+    let delegations: Vec<(PublicKey, PublicKey)> =
+        schema.vote_delegations(election_id).iter().collect();
+
+    let mut added = vec![0u64; results.len()];
+    let mut delegated: Vec<(PublicKey, Vec<PublicKey>, u64)> = vec![];
+    for voter in voters.iter().filter(|voter| !voted.contains(voter.pub_key())) {
+        let delegate = match liquid::resolve(voter.pub_key(), &delegations, &voted) {
+            Some(delegate) => delegate,
+            None => continue,
+        };
+
+        let pos = match choices.iter().find(|choice| choice.0 == delegate) {
+            Some(choice) => choice.1,
+            None => {
+                println!("count_delegations: Vote of delegate {:?} is not counted", delegate);
+                continue;
+            }
+        };
+        added[pos] = added[pos]
+            .checked_add(voter.weight())
+            .ok_or(Error::WeightOverflow)?;
+
+        match delegated.iter_mut().find(|entry| entry.0 == delegate) {
+            Some(entry) => {
+                let weight = entry.2.checked_add(voter.weight());
+                entry.1.push(*voter.pub_key());
+                entry.2 = weight.ok_or(Error::WeightOverflow)?;
+            }
+            None => delegated.push((delegate, vec![*voter.pub_key()], voter.weight())),
+        }
+    }
+
+    let mut counted = Vec::with_capacity(results.len());
+    for (result, added) in results.iter().zip(added) {
+        let vote_num = result
+            .vote_num()
+            .checked_add(added)
+            .ok_or(Error::WeightOverflow)?;
+        counted.push(DecryptedCandidateResult::new(
+            result.candidate(),
+            result.votes(),
+            vote_num,
+        ));
+    }
+    *results = counted;
+
+    Ok(delegated
+        .into_iter()
+        .map(|(delegate, delegators, weight)| DelegatedWeight::new(&delegate, delegators, weight))
+        .collect())
+}
+
+/// Decrypts encrypted totals of candidates of the homomorphic election with shared elements,
+/// reconstructed from decryption shares. Individual ballots are never decrypted.
+fn decrypt_totals<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    shared: &[RistrettoPoint],
+) -> Result<Vec<DecryptedCandidateResult>, Error> {
+    let ballots_num = schema.ballots(election_id).keys().count() as u64;
+    let totals: Vec<(PublicKey, Ciphertext)> =
+        schema.encrypted_totals(election_id).iter().collect();
+
+    let mut results = vec![];
+    for candidate in schema.candidates(election_id).keys() {
+        let votes_num = match totals.iter().position(|total| total.0 == candidate) {
+            Some(pos) => {
+                let b = group::key_to_point(totals[pos].1.b()).ok_or(Error::TotalOutOfRange)?;
+                elgamal::discrete_log(&(b - shared[pos]), ballots_num)
+                    .ok_or(Error::TotalOutOfRange)?
+            }
+            None => 0,
+        };
+        results.push(DecryptedCandidateResult::new(&candidate, vec![], votes_num));
+    }
+
+    Ok(results)
+}
+
+/// Decrypts mixed votes of the mixnet election with shared elements, reconstructed
+/// from decryption shares, and counts them by candidate points.
+///
+/// Votes which do not decrypt to a point of a candidate are not counted.
+fn decrypt_mixed<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    shared: &[RistrettoPoint],
+) -> Vec<DecryptedCandidateResult> {
+    let mut results: Vec<(PublicKey, RistrettoPoint, u64)> = schema
+        .candidates(election_id)
+        .keys()
+        .map(|candidate| (candidate, mixnet::candidate_point(&candidate), 0))
+        .collect();
+
+    for (ciphertext, shared) in schema.mixed_votes(election_id).iter().zip(shared) {
+        let point = match group::key_to_point(ciphertext.b()) {
+            Some(b) => b - shared,
+            None => continue,
+        };
+
+        match results.iter_mut().find(|res| res.1 == point) {
+            Some(result) => result.2 += 1,
+            None => println!("decrypt_mixed: Vote is not for a candidate {:?}", ciphertext),
+        }
+    }
+
+    results
+        .into_iter()
+        .map(|(candidate, _, votes_num)| {
+            DecryptedCandidateResult::new(&candidate, vec![], votes_num)
+        })
+        .collect()
+}
+
+/// Counts revealed votes of the commit-reveal election.
+///
+/// Commitments which are not revealed are not counted, see `VoteServiceSchema::unrevealed`.
+fn count_reveals<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+) -> Vec<DecryptedCandidateResult> {
+    let reveals: Vec<(PublicKey, Reveal)> = schema.reveals(election_id).iter().collect();
+
+    schema
+        .candidates(election_id)
+        .keys()
+        .map(|candidate| {
+            let votes: Vec<Vote> = reveals
+                .iter()
+                .filter(|(_, reveal)| *reveal.candidate() == candidate)
+                .map(|(voter, _)| Vote::new(voter, &candidate))
+                .collect();
+            let votes_num = votes.len() as u64;
+            DecryptedCandidateResult::new(&candidate, votes, votes_num)
+        })
+        .collect()
+}
+
+/// Decrypts ranked votes of the election with shared elements, reconstructed from
+/// decryption shares.
+///
+/// Returns first preferences of the candidates and the rankings. Votes which can not
+/// be decrypted, are not cast by their voter or rank unknown candidates or the same
+/// candidate twice are not counted and are added to `rejected`. Withdrawn candidates
+/// are removed from the rankings and have no results, so their votes pass to the next
+/// preference.
+fn decrypt_ranked<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    shared: &[RistrettoPoint],
+    rejected: &mut Vec<RejectedVote>,
+) -> (Vec<DecryptedCandidateResult>, Vec<Vec<PublicKey>>) {
+    let candidates: Vec<PublicKey> = schema.candidates(election_id).keys().collect();
+    let withdrawn = withdrawn_candidates(schema, election_id);
+    let mut ballots: Vec<Vec<PublicKey>> = vec![];
+    for ((vote_id, enc_vote), shared) in schema.votes(election_id).iter().zip(shared) {
+        let vote = match cipher::decrypt_ranked_vote(&enc_vote, shared) {
+            Some(vote) => vote,
+            None => {
+                println!("decrypt_ranked: Failed to decrypt vote {:?}", vote_id);
+                rejected.push(RejectedVote::new(&vote_id, RejectionReason::Malformed as u8));
+                continue;
+            }
+        };
+
+        if vote.from().hash() != vote_id {
+            println!("decrypt_ranked: Vote is not cast by its voter {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::WrongVoter as u8));
+            continue;
+        }
+
+        let mut ranking = vote.ranking();
+        if !ranked::is_valid_ranking(&ranking, &candidates) {
+            println!("decrypt_ranked: Invalid ranking {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::InvalidChoice as u8));
+            continue;
+        }
+        ranking.retain(|candidate| !withdrawn.contains(candidate));
+        ballots.push(ranking);
+    }
+
+    let results = candidates
+        .iter()
+        .filter(|candidate| !withdrawn.contains(candidate))
+        .map(|candidate| {
+            let votes_num = ballots
+                .iter()
+                .filter(|ranking| ranking.first() == Some(candidate))
+                .count() as u64;
+            DecryptedCandidateResult::new(candidate, vec![], votes_num)
+        })
+        .collect();
+    (results, ballots)
+}
+
+/// Returns ids of the withdrawn candidates of the election.
+fn withdrawn_candidates<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+) -> Vec<PublicKey> {
+    schema
+        .candidates(election_id)
+        .values()
+        .filter(|candidate| candidate.withdrawn())
+        .map(|candidate| *candidate.pub_key())
+        .collect()
+}
+
+/// Stores pairwise preferences of the ranked ballots together with their Schulze
+/// and ranked pairs winners.
+fn store_pairwise_results(
+    schema: &mut VoteServiceSchema<&mut Fork>,
+    election_id: u64,
+    candidates: &[PublicKey],
+    ballots: &[Vec<PublicKey>],
+) {
+    let matrix = ranked::pairwise_preferences(candidates, ballots);
+    let schulze = ranked::schulze_winners(&matrix);
+    let ranked_pairs = ranked::ranked_pairs_winners(&matrix);
+    for (pos, (candidate, preferences)) in candidates.iter().zip(matrix).enumerate() {
+        let result = PairwiseResult::new(
+            candidate,
+            preferences,
+            schulze.contains(&pos),
+            ranked_pairs.contains(&pos),
+        );
+        println!("store_pairwise_results: Store pairwise result: {:?}", result);
+        schema.pairwise_results_mut(election_id).put(candidate, result);
+    }
+}
+
+/// Decrypts approval votes of the election with shared elements, reconstructed from
+/// decryption shares, and counts approvals of every candidate.
+///
+/// Votes which can not be decrypted, are not cast by their voter or approve unknown
+/// candidates or the same candidate twice are not counted and are added to `rejected`.
+fn decrypt_approvals<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    shared: &[RistrettoPoint],
+    rejected: &mut Vec<RejectedVote>,
+) -> Vec<ApprovalResult> {
+    let candidates: Vec<PublicKey> = schema.candidates(election_id).keys().collect();
+    let mut approvals = vec![0u64; candidates.len()];
+    for ((vote_id, enc_vote), shared) in schema.votes(election_id).iter().zip(shared) {
+        let vote = match cipher::decrypt_approval_vote(&enc_vote, shared) {
+            Some(vote) => vote,
+            None => {
+                println!("decrypt_approvals: Failed to decrypt vote {:?}", vote_id);
+                rejected.push(RejectedVote::new(&vote_id, RejectionReason::Malformed as u8));
+                continue;
+            }
+        };
+
+        if vote.from().hash() != vote_id {
+            println!("decrypt_approvals: Vote is not cast by its voter {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::WrongVoter as u8));
+            continue;
+        }
+
+        let approved = vote.approved();
+        if !is_valid_approval(&approved, &candidates) {
+            println!("decrypt_approvals: Invalid approval {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::InvalidChoice as u8));
+            continue;
+        }
+        for (candidate, count) in candidates.iter().zip(approvals.iter_mut()) {
+            if approved.contains(candidate) {
+                *count += 1;
+            }
+        }
+    }
+
+    candidates
+        .iter()
+        .zip(approvals)
+        .map(|(candidate, count)| ApprovalResult::new(candidate, count))
+        .collect()
+}
+
+/// Decrypts score votes of the election with shared elements, reconstructed from
+/// decryption shares, and sums up scores of every candidate.
+///
+/// Votes which can not be decrypted, are not cast by their voter or are not valid
+/// for the maximal score are not counted and are added to `rejected`. Candidates
+/// missing from a valid vote are scored zero.
+fn decrypt_scores<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election_id: u64,
+    shared: &[RistrettoPoint],
+    max_score: u8,
+    rejected: &mut Vec<RejectedVote>,
+) -> Vec<ScoreResult> {
+    let candidates: Vec<PublicKey> = schema.candidates(election_id).keys().collect();
+    let mut distributions = vec![vec![0u64; max_score as usize + 1]; candidates.len()];
+    for ((vote_id, enc_vote), shared) in schema.votes(election_id).iter().zip(shared) {
+        let vote = match cipher::decrypt_score_vote(&enc_vote, shared) {
+            Some(vote) => vote,
+            None => {
+                println!("decrypt_scores: Failed to decrypt vote {:?}", vote_id);
+                rejected.push(RejectedVote::new(&vote_id, RejectionReason::Malformed as u8));
+                continue;
+            }
+        };
+
+        if vote.from().hash() != vote_id {
+            println!("decrypt_scores: Vote is not cast by its voter {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::WrongVoter as u8));
+            continue;
+        }
+
+        let scores = vote.scores();
+        if !is_valid_score(&scores, &candidates, max_score) {
+            println!("decrypt_scores: Invalid scores {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::InvalidChoice as u8));
+            continue;
+        }
+        for (candidate, distribution) in candidates.iter().zip(distributions.iter_mut()) {
+            let score = scores
+                .iter()
+                .find(|score| score.candidate() == candidate)
+                .map_or(0, |score| score.score());
+            distribution[score as usize] += 1;
+        }
+    }
+
+    candidates
+        .iter()
+        .zip(distributions)
+        .map(|(candidate, distribution)| {
+            let total = distribution
+                .iter()
+                .enumerate()
+                .map(|(score, &count)| score as u64 * count)
+                .sum();
+            ScoreResult::new(candidate, total, distribution)
+        })
+        .collect()
+}
+
+/// Checks that the approval vote approves only candidates of the election,
+/// each of them at most once.
+fn is_valid_approval(approved: &[PublicKey], candidates: &[PublicKey]) -> bool {
+    approved.iter().enumerate().all(|(pos, candidate)| {
+        candidates.contains(candidate) && !approved[..pos].contains(candidate)
+    })
+}
+
+/// Checks that the score vote scores only candidates of the election, each of them
+/// at most once and not higher than the maximal score.
+fn is_valid_score(scores: &[CandidateScore], candidates: &[PublicKey], max_score: u8) -> bool {
+    scores.iter().enumerate().all(|(pos, score)| {
+        candidates.contains(score.candidate())
+            && score.score() <= max_score
+            && scores[..pos]
+                .iter()
+                .all(|other| other.candidate() != score.candidate())
+    })
+}
+
+/// Decrypts votes of the referendum with shared elements, reconstructed from
+/// decryption shares, and applies pass rule of the referendum to them.
+///
+/// Votes which can not be decrypted, are not cast by their voter or have unknown
+/// choice are not counted, neither for the majority nor for the quorum, and are added
+/// to `rejected`.
+fn decrypt_referendum<T: AsRef<dyn Snapshot>>(
+    schema: &VoteServiceSchema<T>,
+    election: &Election,
+    shared: &[RistrettoPoint],
+    rejected: &mut Vec<RejectedVote>,
+) -> ReferendumResult {
+    let election_id = election.id();
+    let (mut yes, mut no, mut abstain) = (0, 0, 0);
+    for ((vote_id, enc_vote), shared) in schema.votes(election_id).iter().zip(shared) {
+        let vote = match cipher::decrypt_referendum_vote(&enc_vote, shared) {
+            Some(vote) => vote,
+            None => {
+                println!("decrypt_referendum: Failed to decrypt vote {:?}", vote_id);
+                rejected.push(RejectedVote::new(&vote_id, RejectionReason::Malformed as u8));
+                continue;
+            }
+        };
+
+        if vote.from().hash() != vote_id {
+            println!("decrypt_referendum: Vote is not cast by its voter {:?}", vote);
+            rejected.push(RejectedVote::new(&vote_id, RejectionReason::WrongVoter as u8));
+            continue;
+        }
+
+        match ReferendumChoice::from_u8(vote.choice()) {
+            Some(ReferendumChoice::Yes) => yes += 1,
+            Some(ReferendumChoice::No) => no += 1,
+            Some(ReferendumChoice::Abstain) => abstain += 1,
+            None => {
+                println!("decrypt_referendum: Unknown choice {:?}", vote);
+                rejected.push(RejectedVote::new(&vote_id, RejectionReason::InvalidChoice as u8));
+            }
+        }
+    }
+
+    let registered = schema.voters(election_id).keys().count() as u64;
+    let quorum_reached = 100 * (yes + no + abstain) >= u64::from(election.quorum()) * registered;
+    let majority_reached = Majority::from_u8(election.majority())
+        .map_or(false, |majority| majority.is_reached(yes, no));
+    ReferendumResult::new(
+        yes,
+        no,
+        abstain,
+        registered,
+        quorum_reached,
+        quorum_reached && majority_reached,
+    )
 }
@@ -0,0 +1,47 @@
+use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use group;
+use schema::Ciphertext;
+
+/// Exponential ElGamal encryption.
+///
+/// Message `m` is encrypted as `(r * G, r * Y + m * G)`, where `Y` is the election
+/// public key. Ciphertexts are added component-wise, and the sum of ciphertexts
+/// decrypts to the sum of messages.
+
+/// Encrypts the message against the election public key with the specified randomness.
+pub fn encrypt(message: u64, election_key: &RistrettoPoint, randomness: &Scalar) -> Ciphertext {
+    let a = randomness * &RISTRETTO_BASEPOINT_TABLE;
+    let b = randomness * election_key + &Scalar::from(message) * &RISTRETTO_BASEPOINT_TABLE;
+    Ciphertext::new(&group::point_to_key(&a), &group::point_to_key(&b))
+}
+
+/// Returns whether both components of the ciphertext are valid group elements.
+pub fn is_valid(ciphertext: &Ciphertext) -> bool {
+    group::key_to_point(ciphertext.a()).is_some() && group::key_to_point(ciphertext.b()).is_some()
+}
+
+/// Adds ciphertexts component-wise.
+///
+/// Returns `None` if any of the ciphertexts is malformed.
+pub fn add(lhs: &Ciphertext, rhs: &Ciphertext) -> Option<Ciphertext> {
+    let a = group::key_to_point(lhs.a())? + group::key_to_point(rhs.a())?;
+    let b = group::key_to_point(lhs.b())? + group::key_to_point(rhs.b())?;
+    Some(Ciphertext::new(&group::point_to_key(&a), &group::point_to_key(&b)))
+}
+
+/// Finds `m` not greater than `max`, such that `point == m * G`.
+///
+/// Exhaustive search is fine, since totals never exceed the number of ballots.
+pub fn discrete_log(point: &RistrettoPoint, max: u64) -> Option<u64> {
+    let mut acc = RistrettoPoint::identity();
+    for message in 0..=max {
+        if acc == *point {
+            return Some(message);
+        }
+        acc += RISTRETTO_BASEPOINT_POINT;
+    }
+    None
+}
@@ -4,45 +4,413 @@ use exonum::blockchain::ExecutionError;
 
 /// Contract errors.
 
-/// Error codes emitted by transactions during execution.
-#[derive(Debug, Fail)]
-#[repr(u8)]
-pub enum Error {
-    /// Candidate already exists.
-    ///
-    /// Can be emitted by `TxCreateCandidate`.
-    #[fail(display = "Candidate already exists")]
-    CandidateAlreadyExists = 0,
-
-    /// Voter already exists.
-    ///
-    /// Can be emitted by `TxCreateVoter`.
-    #[fail(display = "Voter already exists")]
-    VoterAlreadyExists = 1,
-
-    /// Vote already exists.
-    ///
-    /// Can be emitted by `TxAddVote`.
-    #[fail(display = "Vote already exists")]
-    VoteAlreadyExists = 2,
-
-    /// Candidate not found.
-    ///
-    /// Can be emitted by `TxAddVote`.
-    #[fail(display = "Candidate not found")]
-    CandidateNotFound = 3,
-
-    /// Candidate vote result not found.
-    ///
-    /// Can be emitted by `TxAddVote`.
-    #[fail(display = "Candidate result not found")]
-    CandidateResultNotFound = 4,
-
-    /// Voter not found.
-    ///
-    /// Can be emitted by `TxAddVote`.
-    #[fail(display = "Voter not found")]
-    VoterNotFound = 5,
+/// Defines the error enum together with `from_code`, which finds a variant by its code,
+/// so the lookup can not miss a variant.
+macro_rules! contract_errors {
+    (
+        $(#[$attr:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_attr:meta])*
+                $variant:ident = $code:expr,
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        pub enum $name {
+            $(
+                $(#[$variant_attr])*
+                $variant = $code,
+            )*
+        }
+
+        impl $name {
+            /// Returns the error with the specified code, e.g. to name the error reported
+            /// by the node for a committed transaction.
+            pub fn from_code(code: u8) -> Option<$name> {
+                match code {
+                    $(code if code == $name::$variant as u8 => Some($name::$variant),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+contract_errors! {
+    /// Error codes emitted by transactions during execution.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Fail)]
+    #[repr(u8)]
+    pub enum Error {
+        /// Candidate already exists.
+        ///
+        /// Can be emitted by `TxCreateCandidate`.
+        #[fail(display = "Candidate already exists")]
+        CandidateAlreadyExists = 0,
+
+        /// Voter already exists.
+        ///
+        /// Can be emitted by `TxCreateVoter`.
+        #[fail(display = "Voter already exists")]
+        VoterAlreadyExists = 1,
+
+        /// Vote already exists.
+        ///
+        /// Can be emitted by transactions which cast votes, unless re-voting
+        /// is enabled for the election.
+        #[fail(display = "Vote already exists")]
+        VoteAlreadyExists = 2,
+
+        /// Candidate not found.
+        ///
+        /// Can be emitted by `TxRevealVote`, `TxWithdrawCandidate` or `TxUpdateCandidate`.
+        #[fail(display = "Candidate not found")]
+        CandidateNotFound = 3,
+
+        /// Voter not found.
+        ///
+        /// Can be emitted by `TxAddVote`, `TxAddBallot`, `TxSetVoterWeight`, `TxTransferVote`,
+        /// `TxRevokeVoter` or transactions which issue tokens.
+        #[fail(display = "Voter not found")]
+        VoterNotFound = 5,
+
+        /// Election already exists.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Election already exists")]
+        ElectionAlreadyExists = 6,
+
+        /// Election not found.
+        ///
+        /// Can be emitted by `TxCreateCandidate`, `TxCreateVoter` or `TxAddVote`.
+        #[fail(display = "Election not found")]
+        ElectionNotFound = 7,
+
+        /// Registration of the election is over.
+        ///
+        /// Can be emitted by `TxCreateCandidate`, `TxCreateVoter`, `TxSetVoterWeight`
+        /// or `TxRevokeVoter`.
+        #[fail(display = "Registration closed")]
+        RegistrationClosed = 8,
+
+        /// Voting of the election is not open yet.
+        ///
+        /// Can be emitted by transactions which cast votes.
+        #[fail(display = "Voting not open")]
+        VotingNotOpen = 9,
+
+        /// Voting of the election is over.
+        ///
+        /// Can be emitted by any transaction which casts, delegates a vote or issues a token,
+        /// by `TxWithdrawCandidate` or `TxUpdateCandidate`.
+        #[fail(display = "Voting closed")]
+        VotingClosed = 10,
+
+        /// Voting of the election is not over yet, or the reveal period of the commit-reveal
+        /// election is not over on tally.
+        ///
+        /// Can be emitted by `TxTallyElection`, `TxDecryptionShare`, `TxShuffle` or `TxRevealVote`.
+        #[fail(display = "Election not closed")]
+        ElectionNotClosed = 11,
+
+        /// Transaction is not signed by the election author.
+        ///
+        /// Can be emitted by `TxTallyElection`.
+        #[fail(display = "Not election author")]
+        NotElectionAuthor = 12,
+
+        /// Voting period of the election is empty.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid election period")]
+        InvalidElectionPeriod = 13,
+
+        /// Transaction is signed neither by the voter nor by the voter's delegate.
+        ///
+        /// Can be emitted by `TxAddVote`, `TxAddBallot` or `TxRequestToken`.
+        #[fail(display = "Vote is not signed by the voter")]
+        SignerNotVoter = 14,
+
+        /// Transaction is not signed by an authorized key.
+        ///
+        /// Can be emitted by `TxCreateElection`, `TxCreateCandidate`, `TxCreateVoter`,
+        /// `TxSetVoterWeight`, `TxWithdrawCandidate`, `TxUpdateCandidate`, `TxRevokeVoter`,
+        /// `TxDecryptionShare`, `TxShuffle`, `TxTokenCommitment` or `TxIssueToken`.
+        #[fail(display = "Unauthorized")]
+        Unauthorized = 15,
+
+        /// Validator already published decryption shares for the election.
+        ///
+        /// Can be emitted by `TxDecryptionShare`.
+        #[fail(display = "Decryption share already exists")]
+        DecryptionShareAlreadyExists = 16,
+
+        /// Decryption shares do not match the votes or the verification key of the validator.
+        ///
+        /// Can be emitted by `TxDecryptionShare` or `TxTallyElection`.
+        #[fail(display = "Invalid decryption share")]
+        InvalidDecryptionShare = 17,
+
+        /// Less than threshold of validators published decryption shares.
+        ///
+        /// Can be emitted by `TxTallyElection`.
+        #[fail(display = "Not enough decryption shares")]
+        NotEnoughDecryptionShares = 18,
+
+        /// One-time key of the encrypted vote is malformed.
+        ///
+        /// Can be emitted by transactions which cast encrypted votes.
+        #[fail(display = "Invalid vote key")]
+        InvalidVoteKey = 19,
+
+        /// Ballot mode of the election is unknown.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Unknown election mode")]
+        UnknownElectionMode = 20,
+
+        /// Transaction does not match ballot mode of the election.
+        ///
+        /// Can be emitted by transactions which cast votes or issue tokens,
+        /// by `TxCreateCandidate`, `TxTransferVote`, `TxShuffle` or `TxDecryptionShare`.
+        #[fail(display = "Wrong election mode")]
+        WrongElectionMode = 21,
+
+        /// Ballot does not have a valid ciphertext for every candidate, or encrypted ballot
+        /// is too long for the candidates of the election.
+        ///
+        /// Can be emitted by `TxAddBallot`, `TxAddMixVote`, `TxAddRankedVote`,
+        /// `TxAddApprovalVote`, `TxAddScoreVote` or `TxAddReferendumVote`.
+        #[fail(display = "Invalid ballot")]
+        InvalidBallot = 22,
+
+        /// Decrypted total of a candidate exceeds the number of ballots.
+        ///
+        /// Can be emitted by `TxTallyElection`.
+        #[fail(display = "Total out of range")]
+        TotalOutOfRange = 23,
+
+        /// Validity proofs of the ballot are invalid.
+        ///
+        /// Can be emitted by `TxAddVote`, `TxAddBallot` or `TxAddMixVote`.
+        #[fail(display = "Invalid ballot proof")]
+        InvalidBallotProof = 24,
+
+        /// Token issuer already published a commitment for the voter, which has not expired.
+        ///
+        /// Can be emitted by `TxTokenCommitment`.
+        #[fail(display = "Token commitment already exists")]
+        TokenCommitmentExists = 25,
+
+        /// Token issuer has not published a commitment for the voter yet.
+        ///
+        /// Can be emitted by `TxRequestToken` or `TxIssueToken`.
+        #[fail(display = "Token commitment not found")]
+        TokenCommitmentNotFound = 26,
+
+        /// Voter already requested a token.
+        ///
+        /// Can be emitted by `TxRequestToken`.
+        #[fail(display = "Token already requested")]
+        TokenAlreadyRequested = 27,
+
+        /// Voter has not requested a token yet.
+        ///
+        /// Can be emitted by `TxIssueToken`.
+        #[fail(display = "Token not requested")]
+        TokenNotRequested = 28,
+
+        /// Token issuer already signed the request of the voter.
+        ///
+        /// Can be emitted by `TxIssueToken`.
+        #[fail(display = "Token already issued")]
+        TokenAlreadyIssued = 29,
+
+        /// Response of the token issuer does not match the request.
+        ///
+        /// Can be emitted by `TxIssueToken`.
+        #[fail(display = "Invalid token response")]
+        InvalidTokenResponse = 30,
+
+        /// Token, its commitment or its request is malformed or not signed by the token issuer.
+        ///
+        /// Can be emitted by `TxTokenCommitment`, `TxRequestToken` or `TxAddAnonymousVote`.
+        #[fail(display = "Invalid token")]
+        InvalidToken = 31,
+
+        /// Token was already spent on a vote.
+        ///
+        /// Can be emitted by `TxAddAnonymousVote`.
+        #[fail(display = "Token already spent")]
+        TokenAlreadySpent = 32,
+
+        /// Ring signature of the vote is malformed or not made by a registered voter.
+        ///
+        /// Can be emitted by `TxAddRingVote`.
+        #[fail(display = "Invalid ring signature")]
+        InvalidRingSignature = 33,
+
+        /// Voter with the same key image already voted.
+        ///
+        /// Can be emitted by `TxAddRingVote`.
+        #[fail(display = "Key image already used")]
+        KeyImageAlreadyUsed = 34,
+
+        /// Validator already shuffled votes of the election.
+        ///
+        /// Can be emitted by `TxShuffle`.
+        #[fail(display = "Mix already exists")]
+        MixAlreadyExists = 35,
+
+        /// Shuffle is made for another stage of mixing, or mixing is already complete.
+        ///
+        /// Can be emitted by `TxShuffle`.
+        #[fail(display = "Wrong mix stage")]
+        WrongMixStage = 36,
+
+        /// Shuffle proof is invalid.
+        ///
+        /// Can be emitted by `TxShuffle`.
+        #[fail(display = "Invalid shuffle")]
+        InvalidShuffle = 37,
+
+        /// Not enough validators shuffled votes of the mixnet election yet.
+        ///
+        /// Can be emitted by `TxDecryptionShare` or `TxTallyElection`.
+        #[fail(display = "Mixing not complete")]
+        MixingNotComplete = 38,
+
+        /// Voter did not commit to a vote.
+        ///
+        /// Can be emitted by `TxRevealVote`.
+        #[fail(display = "Commitment not found")]
+        CommitmentNotFound = 39,
+
+        /// Vote of the voter is already revealed.
+        ///
+        /// Can be emitted by `TxRevealVote`.
+        #[fail(display = "Vote already revealed")]
+        VoteAlreadyRevealed = 40,
+
+        /// Revealed choice and salt do not match the commitment.
+        ///
+        /// Can be emitted by `TxRevealVote`.
+        #[fail(display = "Invalid reveal")]
+        InvalidReveal = 41,
+
+        /// Number of seats is zero, or more than one outside of single transferable vote mode.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid number of seats")]
+        InvalidSeats = 42,
+
+        /// Maximal score is zero in score mode, or is not zero in other modes.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid maximal score")]
+        InvalidMaxScore = 43,
+
+        /// Majority is unknown or quorum is above 100 percent in referendum mode,
+        /// or any of them is not zero in other modes.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid pass rule")]
+        InvalidPassRule = 44,
+
+        /// Weight of the voter is zero.
+        ///
+        /// Can be emitted by `TxCreateVoter` or `TxSetVoterWeight`.
+        #[fail(display = "Invalid voter weight")]
+        InvalidWeight = 45,
+
+        /// Transfer of the vote closes a cycle of delegations.
+        ///
+        /// Can be emitted by `TxTransferVote`.
+        #[fail(display = "Delegation cycle")]
+        DelegationCycle = 46,
+
+        /// Re-voting is enabled for the election mode which does not support it.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid re-voting option")]
+        InvalidRevoting = 47,
+
+        /// Candidate is withdrawn from the election.
+        ///
+        /// Can be emitted by `TxWithdrawCandidate`, `TxUpdateCandidate` or `TxRevealVote`.
+        #[fail(display = "Candidate withdrawn")]
+        CandidateWithdrawn = 48,
+
+        /// Registration of the voter is revoked.
+        ///
+        /// Can be emitted by `TxCreateVoter`, `TxRevokeVoter` or `TxAddVote`.
+        #[fail(display = "Voter revoked")]
+        VoterRevoked = 49,
+
+        /// Eligibility roll is committed for the election mode which does not support it.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid roll root")]
+        InvalidRollRoot = 50,
+
+        /// Merkle path does not prove that the voter is in the eligibility roll.
+        ///
+        /// Can be emitted by `TxAddVote`.
+        #[fail(display = "Voter not in roll")]
+        NotInRoll = 51,
+
+        /// Votes of the election must be encrypted, but the service configuration
+        /// has no election key.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Election key not found")]
+        ElectionKeyNotFound = 52,
+
+        /// Signing session of another voter's token is open.
+        ///
+        /// Can be emitted by `TxTokenCommitment`.
+        #[fail(display = "Token session open")]
+        TokenSessionOpen = 53,
+
+        /// The voter has not requested the token within `TOKEN_SESSION_BLOCKS`
+        /// after the commitment.
+        ///
+        /// Can be emitted by `TxRequestToken`.
+        #[fail(display = "Token session expired")]
+        TokenSessionExpired = 54,
+
+        /// Reveal period of the commit-reveal election is not after the voting period,
+        /// or the election of another mode has a reveal period.
+        ///
+        /// Can be emitted by `TxCreateElection`.
+        #[fail(display = "Invalid reveal period")]
+        InvalidRevealPeriod = 55,
+
+        /// Reveal period of the commit-reveal election is over.
+        ///
+        /// Can be emitted by `TxRevealVote`.
+        #[fail(display = "Reveal closed")]
+        RevealClosed = 56,
+
+        /// Weight of the voter is not 1, but only votes of encrypted elections are weighted.
+        ///
+        /// Can be emitted by `TxCreateVoter` or `TxSetVoterWeight`.
+        #[fail(display = "Weight not supported")]
+        WeightNotSupported = 57,
+
+        /// Sum of weights of votes for a candidate does not fit into `u64`.
+        ///
+        /// Can be emitted by `TxTallyElection`.
+        #[fail(display = "Weight overflow")]
+        WeightOverflow = 58,
+
+        /// One-time key of the encrypted vote is already used by another vote of the election,
+        /// e.g. the vote is copied from another voter or replayed after re-voting.
+        ///
+        /// Can be emitted by transactions which cast encrypted votes.
+        #[fail(display = "Vote key already used")]
+        VoteKeyAlreadyUsed = 59,
+    }
 }
 
 impl From<Error> for ExecutionError {
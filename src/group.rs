@@ -0,0 +1,53 @@
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use exonum::crypto::{Hash, PublicKey};
+use rand::rngs::OsRng;
+use sha2::Sha512;
+
+/// Ristretto group helpers.
+///
+/// Points and scalars are persisted as 32-byte `PublicKey` and `Hash` values,
+/// so they can be used in transactions and schema structs.
+
+/// Converts a group element to its persisted representation.
+pub fn point_to_key(point: &RistrettoPoint) -> PublicKey {
+    PublicKey::new(point.compress().to_bytes())
+}
+
+/// Converts persisted representation to a group element.
+///
+/// Returns `None` if the bytes are not a valid encoding of a group element.
+pub fn key_to_point(key: &PublicKey) -> Option<RistrettoPoint> {
+    CompressedRistretto::from_slice(key.as_ref()).decompress()
+}
+
+/// Converts a scalar to its persisted representation.
+pub fn scalar_to_hash(scalar: &Scalar) -> Hash {
+    Hash::new(scalar.to_bytes())
+}
+
+/// Converts persisted representation to a scalar.
+///
+/// Returns `None` if the bytes are not a canonical encoding of a scalar.
+pub fn hash_to_scalar(hash: &Hash) -> Option<Scalar> {
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(hash.as_ref());
+    Scalar::from_canonical_bytes(bytes)
+}
+
+/// Generates a random scalar.
+pub fn random_scalar() -> Scalar {
+    let mut rng = OsRng::new().expect("group::random_scalar: failed to init rng");
+    Scalar::random(&mut rng)
+}
+
+/// Hashes a domain separation tag and group elements to a scalar.
+///
+/// Used as the Fiat-Shamir challenge of non-interactive proofs.
+pub fn challenge(tag: &[u8], points: &[&RistrettoPoint]) -> Scalar {
+    let mut bytes = tag.to_vec();
+    for point in points {
+        bytes.extend_from_slice(point.compress().as_bytes());
+    }
+    Scalar::hash_from_bytes::<Sha512>(&bytes)
+}
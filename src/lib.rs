@@ -6,48 +6,170 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate byteorder;
+extern crate curve25519_dalek;
+extern crate rand;
+extern crate reqwest;
 extern crate ring;
 extern crate serde_json;
+extern crate sha2;
 extern crate toml;
-#[macro_use]
-extern crate lazy_static;
 #[cfg(test)]
 extern crate exonum_testkit;
-extern crate untrusted;
 
 pub mod agreement;
 pub mod api;
+pub mod blind;
 pub mod cipher;
 pub mod cmd;
 pub mod config;
 pub mod contracts;
+pub mod elgamal;
 pub mod errors;
+pub mod group;
+pub mod liquid;
+pub mod lsag;
+pub mod mixnet;
+pub mod proofs;
+pub mod ranked;
+pub mod roll;
 pub mod schema;
 #[cfg(test)]
 pub mod tests;
+pub mod threshold;
 pub mod transactions;
+pub mod zkp;
 
 use api::VoteServiceApi;
 use config::VoteServiceConfig;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::scalar::Scalar;
 use exonum::encoding::serialize::json::reexport::Value;
+use exonum::encoding::serialize::FromHex;
 use exonum::{
     api::ServiceApiBuilder,
-    blockchain::{Service, Transaction, TransactionSet},
+    blockchain::{Service, ServiceContext, Transaction, TransactionSet},
     crypto::Hash,
     encoding,
     messages::RawTransaction,
+    node::TransactionSend,
     storage::{Fork, Snapshot},
 };
+use schema::{Election, ElectionPhase, VoteServiceSchema};
 use serde_json::to_value;
-use transactions::VoteTransactions;
+use threshold::KeyShare;
+use transactions::{TxDecryptionShare, TxShuffle, VoteTransactions};
 
 /// Service ID for the `Service` trait.
 pub const SERVICE_ID: u16 = 42;
 
 /// Exonum `Service` implementation.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct VoteService {
     config: VoteServiceConfig,
+    key_shares: Vec<Scalar>,
+}
+
+impl VoteService {
+    /// Creates a new service with the specified node configuration.
+    ///
+    /// Panics if a configured key share is malformed or no administrators are configured,
+    /// as elections could never be created then.
+    pub fn new(config: VoteServiceConfig) -> Self {
+        assert!(
+            !config.administrators.is_empty(),
+            "At least one administrator is required"
+        );
+        let key_shares = config
+            .key_shares
+            .iter()
+            .map(|key_share| {
+                let bytes = Hash::from_hex(key_share).expect("Invalid key share");
+                group::hash_to_scalar(&bytes).expect("Invalid key share")
+            })
+            .collect();
+
+        VoteService { config, key_shares }
+    }
+
+    /// Returns transaction with decryption shares of the validator for the election,
+    /// unless votes of the election are not encrypted, the node has no share
+    /// of the election key or the shares are already published.
+    /// The share is found by its verification key stored with the election.
+    fn decryption_share_tx(
+        &self,
+        schema: &VoteServiceSchema<&dyn Snapshot>,
+        context: &ServiceContext,
+        index: u16,
+        election: &Election,
+    ) -> Option<TxDecryptionShare> {
+        if !election.election_mode()?.is_encrypted() {
+            return None;
+        }
+
+        let verification_key = election
+            .verification_keys()
+            .get(index as usize - 1)
+            .and_then(group::key_to_point)?;
+        let secret = *self
+            .key_shares
+            .iter()
+            .find(|secret| *secret * &RISTRETTO_BASEPOINT_TABLE == verification_key)?;
+        if schema
+            .decryption_share(election.id(), context.public_key())
+            .is_some()
+        {
+            return None;
+        }
+
+        let key_share = KeyShare { index, secret };
+        let targets = schema.decryption_targets(election);
+        let partials = match threshold::partial_decryptions(&key_share, &targets) {
+            Some(partials) => partials,
+            None => {
+                println!(
+                    "VoteService::decryption_share_tx: Invalid ciphertext in election {}",
+                    election.id()
+                );
+                return None;
+            }
+        };
+        Some(TxDecryptionShare::new(
+            context.public_key(),
+            election.id(),
+            partials,
+            context.secret_key(),
+        ))
+    }
+
+    /// Returns transaction with a shuffle of the votes of the mixnet election
+    /// for the next stage of mixing, if the turn of the validator starts at this height,
+    /// see `VoteServiceSchema::mixer`.
+    fn shuffle_tx(
+        &self,
+        schema: &VoteServiceSchema<&dyn Snapshot>,
+        context: &ServiceContext,
+        election: &Election,
+    ) -> Option<TxShuffle> {
+        if schema.mixer(election, schema.current_height()) != Some(*context.public_key()) {
+            return None;
+        }
+
+        let election_id = election.id();
+        let mixes = schema.mixes(election_id);
+
+        let election_key = group::key_to_point(election.election_key())?;
+        let stage = mixes.len() as u16;
+        let input = schema.mixed_votes(election_id);
+        let (ciphertexts, proof) = mixnet::shuffle(&election_key, election_id, stage, &input)?;
+        Some(TxShuffle::new(
+            context.public_key(),
+            election_id,
+            stage,
+            ciphertexts,
+            proof,
+            context.secret_key(),
+        ))
+    }
 }
 
 impl Service for VoteService {
@@ -64,8 +186,9 @@ impl Service for VoteService {
         Ok(tx.into())
     }
 
-    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
-        vec![]
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        let schema = VoteServiceSchema::new(snapshot);
+        schema.state_hash()
     }
 
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
@@ -73,7 +196,47 @@ impl Service for VoteService {
     }
 
     fn initialize(&self, _fork: &mut Fork) -> Value {
-        to_value(self.config.clone()).unwrap()
+        // Key share is node-local and must not get into the genesis block.
+        let mut config = self.config.clone();
+        config.key_shares.clear();
+        to_value(config).unwrap()
+    }
+
+    /// Shuffles votes of every mixnet election, which voting is over, in the turn
+    /// of the validator, and then publishes decryption shares of the validator
+    /// for every such election. Decryption shares are sent until they get into the blockchain.
+    fn after_commit(&self, context: &ServiceContext) {
+        let index = match context.validator_id() {
+            Some(validator_id) => validator_id.0 + 1,
+            None => return,
+        };
+
+        let schema = VoteServiceSchema::new(context.snapshot());
+        let height = schema.current_height();
+        for election in schema.elections().values() {
+            if election.phase(height) != ElectionPhase::Closed {
+                continue;
+            }
+
+            let tx: Box<dyn Transaction> = if !schema.is_mixing_complete(&election) {
+                match self.shuffle_tx(&schema, context, &election) {
+                    Some(tx) => Box::new(tx),
+                    None => continue,
+                }
+            } else {
+                match self.decryption_share_tx(&schema, context, index, &election) {
+                    Some(tx) => Box::new(tx),
+                    None => continue,
+                }
+            };
+
+            if let Err(e) = context.transaction_sender().send(tx) {
+                println!(
+                    "VoteService::after_commit: Failed to send transaction: {}",
+                    e
+                );
+            }
+        }
     }
 }
 
@@ -107,13 +270,6 @@ impl fabric::ServiceFactory for ServiceFactory {
                 .try_into()
                 .unwrap();
 
-        // Initializing agreement module
-        let author_key = service_config.author_public_key.unwrap();
-        let author_key = author_key.as_ref();
-        agreement::init_ephemeral(author_key);
-
-        Box::new(VoteService {
-            config: service_config,
-        })
+        Box::new(VoteService::new(service_config))
     }
 }
@@ -0,0 +1,67 @@
+use exonum::crypto::PublicKey;
+
+/// Liquid democracy.
+///
+/// A voter may delegate the vote to another voter of the election, who may delegate it
+/// further. The vote counts for the choice of the first voter in the chain of delegations
+/// who voted directly, a direct vote of the voter overrides the delegation.
+
+/// Returns the voter who votes on behalf of the specified one: the first voter in the chain
+/// of delegations who voted directly.
+///
+/// Returns `None` if the voter did not delegate the vote, or the chain ends or runs into
+/// a cycle before reaching a direct vote.
+pub fn resolve(
+    voter: &PublicKey,
+    delegations: &[(PublicKey, PublicKey)],
+    voted: &[PublicKey],
+) -> Option<PublicKey> {
+    let mut visited = vec![*voter];
+    let mut current = *voter;
+    loop {
+        let next = delegate_of(&current, delegations)?;
+        if voted.contains(&next) {
+            return Some(next);
+        }
+
+        if visited.contains(&next) {
+            return None;
+        }
+        visited.push(next);
+        current = next;
+    }
+}
+
+/// Checks whether delegation of the vote of the voter to the delegate closes a cycle
+/// of delegations.
+pub fn creates_cycle(
+    voter: &PublicKey,
+    delegate: &PublicKey,
+    delegations: &[(PublicKey, PublicKey)],
+) -> bool {
+    let mut visited = vec![];
+    let mut current = *delegate;
+    loop {
+        if current == *voter {
+            return true;
+        }
+
+        if visited.contains(&current) {
+            return false;
+        }
+        visited.push(current);
+
+        current = match delegate_of(&current, delegations) {
+            Some(next) => next,
+            None => return false,
+        };
+    }
+}
+
+/// Returns the voter the vote is delegated to.
+fn delegate_of(voter: &PublicKey, delegations: &[(PublicKey, PublicKey)]) -> Option<PublicKey> {
+    delegations
+        .iter()
+        .find(|(from, _)| from == voter)
+        .map(|(_, to)| *to)
+}
@@ -0,0 +1,195 @@
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use exonum::crypto::{CryptoHash, Hash, PublicKey, SecretKey};
+use group;
+use sha2::{Digest, Sha512};
+
+/// Linkable ring signatures (LSAG).
+///
+/// A voter signs with the Ed25519 key the voter is registered with, and the signature shows
+/// only that the signer is one of the registered voters. Every signature carries
+/// the key image `I = x * Hp(P)`, which is the same for all signatures of the voter,
+/// so a second vote of the voter is detected without revealing who cast either of them.
+/// Key images are Ristretto points, `Hp` hashes a voter key to a Ristretto point.
+
+/// Domain separation tag of ring signature challenges.
+const RING_TAG: &[u8] = b"voteservice.ring";
+
+/// Domain separation tag of hashing voter keys to points.
+const KEY_IMAGE_TAG: &[u8] = b"voteservice.key_image";
+
+/// Linkable ring signature, ready to be sent in `TxAddRingVote`.
+#[derive(Clone, Debug)]
+pub struct RingSignature {
+    /// Key image of the signer.
+    pub key_image: PublicKey,
+    /// Challenge of the first ring member.
+    pub challenge: Hash,
+    /// Responses of all ring members, in order of the ring.
+    pub responses: Vec<Hash>,
+}
+
+/// Returns key image of the voter with the specified secret key.
+pub fn key_image(secret_key: &SecretKey) -> PublicKey {
+    let secret = secret_scalar(secret_key);
+    let public = PublicKey::new((&secret * &ED25519_BASEPOINT_TABLE).compress().to_bytes());
+    group::point_to_key(&(secret * hash_to_point(&public)))
+}
+
+/// Signs the message with the secret key of a voter on behalf of the ring of voter keys.
+///
+/// Runs on the client side. Keys which are not valid Ed25519 points are not members
+/// of the ring. Returns `None` if the signer key is not a member of the ring.
+pub fn sign(
+    ring: &[PublicKey],
+    secret_key: &SecretKey,
+    election_id: u64,
+    message: &PublicKey,
+) -> Option<RingSignature> {
+    let members = decode_ring(ring);
+    let secret = secret_scalar(secret_key);
+    let public = (&secret * &ED25519_BASEPOINT_TABLE).compress();
+    let signer = members
+        .iter()
+        .position(|member| member.0.as_ref() == public.as_bytes())?;
+
+    let prefix = challenge_prefix(&members, election_id, message);
+    let key_image = secret * members[signer].2;
+    let size = members.len();
+
+    let mut challenges = vec![Scalar::zero(); size];
+    let mut responses: Vec<Scalar> = (0..size).map(|_| group::random_scalar()).collect();
+
+    let nonce = group::random_scalar();
+    challenges[(signer + 1) % size] = challenge(
+        &prefix,
+        &(&nonce * &ED25519_BASEPOINT_TABLE),
+        &(nonce * members[signer].2),
+    );
+
+    let mut pos = (signer + 1) % size;
+    while pos != signer {
+        let (commitment, image_commitment) =
+            commitments(&members[pos], &key_image, &challenges[pos], &responses[pos]);
+        challenges[(pos + 1) % size] = challenge(&prefix, &commitment, &image_commitment);
+        pos = (pos + 1) % size;
+    }
+    responses[signer] = nonce - challenges[signer] * secret;
+
+    Some(RingSignature {
+        key_image: group::point_to_key(&key_image),
+        challenge: group::scalar_to_hash(&challenges[0]),
+        responses: responses.iter().map(group::scalar_to_hash).collect(),
+    })
+}
+
+/// Checks the ring signature of the message.
+pub fn verify(
+    ring: &[PublicKey],
+    election_id: u64,
+    message: &PublicKey,
+    key_image: &PublicKey,
+    challenge_zero: &Hash,
+    responses: &[Hash],
+) -> bool {
+    let members = decode_ring(ring);
+    if members.is_empty() || members.len() != responses.len() {
+        return false;
+    }
+
+    let (key_image, challenge_zero) = match (
+        group::key_to_point(key_image),
+        group::hash_to_scalar(challenge_zero),
+    ) {
+        (Some(key_image), Some(challenge)) => (key_image, challenge),
+        _ => return false,
+    };
+
+    let prefix = challenge_prefix(&members, election_id, message);
+    let mut current = challenge_zero;
+    for (member, response) in members.iter().zip(responses) {
+        let response = match group::hash_to_scalar(response) {
+            Some(response) => response,
+            None => return false,
+        };
+        let (commitment, image_commitment) =
+            commitments(member, &key_image, &current, &response);
+        current = challenge(&prefix, &commitment, &image_commitment);
+    }
+
+    current == challenge_zero
+}
+
+/// Decodes voter keys, skipping keys which are not valid points of the prime order subgroup.
+fn decode_ring(ring: &[PublicKey]) -> Vec<(PublicKey, EdwardsPoint, RistrettoPoint)> {
+    ring.iter()
+        .filter_map(|key| {
+            let point = CompressedEdwardsY::from_slice(key.as_ref()).decompress()?;
+            if point.is_torsion_free() {
+                Some((*key, point, hash_to_point(key)))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Computes commitments of the ring member for the challenge and the response.
+fn commitments(
+    member: &(PublicKey, EdwardsPoint, RistrettoPoint),
+    key_image: &RistrettoPoint,
+    challenge: &Scalar,
+    response: &Scalar,
+) -> (EdwardsPoint, RistrettoPoint) {
+    (
+        response * &ED25519_BASEPOINT_TABLE + challenge * member.1,
+        response * member.2 + challenge * key_image,
+    )
+}
+
+/// Hashes the ring, the election and the message, which every challenge is bound to.
+fn challenge_prefix(
+    members: &[(PublicKey, EdwardsPoint, RistrettoPoint)],
+    election_id: u64,
+    message: &PublicKey,
+) -> Vec<u8> {
+    let mut prefix = RING_TAG.to_vec();
+    prefix.extend_from_slice(election_id.hash().as_ref());
+    prefix.extend_from_slice(message.as_ref());
+    for member in members {
+        prefix.extend_from_slice(member.0.as_ref());
+    }
+    prefix
+}
+
+/// Computes challenge of the next ring member.
+fn challenge(
+    prefix: &[u8],
+    commitment: &EdwardsPoint,
+    image_commitment: &RistrettoPoint,
+) -> Scalar {
+    let mut bytes = prefix.to_vec();
+    bytes.extend_from_slice(commitment.compress().as_bytes());
+    bytes.extend_from_slice(image_commitment.compress().as_bytes());
+    Scalar::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// Hashes a voter key to a Ristretto point.
+fn hash_to_point(key: &PublicKey) -> RistrettoPoint {
+    let mut bytes = KEY_IMAGE_TAG.to_vec();
+    bytes.extend_from_slice(key.as_ref());
+    RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// Derives the Ed25519 secret scalar from the secret key, the first half of which is the seed.
+fn secret_scalar(secret_key: &SecretKey) -> Scalar {
+    let hash = Sha512::digest(&secret_key.as_ref()[..32]);
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&hash[..32]);
+    bytes[0] &= 248;
+    bytes[31] &= 127;
+    bytes[31] |= 64;
+    Scalar::from_bytes_mod_order(bytes)
+}
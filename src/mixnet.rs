@@ -0,0 +1,435 @@
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use exonum::crypto::{CryptoHash, Hash, PublicKey};
+use group;
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use schema::{Ciphertext, ShuffleProof};
+use sha2::{Digest, Sha512};
+
+/// Verifiable re-encryption mixnet.
+///
+/// A vote is an ElGamal ciphertext `(r * G, r * Y + M)` of the point `M` of the chosen
+/// candidate. After voting is over, validators one by one re-encrypt and permute
+/// the list of votes, so the decrypted list can not be linked to voters.
+///
+/// Every shuffle carries a proof of Terelius and Wikström: the mixer commits to the
+/// permutation with Pedersen commitments and proves, for a challenge vector `u` derived
+/// from the commitment, that the commitment opens to a permutation matrix and that
+/// the output combined with the permuted `u` is a re-encryption of the input combined
+/// with `u`. The proof consists of 4 elements per ciphertext and a few constant ones,
+/// so a shuffle with its proof takes about 192 bytes per vote and shuffles of a few
+/// thousand votes fit into a transaction.
+
+/// Decoded ciphertext.
+type Pair = (RistrettoPoint, RistrettoPoint);
+
+/// Domain separation tag of candidate points.
+const CANDIDATE_TAG: &[u8] = b"voteservice.mixnet.candidate";
+
+/// Domain separation tag of vote proofs.
+const VOTE_PROOF_TAG: &[u8] = b"voteservice.mixnet.vote";
+
+/// Domain separation tag of shuffle proofs.
+const SHUFFLE_PROOF_TAG: &[u8] = b"voteservice.mixnet.shuffle";
+
+/// Domain separation tag of generators of permutation commitments.
+const GENERATOR_TAG: &[u8] = b"voteservice.mixnet.generator";
+
+/// Encrypted vote with the proof of knowledge of its randomness,
+/// ready to be sent in `TxAddMixVote`.
+#[derive(Clone, Debug)]
+pub struct MixVote {
+    /// Ciphertext of the candidate point.
+    pub ciphertext: Ciphertext,
+    /// Challenge of the proof of knowledge.
+    pub challenge: Hash,
+    /// Response of the proof of knowledge.
+    pub response: Hash,
+}
+
+/// Returns the point the vote for the candidate is encoded with.
+pub fn candidate_point(candidate: &PublicKey) -> RistrettoPoint {
+    let mut bytes = CANDIDATE_TAG.to_vec();
+    bytes.extend_from_slice(candidate.as_ref());
+    RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// Encrypts the vote of the voter for the candidate and proves knowledge of its randomness,
+/// so the ciphertext can not be copied from another voter.
+///
+/// Runs on the client side.
+pub fn encrypt_vote(
+    candidate: &PublicKey,
+    election_key: &PublicKey,
+    voter_id: &PublicKey,
+) -> MixVote {
+    let election_key =
+        group::key_to_point(election_key).expect("mixnet::encrypt_vote: invalid election key");
+
+    let randomness = group::random_scalar();
+    let a = &randomness * &RISTRETTO_BASEPOINT_TABLE;
+    let b = randomness * election_key + candidate_point(candidate);
+
+    let nonce = group::random_scalar();
+    let challenge = group::challenge(
+        &tag(VOTE_PROOF_TAG, voter_id),
+        &[&a, &b, &(&nonce * &RISTRETTO_BASEPOINT_TABLE)],
+    );
+    let response = nonce + challenge * randomness;
+
+    MixVote {
+        ciphertext: Ciphertext::new(&group::point_to_key(&a), &group::point_to_key(&b)),
+        challenge: group::scalar_to_hash(&challenge),
+        response: group::scalar_to_hash(&response),
+    }
+}
+
+/// Checks the proof of knowledge of randomness of the vote of the voter.
+pub fn verify_vote(
+    voter_id: &PublicKey,
+    ciphertext: &Ciphertext,
+    challenge: &Hash,
+    response: &Hash,
+) -> bool {
+    let values = (
+        group::key_to_point(ciphertext.a()),
+        group::key_to_point(ciphertext.b()),
+        group::hash_to_scalar(challenge),
+        group::hash_to_scalar(response),
+    );
+    let (a, b, challenge, response) = match values {
+        (Some(a), Some(b), Some(c), Some(s)) => (a, b, c, s),
+        _ => return false,
+    };
+
+    let commitment = &response * &RISTRETTO_BASEPOINT_TABLE - challenge * a;
+    group::challenge(&tag(VOTE_PROOF_TAG, voter_id), &[&a, &b, &commitment]) == challenge
+}
+
+/// Re-encrypts and permutes the ciphertexts and proves correctness of the shuffle.
+///
+/// Runs on the validator side. Returns `None` if any of the ciphertexts is malformed.
+pub fn shuffle(
+    election_key: &RistrettoPoint,
+    election_id: u64,
+    stage: u16,
+    input: &[Ciphertext],
+) -> Option<(Vec<Ciphertext>, ShuffleProof)> {
+    let input = decode(input)?;
+    let (permutation, randomness) = random_shuffle(input.len());
+    let output = apply(election_key, &input, &permutation, &randomness);
+
+    let len = input.len();
+    let (h, generators) = generators(len);
+    let tag = statement_tag(election_id, stage, &input, &output);
+
+    // Commitment to the permutation: c[permutation[i]] = r[permutation[i]] * G + H_i.
+    let commitment_rand = random_scalars(len);
+    let mut commitments = vec![RistrettoPoint::identity(); len];
+    for (generator, &source) in generators.iter().zip(&permutation) {
+        let source = source as usize;
+        commitments[source] = &commitment_rand[source] * &RISTRETTO_BASEPOINT_TABLE + generator;
+    }
+
+    let (seed, u) = challenge_vector(&tag, &commitments);
+    let permuted_u: Vec<Scalar> = permutation.iter().map(|&source| u[source as usize]).collect();
+
+    // Chain of commitments to the permuted challenge vector, starting from H.
+    let chain_rand = random_scalars(len);
+    let mut chain = Vec::with_capacity(len);
+    let mut previous = h;
+    for (r, u) in chain_rand.iter().zip(&permuted_u) {
+        previous = r * &RISTRETTO_BASEPOINT_TABLE + u * previous;
+        chain.push(previous);
+    }
+
+    // Aggregated randomness the responses are computed for.
+    let sum_rand = commitment_rand.iter().fold(Scalar::zero(), |acc, r| acc + r);
+    let mut chain_sum_rand = Scalar::zero();
+    let mut tail = Scalar::one();
+    for (r, u) in chain_rand.iter().zip(&permuted_u).rev() {
+        chain_sum_rand += r * tail;
+        tail *= u;
+    }
+    let weighted_rand = inner_product(&commitment_rand, &u);
+    let reencryption_rand = inner_product(&randomness, &permuted_u);
+
+    let nonces = random_scalars(4);
+    let chain_nonces = random_scalars(len);
+    let permuted_nonces = random_scalars(len);
+    let t = [
+        &nonces[0] * &RISTRETTO_BASEPOINT_TABLE,
+        &nonces[1] * &RISTRETTO_BASEPOINT_TABLE,
+        &nonces[2] * &RISTRETTO_BASEPOINT_TABLE + combine(&permuted_nonces, &generators),
+        combine(&permuted_nonces, &firsts(&output)) - &nonces[3] * &RISTRETTO_BASEPOINT_TABLE,
+        combine(&permuted_nonces, &seconds(&output)) - nonces[3] * election_key,
+    ];
+    let chain_t: Vec<RistrettoPoint> = chain_nonces
+        .iter()
+        .zip(&permuted_nonces)
+        .zip(previous_links(h, &chain))
+        .map(|((w, w_prime), previous)| w * &RISTRETTO_BASEPOINT_TABLE + w_prime * previous)
+        .collect();
+
+    let challenge = final_challenge(&seed, &chain, &t, &chain_t);
+    let responses = [
+        nonces[0] + challenge * sum_rand,
+        nonces[1] + challenge * chain_sum_rand,
+        nonces[2] + challenge * weighted_rand,
+        nonces[3] + challenge * reencryption_rand,
+    ];
+    let chain_responses: Vec<Scalar> = chain_nonces
+        .iter()
+        .zip(&chain_rand)
+        .map(|(w, r)| w + challenge * r)
+        .collect();
+    let permuted_responses: Vec<Scalar> = permuted_nonces
+        .iter()
+        .zip(&permuted_u)
+        .map(|(w, u)| w + challenge * u)
+        .collect();
+
+    let proof = ShuffleProof::new(
+        commitments.iter().map(group::point_to_key).collect(),
+        chain.iter().map(group::point_to_key).collect(),
+        &group::scalar_to_hash(&challenge),
+        responses.iter().map(group::scalar_to_hash).collect(),
+        chain_responses.iter().map(group::scalar_to_hash).collect(),
+        permuted_responses.iter().map(group::scalar_to_hash).collect(),
+    );
+    Some((encode(&output), proof))
+}
+
+/// Checks the proof that the output is a re-encryption of a permutation of the input.
+pub fn verify_shuffle(
+    election_key: &RistrettoPoint,
+    election_id: u64,
+    stage: u16,
+    input: &[Ciphertext],
+    output: &[Ciphertext],
+    proof: &ShuffleProof,
+) -> bool {
+    check_shuffle(election_key, election_id, stage, input, output, proof).unwrap_or(false)
+}
+
+/// Checks the shuffle proof, returns `None` if any of its elements is malformed.
+fn check_shuffle(
+    election_key: &RistrettoPoint,
+    election_id: u64,
+    stage: u16,
+    input: &[Ciphertext],
+    output: &[Ciphertext],
+    proof: &ShuffleProof,
+) -> Option<bool> {
+    let len = input.len();
+    let input = decode(input)?;
+    let output = decode(output)?;
+    let commitments = decode_points(&proof.permutation_commitments(), len)?;
+    let chain = decode_points(&proof.chain_commitments(), len)?;
+    let challenge = group::hash_to_scalar(proof.challenge())?;
+    let responses = decode_scalars(&proof.responses(), 4)?;
+    let chain_responses = decode_scalars(&proof.chain_responses(), len)?;
+    let permuted_responses = decode_scalars(&proof.permuted_responses(), len)?;
+    if output.len() != len {
+        return Some(false);
+    }
+
+    let (h, generators) = generators(len);
+    let tag = statement_tag(election_id, stage, &input, &output);
+    let (seed, u) = challenge_vector(&tag, &commitments);
+
+    let sum_commitments = commitments.iter().fold(RistrettoPoint::identity(), |acc, c| acc + c);
+    let sum_generators = generators.iter().fold(RistrettoPoint::identity(), |acc, h| acc + h);
+    let product_u = u.iter().fold(Scalar::one(), |acc, u| acc * u);
+    let last = *chain.last().unwrap_or(&h);
+
+    let t = [
+        &responses[0] * &RISTRETTO_BASEPOINT_TABLE
+            - challenge * (sum_commitments - sum_generators),
+        &responses[1] * &RISTRETTO_BASEPOINT_TABLE - challenge * (last - product_u * h),
+        &responses[2] * &RISTRETTO_BASEPOINT_TABLE + combine(&permuted_responses, &generators)
+            - challenge * combine(&u, &commitments),
+        combine(&permuted_responses, &firsts(&output))
+            - &responses[3] * &RISTRETTO_BASEPOINT_TABLE
+            - challenge * combine(&u, &firsts(&input)),
+        combine(&permuted_responses, &seconds(&output))
+            - responses[3] * election_key
+            - challenge * combine(&u, &seconds(&input)),
+    ];
+    let chain_t: Vec<RistrettoPoint> = chain_responses
+        .iter()
+        .zip(&permuted_responses)
+        .zip(previous_links(h, &chain).zip(&chain))
+        .map(|((s, s_prime), (previous, current))| {
+            s * &RISTRETTO_BASEPOINT_TABLE + s_prime * previous - challenge * current
+        })
+        .collect();
+
+    Some(final_challenge(&seed, &chain, &t, &chain_t) == challenge)
+}
+
+/// Returns a random permutation of the specified length with re-encryption randomness.
+fn random_shuffle(len: usize) -> (Vec<u32>, Vec<Scalar>) {
+    let mut rng = OsRng::new().expect("mixnet::random_shuffle: failed to init rng");
+    let mut permutation: Vec<u32> = (0..len as u32).collect();
+    permutation.shuffle(&mut rng);
+    (permutation, random_scalars(len))
+}
+
+/// Computes `out[i] = in[permutation[i]] + (randomness[i] * G, randomness[i] * Y)`.
+fn apply(
+    election_key: &RistrettoPoint,
+    input: &[Pair],
+    permutation: &[u32],
+    randomness: &[Scalar],
+) -> Vec<Pair> {
+    permutation
+        .iter()
+        .zip(randomness)
+        .map(|(&source, r)| {
+            let (a, b) = input[source as usize];
+            (a + r * &RISTRETTO_BASEPOINT_TABLE, b + r * election_key)
+        })
+        .collect()
+}
+
+/// Returns generators `H` and `H_1..H_len` of permutation commitments,
+/// which discrete logarithms are unknown.
+fn generators(len: usize) -> (RistrettoPoint, Vec<RistrettoPoint>) {
+    let h = RistrettoPoint::hash_from_bytes::<Sha512>(GENERATOR_TAG);
+    let generators = (0..len as u64)
+        .map(|idx| {
+            let mut bytes = GENERATOR_TAG.to_vec();
+            bytes.extend_from_slice(idx.hash().as_ref());
+            RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+        })
+        .collect();
+    (h, generators)
+}
+
+/// Returns domain separation tag of the shuffle proof bound to the election,
+/// the stage of mixing and the shuffled ciphertexts.
+fn statement_tag(election_id: u64, stage: u16, input: &[Pair], output: &[Pair]) -> Vec<u8> {
+    let mut tag = SHUFFLE_PROOF_TAG.to_vec();
+    tag.extend_from_slice(election_id.hash().as_ref());
+    tag.extend_from_slice(&[(stage >> 8) as u8, stage as u8]);
+    for (a, b) in input.iter().chain(output) {
+        tag.extend_from_slice(a.compress().as_bytes());
+        tag.extend_from_slice(b.compress().as_bytes());
+    }
+    tag
+}
+
+/// Derives the challenge vector from the statement and the permutation commitment.
+/// Returns the vector with the seed, which binds the final challenge to them.
+fn challenge_vector(tag: &[u8], commitments: &[RistrettoPoint]) -> (Vec<u8>, Vec<Scalar>) {
+    let mut hasher = Sha512::new();
+    hasher.input(tag);
+    for commitment in commitments {
+        hasher.input(commitment.compress().as_bytes());
+    }
+    let seed = hasher.result().to_vec();
+
+    let u = (0..commitments.len() as u64)
+        .map(|idx| {
+            let mut bytes = seed.clone();
+            bytes.extend_from_slice(idx.hash().as_ref());
+            Scalar::hash_from_bytes::<Sha512>(&bytes)
+        })
+        .collect();
+    (seed, u)
+}
+
+/// Derives the final challenge of the shuffle proof.
+fn final_challenge(
+    seed: &[u8],
+    chain: &[RistrettoPoint],
+    t: &[RistrettoPoint],
+    chain_t: &[RistrettoPoint],
+) -> Scalar {
+    let points: Vec<&RistrettoPoint> = chain.iter().chain(t).chain(chain_t).collect();
+    group::challenge(seed, &points)
+}
+
+/// Returns the predecessor of every link of the commitment chain, starting from `H`.
+fn previous_links<'a>(
+    h: RistrettoPoint,
+    chain: &'a [RistrettoPoint],
+) -> impl Iterator<Item = RistrettoPoint> + 'a {
+    Some(h).into_iter().chain(chain.iter().cloned())
+}
+
+/// Computes `sum(scalars[i] * points[i])`.
+fn combine(scalars: &[Scalar], points: &[RistrettoPoint]) -> RistrettoPoint {
+    scalars
+        .iter()
+        .zip(points)
+        .fold(RistrettoPoint::identity(), |acc, (s, p)| acc + s * p)
+}
+
+/// Computes `sum(lhs[i] * rhs[i])`.
+fn inner_product(lhs: &[Scalar], rhs: &[Scalar]) -> Scalar {
+    lhs.iter().zip(rhs).fold(Scalar::zero(), |acc, (l, r)| acc + l * r)
+}
+
+/// Returns the first components of the ciphertexts.
+fn firsts(ciphertexts: &[Pair]) -> Vec<RistrettoPoint> {
+    ciphertexts.iter().map(|&(a, _)| a).collect()
+}
+
+/// Returns the second components of the ciphertexts.
+fn seconds(ciphertexts: &[Pair]) -> Vec<RistrettoPoint> {
+    ciphertexts.iter().map(|&(_, b)| b).collect()
+}
+
+/// Generates random scalars.
+fn random_scalars(len: usize) -> Vec<Scalar> {
+    (0..len).map(|_| group::random_scalar()).collect()
+}
+
+/// Decodes exactly `len` points, returns `None` if any of them is malformed.
+fn decode_points(keys: &[PublicKey], len: usize) -> Option<Vec<RistrettoPoint>> {
+    if keys.len() != len {
+        return None;
+    }
+    keys.iter().map(group::key_to_point).collect()
+}
+
+/// Decodes exactly `len` scalars, returns `None` if any of them is malformed.
+fn decode_scalars(hashes: &[Hash], len: usize) -> Option<Vec<Scalar>> {
+    if hashes.len() != len {
+        return None;
+    }
+    hashes.iter().map(group::hash_to_scalar).collect()
+}
+
+/// Decodes ciphertexts, returns `None` if any of them is malformed.
+fn decode(ciphertexts: &[Ciphertext]) -> Option<Vec<Pair>> {
+    ciphertexts
+        .iter()
+        .map(|ciphertext| {
+            Some((
+                group::key_to_point(ciphertext.a())?,
+                group::key_to_point(ciphertext.b())?,
+            ))
+        })
+        .collect()
+}
+
+/// Encodes ciphertexts to their persisted representation.
+fn encode(ciphertexts: &[Pair]) -> Vec<Ciphertext> {
+    ciphertexts
+        .iter()
+        .map(|(a, b)| Ciphertext::new(&group::point_to_key(a), &group::point_to_key(b)))
+        .collect()
+}
+
+/// Returns domain separation tag bound to the voter.
+fn tag(prefix: &[u8], voter_id: &PublicKey) -> Vec<u8> {
+    let mut tag = prefix.to_vec();
+    tag.extend_from_slice(voter_id.as_ref());
+    tag
+}
@@ -0,0 +1,121 @@
+#![allow(bare_trait_objects)]
+
+use api::VoteProof;
+use exonum::{
+    blockchain::Blockchain,
+    crypto::{CryptoHash, PublicKey},
+    messages::Message,
+};
+use schema::{self, EncryptedVote, ELECTION_HASHES_TABLE_INDEX, VOTES_STATE_INDEX};
+use SERVICE_ID;
+
+/// Offline verification of proofs returned by REST API.
+
+/// Errors which can occur during proof verification.
+#[derive(Debug, Fail)]
+pub enum ProofError {
+    /// Block is not signed by the majority of validators.
+    #[fail(display = "Not enough precommits")]
+    NotEnoughPrecommits,
+
+    /// Precommit is for another block or has invalid signature.
+    #[fail(display = "Invalid precommit")]
+    InvalidPrecommit,
+
+    /// Map proof is malformed.
+    #[fail(display = "Invalid map proof")]
+    InvalidMapProof,
+
+    /// Proof is not linked to the block state hash.
+    #[fail(display = "State hash mismatch")]
+    StateHashMismatch,
+}
+
+/// Checks the proof returned by `v1/votes/proof` endpoint.
+///
+/// `validators` are consensus keys of the network validators, which are trusted by the verifier.
+/// Returns the encrypted vote of the voter, or `None` if the proof shows that the voter
+/// has not voted in the election yet.
+pub fn verify_vote_proof(
+    proof: VoteProof,
+    validators: &[PublicKey],
+    election_id: u64,
+    voter_id: &PublicKey,
+) -> Result<Option<EncryptedVote>, ProofError> {
+    let block = proof.block_proof.block;
+    let block_hash = block.hash();
+
+    let mut signed = vec![false; validators.len()];
+    for precommit in &proof.block_proof.precommits {
+        let idx = precommit.validator().0 as usize;
+        let valid = idx < validators.len()
+            && precommit.block_hash() == &block_hash
+            && precommit.height() == block.height()
+            && precommit.verify_signature(&validators[idx]);
+        if !valid {
+            return Err(ProofError::InvalidPrecommit);
+        }
+        signed[idx] = true;
+    }
+
+    let majority = validators.len() * 2 / 3 + 1;
+    if signed.iter().filter(|&&s| s).count() < majority {
+        return Err(ProofError::NotEnoughPrecommits);
+    }
+
+    let to_table = proof
+        .to_table
+        .check()
+        .map_err(|_| ProofError::InvalidMapProof)?;
+    if to_table.merkle_root() != *block.state_hash() {
+        return Err(ProofError::StateHashMismatch);
+    }
+
+    let table_key = Blockchain::service_table_unique_key(SERVICE_ID, ELECTION_HASHES_TABLE_INDEX);
+    let hashes_root = match to_table.entries().into_iter().find(|e| *e.0 == table_key) {
+        Some((_, root)) => *root,
+        None => return Err(ProofError::StateHashMismatch),
+    };
+
+    let to_election = proof
+        .to_election
+        .check()
+        .map_err(|_| ProofError::InvalidMapProof)?;
+    if to_election.merkle_root() != hashes_root {
+        return Err(ProofError::StateHashMismatch);
+    }
+
+    let election_key = election_id.hash();
+    let election_hash = match to_election.entries().into_iter().find(|e| *e.0 == election_key) {
+        Some((_, hash)) => *hash,
+        None => return Err(ProofError::StateHashMismatch),
+    };
+    if schema::election_hash(&proof.election_state) != election_hash {
+        return Err(ProofError::StateHashMismatch);
+    }
+
+    let votes_root = match proof.election_state.get(VOTES_STATE_INDEX) {
+        Some(root) => *root,
+        None => return Err(ProofError::StateHashMismatch),
+    };
+
+    let to_vote = proof
+        .to_vote
+        .check()
+        .map_err(|_| ProofError::InvalidMapProof)?;
+    if to_vote.merkle_root() != votes_root {
+        return Err(ProofError::StateHashMismatch);
+    }
+
+    let voter_hash = voter_id.hash();
+    let vote = to_vote
+        .entries()
+        .into_iter()
+        .find(|e| *e.0 == voter_hash)
+        .map(|(_, vote)| vote.clone());
+    if vote.is_none() && !to_vote.missing_keys().into_iter().any(|k| *k == voter_hash) {
+        return Err(ProofError::InvalidMapProof);
+    }
+
+    Ok(vote)
+}
@@ -0,0 +1,383 @@
+use exonum::crypto::PublicKey;
+use schema::{Round, RoundTally, StvRound};
+use std::cmp::{self, Reverse};
+
+/// Tally of ranked ballots.
+///
+/// A ranked ballot lists candidates in order of preference and counts for the first
+/// of them which is not eliminated yet. Ballots listing none of the remaining candidates
+/// are exhausted.
+///
+/// Condorcet methods compare candidates in pairs instead: a ballot prefers a candidate
+/// to all candidates ranked lower or not ranked at all.
+///
+/// Ties for the fewest votes are broken by the votes of the tied candidates in the latest
+/// earlier round where they differ; candidates tied in all rounds are eliminated
+/// in the order they are listed in the candidates table.
+
+/// Scale of vote values in single transferable vote tally: values are counted
+/// in hundred-thousandths of a vote, smaller fractions of transferred votes are dropped.
+pub const STV_SCALE: u64 = 100_000;
+
+/// Status of a candidate in single transferable vote tally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Hopeful,
+    Elected,
+    Eliminated,
+}
+
+/// Ranked ballot in single transferable vote tally.
+struct StvBallot<'a> {
+    /// Candidates in order of preference.
+    ranking: &'a [PublicKey],
+    /// Position of the next preference in the ranking.
+    next: usize,
+    /// Position of the candidate the ballot counts for in the candidates table.
+    holder: Option<usize>,
+    /// Value of the ballot.
+    weight: u64,
+}
+
+/// Checks that the ranking lists only the specified candidates, each of them at most once.
+pub fn is_valid_ranking(ranking: &[PublicKey], candidates: &[PublicKey]) -> bool {
+    ranking.iter().enumerate().all(|(pos, candidate)| {
+        candidates.contains(candidate) && !ranking[..pos].contains(candidate)
+    })
+}
+
+/// Runs instant-runoff tally of the ballots and returns its rounds.
+///
+/// Every round the candidate with the fewest votes is eliminated, until a candidate
+/// gets the majority of non-exhausted ballots or the only candidate remains.
+pub fn instant_runoff(candidates: &[PublicKey], ballots: &[Vec<PublicKey>]) -> Vec<Round> {
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+    let mut history: Vec<Vec<u64>> = vec![];
+    let mut rounds = vec![];
+
+    loop {
+        let mut votes = vec![0u64; candidates.len()];
+        let mut exhausted = 0;
+        for ballot in ballots {
+            match first_remaining(ballot, candidates, &remaining) {
+                Some(pos) => votes[pos] += 1,
+                None => exhausted += 1,
+            }
+        }
+        let active = ballots.len() as u64 - exhausted;
+        let tallies = round_tallies(candidates, &remaining, &votes);
+
+        let leader = remaining.iter().map(|&pos| votes[pos]).max().unwrap_or(0);
+        if remaining.len() <= 1 || 2 * leader > active {
+            rounds.push(Round::new(tallies, exhausted, vec![]));
+            return rounds;
+        }
+
+        let eliminated = weakest(&remaining, &votes, &history);
+        rounds.push(Round::new(tallies, exhausted, vec![candidates[eliminated]]));
+        remaining.retain(|&pos| pos != eliminated);
+        history.push(votes);
+    }
+}
+
+/// Runs single transferable vote tally of the ballots for the specified number of seats
+/// and returns its rounds. Candidates elected in all rounds are the winners.
+///
+/// Candidates reaching the Droop quota `valid ballots / (seats + 1) + 1` are elected.
+/// Surpluses of elected candidates are transferred one per round, largest first,
+/// by the weighted inclusive Gregory method: every ballot of the candidate passes on
+/// to its next preference with its value multiplied by `surplus / votes of the candidate`.
+/// When there are no surpluses to transfer, the candidate with the fewest votes is eliminated
+/// and its ballots pass on at their value. Once the remaining candidates are not more than
+/// the vacant seats, all of them are elected.
+pub fn single_transferable_vote(
+    candidates: &[PublicKey],
+    ballots: &[Vec<PublicKey>],
+    seats: u16,
+) -> Vec<StvRound> {
+    let seats = seats as usize;
+    let mut status = vec![Status::Hopeful; candidates.len()];
+    let mut transferred = vec![false; candidates.len()];
+    let mut ballots: Vec<StvBallot> = ballots
+        .iter()
+        .map(|ranking| StvBallot {
+            ranking: &ranking[..],
+            next: 0,
+            holder: None,
+            weight: STV_SCALE,
+        })
+        .collect();
+
+    let mut exhausted = 0;
+    for ballot in &mut ballots {
+        exhausted += advance(ballot, candidates, &status);
+    }
+    let valid = ballots.iter().filter(|ballot| ballot.holder.is_some()).count() as u64;
+    let quota = (valid / (seats as u64 + 1) + 1) * STV_SCALE;
+
+    let mut pending: Vec<usize> = vec![];
+    let mut history: Vec<Vec<u64>> = vec![];
+    let mut rounds = vec![];
+
+    loop {
+        let mut values = vec![0u64; candidates.len()];
+        for ballot in &ballots {
+            if let Some(pos) = ballot.holder {
+                values[pos] += ballot.weight;
+            }
+        }
+        for (value, &done) in values.iter_mut().zip(&transferred) {
+            if done {
+                *value = quota;
+            }
+        }
+        let counted: Vec<usize> = (0..candidates.len())
+            .filter(|&pos| status[pos] != Status::Eliminated)
+            .collect();
+        let tallies = round_tallies(candidates, &counted, &values);
+
+        let mut elected = 0;
+        let mut reached: Vec<usize> = vec![];
+        for &pos in &counted {
+            match status[pos] {
+                Status::Elected => elected += 1,
+                Status::Hopeful if values[pos] >= quota => reached.push(pos),
+                _ => {}
+            }
+        }
+        reached.sort_by_key(|&pos| Reverse(values[pos]));
+        reached.truncate(seats.saturating_sub(elected));
+        for &pos in &reached {
+            status[pos] = Status::Elected;
+            if values[pos] > quota {
+                pending.push(pos);
+            } else {
+                transferred[pos] = true;
+            }
+        }
+        elected += reached.len();
+
+        let mut hopeful: Vec<usize> = counted
+            .iter()
+            .cloned()
+            .filter(|&pos| status[pos] == Status::Hopeful)
+            .collect();
+        if elected == seats || elected + hopeful.len() <= seats {
+            hopeful.sort_by_key(|&pos| Reverse(values[pos]));
+            hopeful.truncate(seats - elected);
+            reached.extend(hopeful);
+            let elected_keys = reached.iter().map(|&pos| candidates[pos]).collect();
+            rounds.push(StvRound::new(quota, tallies, exhausted, elected_keys, vec![], vec![]));
+            return rounds;
+        }
+
+        // Transfer the largest surplus, or eliminate the weakest candidate.
+        let (source, value) = match pending
+            .iter()
+            .cloned()
+            .min_by_key(|&pos| (Reverse(values[pos]), pos))
+        {
+            Some(pos) => {
+                pending.retain(|&other| other != pos);
+                transferred[pos] = true;
+                (pos, values[pos] - quota)
+            }
+            None => {
+                let pos = weakest(&hopeful, &values, &history);
+                status[pos] = Status::Eliminated;
+                (pos, values[pos])
+            }
+        };
+
+        let mut received = vec![0u64; candidates.len()];
+        let exhausted_before = exhausted;
+        for ballot in &mut ballots {
+            if ballot.holder != Some(source) {
+                continue;
+            }
+
+            if value != values[source] {
+                let weight = u128::from(ballot.weight) * u128::from(value);
+                ballot.weight = (weight / u128::from(values[source])) as u64;
+            }
+            exhausted += advance(ballot, candidates, &status);
+            if let Some(pos) = ballot.holder {
+                received[pos] += ballot.weight;
+            }
+        }
+
+        let elected_keys = reached.iter().map(|&pos| candidates[pos]).collect();
+        let received_from: Vec<usize> = (0..candidates.len())
+            .filter(|&pos| received[pos] > 0)
+            .collect();
+        rounds.push(StvRound::new(
+            quota,
+            tallies,
+            exhausted_before,
+            elected_keys,
+            vec![candidates[source]],
+            round_tallies(candidates, &received_from, &received),
+        ));
+        history.push(values);
+    }
+}
+
+/// Returns pairwise preferences of the ballots: the number of ballots which prefer
+/// the candidate at the row position to the candidate at the column position.
+pub fn pairwise_preferences(candidates: &[PublicKey], ballots: &[Vec<PublicKey>]) -> Vec<Vec<u64>> {
+    let mut matrix = vec![vec![0u64; candidates.len()]; candidates.len()];
+    for ballot in ballots {
+        let positions: Vec<usize> = ballot
+            .iter()
+            .filter_map(|candidate| candidates.iter().position(|other| other == candidate))
+            .collect();
+        for (rank, &pos) in positions.iter().enumerate() {
+            for (other, count) in matrix[pos].iter_mut().enumerate() {
+                if !positions[..=rank].contains(&other) {
+                    *count += 1;
+                }
+            }
+        }
+    }
+    matrix
+}
+
+/// Returns positions of the Schulze winners of the pairwise preferences.
+///
+/// Strength of a path between candidates is its weakest defeat, a winner has paths
+/// to every other candidate at least as strong as paths back. There are several
+/// winners only if they are tied.
+pub fn schulze_winners(matrix: &[Vec<u64>]) -> Vec<usize> {
+    let count = matrix.len();
+    let mut strength: Vec<Vec<u64>> = (0..count)
+        .map(|from| {
+            (0..count)
+                .map(|to| if matrix[from][to] > matrix[to][from] { matrix[from][to] } else { 0 })
+                .collect()
+        })
+        .collect();
+
+    for via in 0..count {
+        for from in (0..count).filter(|&from| from != via) {
+            for to in (0..count).filter(|&to| to != via && to != from) {
+                let through = cmp::min(strength[from][via], strength[via][to]);
+                if through > strength[from][to] {
+                    strength[from][to] = through;
+                }
+            }
+        }
+    }
+
+    (0..count)
+        .filter(|&pos| (0..count).all(|other| strength[pos][other] >= strength[other][pos]))
+        .collect()
+}
+
+/// Returns positions of the ranked pairs winners of the pairwise preferences.
+///
+/// Defeats are locked from the strongest to the weakest one, unless they create a cycle
+/// with the locked ones. Defeats are ordered by votes of the winner, then by votes
+/// of the loser and then by order of the candidates. Winners are the candidates without
+/// locked defeats, there are several of them only if they are tied.
+pub fn ranked_pairs_winners(matrix: &[Vec<u64>]) -> Vec<usize> {
+    let count = matrix.len();
+    let mut defeats: Vec<(usize, usize)> = (0..count)
+        .flat_map(|winner| (0..count).map(move |loser| (winner, loser)))
+        .filter(|&(winner, loser)| matrix[winner][loser] > matrix[loser][winner])
+        .collect();
+    defeats.sort_by_key(|&(winner, loser)| (Reverse(matrix[winner][loser]), matrix[loser][winner]));
+
+    let mut locked = vec![vec![false; count]; count];
+    for (winner, loser) in defeats {
+        if !reaches(&locked, loser, winner) {
+            locked[winner][loser] = true;
+        }
+    }
+
+    (0..count)
+        .filter(|&pos| locked.iter().all(|defeats| !defeats[pos]))
+        .collect()
+}
+
+/// Checks whether locked defeats lead from one candidate to another.
+fn reaches(locked: &[Vec<bool>], from: usize, to: usize) -> bool {
+    let mut visited = vec![false; locked.len()];
+    let mut stack = vec![from];
+    while let Some(pos) = stack.pop() {
+        if pos == to {
+            return true;
+        }
+
+        if !visited[pos] {
+            visited[pos] = true;
+            stack.extend((0..locked.len()).filter(|&next| locked[pos][next]));
+        }
+    }
+    false
+}
+
+/// Returns position of the first candidate of the ballot which is not eliminated yet.
+fn first_remaining(
+    ballot: &[PublicKey],
+    candidates: &[PublicKey],
+    remaining: &[usize],
+) -> Option<usize> {
+    ballot
+        .iter()
+        .filter_map(|candidate| candidates.iter().position(|other| other == candidate))
+        .find(|pos| remaining.contains(pos))
+}
+
+/// Passes the ballot on to its next hopeful preference.
+/// Returns value of the ballot if it is exhausted, zero otherwise.
+fn advance(ballot: &mut StvBallot, candidates: &[PublicKey], status: &[Status]) -> u64 {
+    ballot.holder = None;
+    while ballot.next < ballot.ranking.len() {
+        let candidate = &ballot.ranking[ballot.next];
+        ballot.next += 1;
+        match candidates.iter().position(|other| other == candidate) {
+            Some(pos) if status[pos] == Status::Hopeful => {
+                ballot.holder = Some(pos);
+                return 0;
+            }
+            _ => {}
+        }
+    }
+    ballot.weight
+}
+
+/// Returns votes of the candidates at the specified positions of the candidates table.
+fn round_tallies(
+    candidates: &[PublicKey],
+    positions: &[usize],
+    votes: &[u64],
+) -> Vec<RoundTally> {
+    positions
+        .iter()
+        .map(|&pos| RoundTally::new(&candidates[pos], votes[pos]))
+        .collect()
+}
+
+/// Returns position of the candidate to eliminate: the one with the fewest votes,
+/// ties are broken by the previous rounds and then by the order of the candidates.
+///
+/// Positions of the hopeful candidates must be in ascending order.
+fn weakest(hopeful: &[usize], votes: &[u64], history: &[Vec<u64>]) -> usize {
+    let fewest = hopeful.iter().map(|&pos| votes[pos]).min().unwrap_or(0);
+    let mut tied: Vec<usize> = hopeful
+        .iter()
+        .cloned()
+        .filter(|&pos| votes[pos] == fewest)
+        .collect();
+
+    for votes in history.iter().rev() {
+        if tied.len() == 1 {
+            break;
+        }
+
+        let fewest = tied.iter().map(|&pos| votes[pos]).min().unwrap_or(0);
+        tied.retain(|&pos| votes[pos] == fewest);
+    }
+
+    tied[0]
+}
@@ -0,0 +1,98 @@
+use exonum::crypto::{self, Hash, PublicKey};
+
+/// Eligibility roll committed as a Merkle root.
+///
+/// Instead of registering every voter on chain, the election commits to the root
+/// of a Merkle tree over keys of the eligible voters. A voter proves eligibility with
+/// the index of the voter key in the roll and hashes of the siblings on the way
+/// from the leaf to the root, so the full roll is never posted.
+/// The leaves are padded with zero hashes up to a power of two, leaves and inner nodes
+/// are hashed with different prefixes, so an inner node can not be passed as a leaf.
+
+/// Prefix of the leaf hashes.
+const LEAF_PREFIX: u8 = 0;
+
+/// Prefix of the inner node hashes.
+const NODE_PREFIX: u8 = 1;
+
+/// Returns Merkle root of the roll. Root of the empty roll is zero hash,
+/// which means that the election has no roll.
+pub fn root(keys: &[PublicKey]) -> Hash {
+    match levels(keys).last() {
+        Some(level) => level[0],
+        None => Hash::zero(),
+    }
+}
+
+/// Returns the Merkle path of the voter key with the specified index in the roll:
+/// hashes of the siblings from the leaf level up.
+pub fn path(keys: &[PublicKey], index: usize) -> Option<Vec<Hash>> {
+    if index >= keys.len() {
+        return None;
+    }
+
+    let levels = levels(keys);
+    let path = levels[..levels.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(height, level)| level[(index >> height) ^ 1])
+        .collect();
+    Some(path)
+}
+
+/// Checks that the Merkle path proves the voter key to have the specified index
+/// in the roll with the specified root.
+pub fn verify(root: &Hash, key: &PublicKey, index: u64, path: &[Hash]) -> bool {
+    if path.len() >= 64 || index >> path.len() != 0 {
+        return false;
+    }
+
+    let mut hash = leaf_hash(key);
+    for (height, sibling) in path.iter().enumerate() {
+        hash = if (index >> height) & 1 == 0 {
+            node_hash(&hash, sibling)
+        } else {
+            node_hash(sibling, &hash)
+        };
+    }
+    hash == *root
+}
+
+/// Returns all levels of the Merkle tree, from the padded leaves up to the root.
+fn levels(keys: &[PublicKey]) -> Vec<Vec<Hash>> {
+    if keys.is_empty() {
+        return vec![];
+    }
+
+    let mut level: Vec<Hash> = keys.iter().map(leaf_hash).collect();
+    level.resize(keys.len().next_power_of_two(), Hash::zero());
+
+    let mut levels = vec![];
+    while level.len() > 1 {
+        let next = level
+            .chunks(2)
+            .map(|pair| node_hash(&pair[0], &pair[1]))
+            .collect();
+        levels.push(level);
+        level = next;
+    }
+    levels.push(level);
+    levels
+}
+
+/// Returns hash of the leaf with the voter key.
+fn leaf_hash(key: &PublicKey) -> Hash {
+    let mut bytes = Vec::with_capacity(33);
+    bytes.push(LEAF_PREFIX);
+    bytes.extend_from_slice(key.as_ref());
+    crypto::hash(&bytes)
+}
+
+/// Returns hash of the inner node with the specified children.
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut bytes = Vec::with_capacity(65);
+    bytes.push(NODE_PREFIX);
+    bytes.extend_from_slice(left.as_ref());
+    bytes.extend_from_slice(right.as_ref());
+    crypto::hash(&bytes)
+}
@@ -1,10 +1,260 @@
+use config::VoteServiceConfig;
 use exonum::{
-    crypto::{Hash, PublicKey},
-    storage::{Fork, MapIndex, Snapshot},
+    blockchain::Schema,
+    crypto::{self, CryptoHash, Hash, PublicKey},
+    storage::{Fork, ProofListIndex, ProofMapIndex, Snapshot},
 };
 
 /// Persistent data.
 
+encoding_struct! {
+    /// Election struct used to persist data for election within service.
+    ///
+    /// Candidate set, voter roll, votes and results of the election
+    /// are stored in separate tables, namespaced by the election id.
+    struct Election {
+        /// Id of the election.
+        id: u64,
+        /// Public key of the election author.
+        author: &PublicKey,
+        /// Title of the election.
+        title: &str,
+        /// Description of the election.
+        description: &str,
+        /// Height of the block, since which voting is open.
+        start_height: u64,
+        /// Height of the block, since which voting is closed.
+        end_height: u64,
+        /// Height of the block, since which votes of a commit-reveal election
+        /// can not be revealed, zero in other modes.
+        reveal_end_height: u64,
+        /// Ballot mode of the election, see `ElectionMode`.
+        mode: u8,
+        /// Number of seats to fill, more than one only in single transferable vote mode.
+        seats: u16,
+        /// Highest score of a candidate in score mode, zero in other modes.
+        max_score: u8,
+        /// Majority required to pass the motion in referendum mode, see `Majority`.
+        majority: u8,
+        /// Percentage of registered voters, which must vote to pass the motion
+        /// in referendum mode, zero in other modes.
+        quorum: u8,
+        /// Whether a voter may cast the vote again until voting is over, only the last
+        /// vote counts. Available only in encrypted mode.
+        revoting: bool,
+        /// Merkle root of the eligibility roll, see `roll`, zero hash if the election
+        /// has no roll. Available only in encrypted mode.
+        roll_root: &Hash,
+        /// Joint public key of validators, votes of the election are encrypted against.
+        /// Zero key if votes of the election are not encrypted.
+        election_key: &PublicKey,
+        /// Verification keys of the validators key shares of the election key,
+        /// in order of validators.
+        verification_keys: Vec<PublicKey>,
+        /// Whether results of the election were decrypted and tallied.
+        tallied: bool,
+    }
+}
+
+/// Phase of the election lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ElectionPhase {
+    /// Candidates and voters can be registered.
+    Registration,
+    /// Votes can be added.
+    Voting,
+    /// Voting is over, voters of a commit-reveal election reveal their votes.
+    Revealing,
+    /// Voting is over, validators publish decryption shares.
+    Closed,
+    /// Results are decrypted and tallied.
+    Tallied,
+}
+
+/// Ballot mode of the election.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum ElectionMode {
+    /// Every vote is encrypted as a whole and decrypted on tally.
+    Encrypted = 0,
+    /// Ballot is a vector of exponential ElGamal ciphertexts over candidates,
+    /// only encrypted totals of candidates are decrypted on tally.
+    Homomorphic = 1,
+    /// Votes are encrypted as in `Encrypted` mode, but cast from one-time keys
+    /// with blind-signed voting tokens, so they are not linked to voters.
+    Anonymous = 2,
+    /// Votes are encrypted as in `Encrypted` mode, but cast from one-time keys
+    /// with linkable ring signatures over keys of the registered voters.
+    RingSigned = 3,
+    /// Vote is an ElGamal ciphertext of the candidate point; validators shuffle
+    /// the votes after voting is over and only the mixed list is decrypted.
+    Mixnet = 4,
+    /// Voter commits to a hash of the choice during voting and reveals the choice
+    /// after voting is over; no encryption keys are involved.
+    CommitReveal = 5,
+    /// Vote is an encrypted ranking of candidates, tallied by instant runoff.
+    ///
+    /// Ties for the fewest votes in a round are broken by the votes of the tied
+    /// candidates in the latest earlier round where they differ; candidates tied
+    /// in all rounds are eliminated in order of the candidates table.
+    Ranked = 6,
+    /// Votes are encrypted rankings as in `Ranked` mode, tallied by single transferable vote
+    /// for several seats, see `ranked::single_transferable_vote`. Ties are broken
+    /// as in `Ranked` mode.
+    SingleTransferable = 7,
+    /// Votes are encrypted sets of approved candidates, every approval counts
+    /// for the candidate.
+    Approval = 8,
+    /// Votes are encrypted scores of candidates from zero up to the maximal score
+    /// of the election, candidates missing from the ballot are scored zero.
+    Score = 9,
+    /// Votes are encrypted choices of the built-in options `ReferendumChoice` on a motion,
+    /// the election has no candidates. The motion passes if the quorum of registered voters
+    /// voted and the majority of the election is reached.
+    Referendum = 10,
+}
+
+impl ElectionMode {
+    /// Returns mode with the specified code.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ElectionMode::Encrypted),
+            1 => Some(ElectionMode::Homomorphic),
+            2 => Some(ElectionMode::Anonymous),
+            3 => Some(ElectionMode::RingSigned),
+            4 => Some(ElectionMode::Mixnet),
+            5 => Some(ElectionMode::CommitReveal),
+            6 => Some(ElectionMode::Ranked),
+            7 => Some(ElectionMode::SingleTransferable),
+            8 => Some(ElectionMode::Approval),
+            9 => Some(ElectionMode::Score),
+            10 => Some(ElectionMode::Referendum),
+            _ => None,
+        }
+    }
+
+    /// Returns whether votes of the mode are decrypted by validators on tally.
+    pub fn is_encrypted(self) -> bool {
+        self != ElectionMode::CommitReveal
+    }
+}
+
+/// Majority of `Yes` votes among `Yes` and `No` ones required to pass a motion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Majority {
+    /// More `Yes` votes than `No` ones.
+    Simple = 0,
+    /// At least three fifths of the votes are `Yes` ones.
+    ThreeFifths = 1,
+    /// At least two thirds of the votes are `Yes` ones.
+    TwoThirds = 2,
+}
+
+impl Majority {
+    /// Returns majority with the specified code.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Majority::Simple),
+            1 => Some(Majority::ThreeFifths),
+            2 => Some(Majority::TwoThirds),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the majority is reached with the specified `Yes` and `No` votes.
+    pub fn is_reached(self, yes: u64, no: u64) -> bool {
+        match self {
+            Majority::Simple => yes > no,
+            Majority::ThreeFifths => yes > 0 && 5 * yes >= 3 * (yes + no),
+            Majority::TwoThirds => yes > 0 && 3 * yes >= 2 * (yes + no),
+        }
+    }
+}
+
+/// Built-in option of a referendum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferendumChoice {
+    /// Vote for the motion.
+    Yes = 0,
+    /// Vote against the motion.
+    No = 1,
+    /// Abstention, counted only for the quorum.
+    Abstain = 2,
+}
+
+impl ReferendumChoice {
+    /// Returns choice with the specified code.
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ReferendumChoice::Yes),
+            1 => Some(ReferendumChoice::No),
+            2 => Some(ReferendumChoice::Abstain),
+            _ => None,
+        }
+    }
+}
+
+/// Reason an encrypted ballot is not counted on tally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum RejectionReason {
+    /// Ballot can not be decrypted, its plaintext is malformed or does not match
+    /// the proven choice of the vote.
+    Malformed = 0,
+    /// Ballot is not cast by the voter it is stored for, e.g. copied from another voter.
+    WrongVoter = 1,
+    /// Ballot chooses an unknown candidate, a candidate twice, an unknown option
+    /// or a score out of range.
+    InvalidChoice = 2,
+    /// Ballot chooses a single candidate, who is withdrawn from the election.
+    CandidateWithdrawn = 3,
+}
+
+impl Election {
+    /// Returns ballot mode of the election.
+    pub fn election_mode(&self) -> Option<ElectionMode> {
+        ElectionMode::from_u8(self.mode())
+    }
+
+    /// Returns phase of the election at the specified block height.
+    pub fn phase(&self, height: u64) -> ElectionPhase {
+        if self.tallied() {
+            ElectionPhase::Tallied
+        } else if height < self.start_height() {
+            ElectionPhase::Registration
+        } else if height < self.end_height() {
+            ElectionPhase::Voting
+        } else if height < self.reveal_end_height() {
+            ElectionPhase::Revealing
+        } else {
+            ElectionPhase::Closed
+        }
+    }
+
+    /// Returns a copy of the election marked as tallied.
+    pub fn set_tallied(self) -> Self {
+        Self::new(
+            self.id(),
+            self.author(),
+            self.title(),
+            self.description(),
+            self.start_height(),
+            self.end_height(),
+            self.reveal_end_height(),
+            self.mode(),
+            self.seats(),
+            self.max_score(),
+            self.majority(),
+            self.quorum(),
+            self.revoting(),
+            self.roll_root(),
+            self.election_key(),
+            self.verification_keys(),
+            true,
+        )
+    }
+}
+
 encoding_struct! {
     /// Candidate struct used to persist data for candidate within service.
     struct Candidate {
@@ -14,6 +264,20 @@ encoding_struct! {
         name: &str,
         /// Info about candidate.
         info: &str,
+        /// Whether the candidate was withdrawn from the election.
+        withdrawn: bool,
+    }
+}
+
+impl Candidate {
+    /// Returns a copy of the candidate with the specified info.
+    pub fn set_info(self, info: &str) -> Self {
+        Self::new(self.pub_key(), self.name(), info, self.withdrawn())
+    }
+
+    /// Returns a copy of the candidate marked as withdrawn.
+    pub fn set_withdrawn(self) -> Self {
+        Self::new(self.pub_key(), self.name(), self.info(), true)
     }
 }
 
@@ -24,6 +288,9 @@ encoding_struct! {
         pub_key: &PublicKey,
         /// Name of the voter.
         name: &str,
+        /// Weight of the vote of the voter, e.g. number of shares. Counted in encrypted mode,
+        /// where votes are linked to the voters.
+        weight: u64,
     }
 }
 
@@ -37,9 +304,61 @@ encoding_struct! {
     }
 }
 
+encoding_struct! {
+    /// RankedVote struct used to persist ranked ballot within service.
+    struct RankedVote {
+        /// Id of the voter.
+        from: &PublicKey,
+        /// Ids of the candidates in order of preference.
+        ranking: Vec<PublicKey>,
+    }
+}
+
+encoding_struct! {
+    /// ApprovalVote struct used to persist approval ballot within service.
+    struct ApprovalVote {
+        /// Id of the voter.
+        from: &PublicKey,
+        /// Ids of the approved candidates.
+        approved: Vec<PublicKey>,
+    }
+}
+
+encoding_struct! {
+    /// CandidateScore struct used to persist score of a candidate in score ballot.
+    struct CandidateScore {
+        /// Id of the candidate.
+        candidate: &PublicKey,
+        /// Score of the candidate.
+        score: u8,
+    }
+}
+
+encoding_struct! {
+    /// ScoreVote struct used to persist score ballot within service.
+    struct ScoreVote {
+        /// Id of the voter.
+        from: &PublicKey,
+        /// Scores of the candidates.
+        scores: Vec<CandidateScore>,
+    }
+}
+
+encoding_struct! {
+    /// ReferendumVote struct used to persist referendum ballot within service.
+    struct ReferendumVote {
+        /// Id of the voter.
+        from: &PublicKey,
+        /// Chosen option, see `ReferendumChoice`.
+        choice: u8,
+    }
+}
+
 encoding_struct! {
     /// EncryptedVote struct used to persist encrypted data for vote within service.
     struct EncryptedVote {
+        /// One-time public key of the client which encrypted the vote.
+        pub_key: &PublicKey,
         /// Encrypted data vector.
         data: Vec<u8>,
     }
@@ -52,31 +371,357 @@ encoding_struct! {
         candidate: &PublicKey,
         /// Vector of decrypted votes.
         votes: Vec<Vote>,
-        /// Number of the votes.
+        /// Number of the votes, weighted by voter weights in encrypted mode.
         vote_num: u64
     }
 }
 
 encoding_struct! {
-    /// CandidateResult struct used to persist encrypted data for candidate vote result within service.
-    struct CandidateResult {
+    /// Ciphertext struct used to persist exponential ElGamal ciphertext within service.
+    struct Ciphertext {
+        /// One-time key: `r * G`.
+        a: &PublicKey,
+        /// Encrypted message: `r * Y + m * G`.
+        b: &PublicKey,
+    }
+}
+
+encoding_struct! {
+    /// BitProof struct used to persist disjunctive proof that a ciphertext encrypts 0 or 1.
+    struct BitProof {
+        /// Challenge of the branch for 0.
+        challenge_zero: &Hash,
+        /// Challenge of the branch for 1.
+        challenge_one: &Hash,
+        /// Response of the branch for 0.
+        response_zero: &Hash,
+        /// Response of the branch for 1.
+        response_one: &Hash,
+    }
+}
+
+encoding_struct! {
+    /// ChoiceProof struct used to persist a branch of the disjunctive proof that
+    /// the choice of a vote encrypts the point of one of the candidates, see `zkp::verify_vote`.
+    struct ChoiceProof {
+        /// Challenge of the branch of the candidate.
+        challenge: &Hash,
+        /// Response of the branch of the candidate.
+        response: &Hash,
+    }
+}
+
+encoding_struct! {
+    /// Ballot struct used to persist homomorphic ballot within service.
+    struct Ballot {
+        /// Ciphertexts of the ballot, in order of the candidates table.
+        ciphertexts: Vec<Ciphertext>,
+        /// Proofs that ciphertexts encrypt 0 or 1.
+        proofs: Vec<BitProof>,
+        /// Challenge of the proof that the sum of ciphertexts encrypts 1.
+        sum_challenge: &Hash,
+        /// Response of the proof that the sum of ciphertexts encrypts 1.
+        sum_response: &Hash,
+    }
+}
+
+encoding_struct! {
+    /// PartialDecryption struct used to persist decryption share of a single ciphertext.
+    struct PartialDecryption {
+        /// Id of the ciphertext, see `VoteServiceSchema::decryption_targets`.
+        ciphertext_id: &Hash,
+        /// One-time key of the ciphertext multiplied by the key share of the validator.
+        share: &PublicKey,
+        /// Challenge of the proof that the share matches the verification key.
+        challenge: &Hash,
+        /// Response of the proof that the share matches the verification key.
+        response: &Hash,
+    }
+}
+
+encoding_struct! {
+    /// DecryptionShare struct used to persist decryption shares of a validator within service.
+    struct DecryptionShare {
+        /// Service key of the validator.
+        validator: &PublicKey,
+        /// Index of the validator key share.
+        index: u16,
+        /// Decryption shares of all ciphertexts of the election,
+        /// see `VoteServiceSchema::decryption_targets`.
+        partials: Vec<PartialDecryption>,
+    }
+}
+
+encoding_struct! {
+    /// TokenIssuance struct used to persist blind signing of a voting token for a voter.
+    ///
+    /// Zero hash stands for the challenge or the response which is not published yet.
+    struct TokenIssuance {
+        /// Commitment of the token issuer.
+        commitment: &PublicKey,
+        /// Blinded challenge of the voter.
+        challenge: &Hash,
+        /// Response of the token issuer to the blinded challenge.
+        response: &Hash,
+        /// Height of the block with the commitment.
+        height: u64,
+    }
+}
+
+/// Number of blocks after the commitment, within which the voter must request the token.
+pub const TOKEN_SESSION_BLOCKS: u64 = 10;
+
+impl TokenIssuance {
+    /// Returns whether the voter has requested the token.
+    pub fn is_requested(&self) -> bool {
+        *self.challenge() != Hash::zero()
+    }
+
+    /// Returns whether the token issuer has signed the request.
+    pub fn is_issued(&self) -> bool {
+        *self.response() != Hash::zero()
+    }
+
+    /// Returns whether the voter has not requested the token in time.
+    /// The token of an expired session is never issued.
+    pub fn is_expired(&self, height: u64) -> bool {
+        !self.is_requested() && height >= self.height() + TOKEN_SESSION_BLOCKS
+    }
+
+    /// Returns whether the signing session is open at the specified height:
+    /// the commitment is published, but the token is neither issued nor expired.
+    pub fn is_open(&self, height: u64) -> bool {
+        !self.is_issued() && !self.is_expired(height)
+    }
+}
+
+encoding_struct! {
+    /// ShuffleProof struct used to persist the proof of a shuffle, see `mixnet`.
+    struct ShuffleProof {
+        /// Commitment to the permutation, one point per ciphertext.
+        permutation_commitments: Vec<PublicKey>,
+        /// Chained commitments to the permuted challenge vector.
+        chain_commitments: Vec<PublicKey>,
+        /// Fiat-Shamir challenge.
+        challenge: &Hash,
+        /// Responses for the aggregated randomness of commitments and re-encryption.
+        responses: Vec<Hash>,
+        /// Responses for the randomness of chained commitments.
+        chain_responses: Vec<Hash>,
+        /// Responses for the permuted challenge vector.
+        permuted_responses: Vec<Hash>,
+    }
+}
+
+/// Number of blocks each validator is given to shuffle votes of a mixnet election.
+pub const MIX_TURN_BLOCKS: u64 = 5;
+
+encoding_struct! {
+    /// Mix struct used to persist a shuffle of the votes made by a validator.
+    struct Mix {
+        /// Service key of the validator.
+        validator: &PublicKey,
+        /// Re-encrypted and permuted ciphertexts.
+        ciphertexts: Vec<Ciphertext>,
+        /// Proof that the ciphertexts are a shuffle of the previous list.
+        proof: ShuffleProof,
+    }
+}
+
+encoding_struct! {
+    /// Reveal struct used to persist the choice of a voter in commit-reveal mode.
+    struct Reveal {
         /// Id of the candidate.
         candidate: &PublicKey,
-        /// Vector of encrypted votes.
-        votes: Vec<EncryptedVote>,
-        /// Number of the votes.
-        vote_num: u64
+        /// Salt of the commitment.
+        salt: &Hash,
     }
 }
 
 encoding_struct! {
-    /// VoteResult struct used to persist encrypted data for vote result within service.
-    struct VoteResult {
-        /// Public key of vote service.
-        pub_key: &PublicKey,
-        /// Vector of encrypted results for candidates.
-        candidate_results: Vec<CandidateResult>,
+    /// RoundTally struct used to persist votes of a candidate in a round of ranked tally.
+    struct RoundTally {
+        /// Id of the candidate.
+        candidate: &PublicKey,
+        /// Number of ballots which rank the candidate first among the remaining ones.
+        votes: u64,
+    }
+}
+
+encoding_struct! {
+    /// Round struct used to persist a round of instant-runoff tally.
+    struct Round {
+        /// Votes of the remaining candidates, in order of the candidates table.
+        tallies: Vec<RoundTally>,
+        /// Number of ballots which rank none of the remaining candidates.
+        exhausted: u64,
+        /// Candidate eliminated after the round, empty in the final round.
+        eliminated: Vec<PublicKey>,
+    }
+}
+
+encoding_struct! {
+    /// StvRound struct used to persist a round of single transferable vote tally.
+    ///
+    /// Votes are counted in fractions of a vote, see `ranked::STV_SCALE`.
+    struct StvRound {
+        /// Droop quota of the election.
+        quota: u64,
+        /// Votes of the candidates which are not eliminated, in order of the candidates table.
+        tallies: Vec<RoundTally>,
+        /// Value of the exhausted ballots.
+        exhausted: u64,
+        /// Candidates elected in the round.
+        elected: Vec<PublicKey>,
+        /// Candidate whose surplus or votes are transferred after the round,
+        /// empty in the final round.
+        transferred: Vec<PublicKey>,
+        /// Votes received by the candidates from the transfer.
+        transfers: Vec<RoundTally>,
+    }
+}
+
+encoding_struct! {
+    /// ApprovalResult struct used to persist tallied approvals of a candidate.
+    struct ApprovalResult {
+        /// Id of the candidate.
+        candidate: &PublicKey,
+        /// Number of ballots which approve the candidate.
+        approvals: u64,
+    }
+}
+
+encoding_struct! {
+    /// ScoreResult struct used to persist tallied scores of a candidate.
+    struct ScoreResult {
+        /// Id of the candidate.
+        candidate: &PublicKey,
+        /// Sum of the scores of the candidate.
+        total: u64,
+        /// Number of ballots per score, from zero up to the maximal score.
+        distribution: Vec<u64>,
+    }
+}
+
+encoding_struct! {
+    /// PairwiseResult struct used to persist pairwise preferences of a candidate
+    /// in Condorcet tally of ranked ballots.
+    struct PairwiseResult {
+        /// Id of the candidate.
+        candidate: &PublicKey,
+        /// Number of ballots which prefer the candidate to every candidate,
+        /// in order of the candidates table.
+        preferences: Vec<u64>,
+        /// Whether the candidate is a Schulze winner.
+        schulze_winner: bool,
+        /// Whether the candidate is a ranked pairs winner.
+        ranked_pairs_winner: bool,
+    }
+}
+
+encoding_struct! {
+    /// ReferendumResult struct used to persist tallied result of a referendum.
+    struct ReferendumResult {
+        /// Number of `Yes` votes.
+        yes: u64,
+        /// Number of `No` votes.
+        no: u64,
+        /// Number of abstentions.
+        abstain: u64,
+        /// Number of registered voters.
+        registered: u64,
+        /// Whether the quorum of registered voters voted.
+        quorum_reached: bool,
+        /// Whether the motion passed.
+        passed: bool,
+    }
+}
+
+encoding_struct! {
+    /// WeightChange struct used to persist a change of the voter weight.
+    struct WeightChange {
+        /// Id of the voter.
+        voter: &PublicKey,
+        /// Weight of the voter before the change.
+        old_weight: u64,
+        /// Weight of the voter after the change.
+        new_weight: u64,
+        /// Height of the block with the change.
+        height: u64,
+    }
+}
+
+encoding_struct! {
+    /// ReplacedVote struct used to persist a vote replaced by the next vote of the voter.
+    struct ReplacedVote {
+        /// Id of the voter.
+        voter: &PublicKey,
+        /// Replaced encrypted vote.
+        vote: EncryptedVote,
+        /// Height of the block with the next vote.
+        height: u64,
+    }
+}
+
+encoding_struct! {
+    /// RejectedVote struct used to persist a ballot, which is not counted on tally,
+    /// so the voter can find out why.
+    struct RejectedVote {
+        /// Id of the vote: hash of the voter id.
+        vote_id: &Hash,
+        /// Reason the ballot is rejected, see `RejectionReason`.
+        reason: u8,
+    }
+}
+
+encoding_struct! {
+    /// DelegatedWeight struct used to persist votes delegated to a voter in liquid democracy.
+    struct DelegatedWeight {
+        /// Id of the voter who voted on behalf of the delegators.
+        delegate: &PublicKey,
+        /// Ids of the voters whose votes are resolved to the delegate.
+        delegators: Vec<PublicKey>,
+        /// Sum of weights of the delegators.
+        weight: u64
+    }
+}
+
+/// Index of elections table in the service state hash.
+pub const ELECTIONS_TABLE_INDEX: usize = 0;
+
+/// Index of election hashes table in the service state hash.
+pub const ELECTION_HASHES_TABLE_INDEX: usize = 1;
+
+/// Index of votes table root in the election state.
+pub const VOTES_STATE_INDEX: usize = 2;
+
+/// Returns aggregated hash of the election state, which is stored in election hashes table.
+pub fn election_hash(election_state: &[Hash]) -> Hash {
+    let mut bytes = Vec::with_capacity(election_state.len() * 32);
+    for hash in election_state {
+        bytes.extend_from_slice(hash.as_ref());
     }
+    crypto::hash(&bytes)
+}
+
+/// Returns commitment of the voter to the vote for the candidate in commit-reveal mode:
+/// `H(H(election_id) || voter_id || choice || salt)`.
+///
+/// Salt must be random, otherwise the choice can be found by trying all candidates.
+/// The commitment is bound to the election and the voter, so another voter can not copy
+/// it and then reveal the same choice after the original voter.
+pub fn vote_commitment(
+    election_id: u64,
+    voter_id: &PublicKey,
+    candidate: &PublicKey,
+    salt: &Hash,
+) -> Hash {
+    let mut bytes = Vec::with_capacity(128);
+    bytes.extend_from_slice(election_id.hash().as_ref());
+    bytes.extend_from_slice(voter_id.as_ref());
+    bytes.extend_from_slice(candidate.as_ref());
+    bytes.extend_from_slice(salt.as_ref());
+    crypto::hash(&bytes)
 }
 
 /// Schema of the key-value storage used by vote service.
@@ -92,66 +737,786 @@ impl<T: AsRef<dyn Snapshot>> VoteServiceSchema<T> {
         VoteServiceSchema { view }
     }
 
-    /// Returns an immutable version of candidates table.
-    pub fn candidates(&self) -> MapIndex<&dyn Snapshot, PublicKey, Candidate> {
-        MapIndex::new("voteservice.candidates", self.view.as_ref())
+    /// Returns height of the block which is being created,
+    /// i.e. the height transactions are executed at.
+    pub fn current_height(&self) -> u64 {
+        Schema::new(self.view.as_ref()).height().next().0
+    }
+
+    /// Returns actual service configuration.
+    pub fn config(&self) -> VoteServiceConfig {
+        VoteServiceConfig::actual(self.view.as_ref())
+    }
+
+    /// Returns whether the key belongs to a service administrator.
+    pub fn is_administrator(&self, pub_key: &PublicKey) -> bool {
+        self.config().administrators.contains(pub_key)
+    }
+
+    /// Returns index of the key share held by the validator with the specified service key.
+    pub fn validator_index(&self, service_key: &PublicKey) -> Option<u16> {
+        Schema::new(self.view.as_ref())
+            .actual_configuration()
+            .validator_keys
+            .iter()
+            .position(|keys| keys.service_key == *service_key)
+            .map(|idx| idx as u16 + 1)
+    }
+
+    /// Returns whether the key can register candidates and voters in the election:
+    /// it belongs to the election author or to a service administrator.
+    pub fn is_election_authority(&self, election: &Election, pub_key: &PublicKey) -> bool {
+        election.author() == pub_key || self.is_administrator(pub_key)
+    }
+
+    /// Returns an immutable version of elections table.
+    pub fn elections(&self) -> ProofMapIndex<&dyn Snapshot, Hash, Election> {
+        ProofMapIndex::new("voteservice.elections", self.view.as_ref())
+    }
+
+    /// Returns a specific election data.
+    pub fn election(&self, election_id: u64) -> Option<Election> {
+        self.elections().get(&election_id.hash())
+    }
+
+    /// Returns an immutable version of election hashes table.
+    ///
+    /// It links state of every election to the service state hash.
+    pub fn election_hashes(&self) -> ProofMapIndex<&dyn Snapshot, Hash, Hash> {
+        ProofMapIndex::new("voteservice.election_hashes", self.view.as_ref())
+    }
+
+    /// Returns an immutable version of candidates table of the election.
+    pub fn candidates(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, Candidate> {
+        ProofMapIndex::new_in_family("voteservice.candidates", &election_id, self.view.as_ref())
     }
 
     /// Returns a specific candidate data.
-    pub fn candidate(&self, pub_key: &PublicKey) -> Option<Candidate> {
-        self.candidates().get(pub_key)
+    pub fn candidate(&self, election_id: u64, pub_key: &PublicKey) -> Option<Candidate> {
+        self.candidates(election_id).get(pub_key)
     }
 
-    /// Returns an immutable version of voters table.
-    pub fn voters(&self) -> MapIndex<&dyn Snapshot, PublicKey, Voter> {
-        MapIndex::new("voteservice.voters", self.view.as_ref())
+    /// Returns an immutable version of voters table of the election.
+    pub fn voters(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, PublicKey, Voter> {
+        ProofMapIndex::new_in_family("voteservice.voters", &election_id, self.view.as_ref())
     }
 
     /// Returns a specific voter data.
-    pub fn voter(&self, pub_key: &PublicKey) -> Option<Voter> {
-        self.voters().get(pub_key)
+    pub fn voter(&self, election_id: u64, pub_key: &PublicKey) -> Option<Voter> {
+        self.voters(election_id).get(pub_key)
     }
 
-    /// Returns an immutable version of votes table.
-    pub fn votes(&self) -> MapIndex<&dyn Snapshot, Hash, EncryptedVote> {
-        MapIndex::new("voteservice.votes", self.view.as_ref())
+    /// Returns an immutable version of revoked voters table of the election.
+    pub fn revoked_voters(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, Voter> {
+        ProofMapIndex::new_in_family(
+            "voteservice.revoked_voters",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns a specific revoked voter data.
+    pub fn revoked_voter(&self, election_id: u64, pub_key: &PublicKey) -> Option<Voter> {
+        self.revoked_voters(election_id).get(pub_key)
+    }
+
+    /// Returns an immutable version of votes table of the election.
+    ///
+    /// Holds only ciphertexts: candidates of the votes are known only after tally.
+    pub fn votes(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, Hash, EncryptedVote> {
+        ProofMapIndex::new_in_family("voteservice.votes", &election_id, self.view.as_ref())
     }
 
     /// Returns a specific vote data.
-    pub fn vote(&self, hash: &Hash) -> Option<EncryptedVote> {
-        self.votes().get(hash)
+    pub fn vote(&self, election_id: u64, hash: &Hash) -> Option<EncryptedVote> {
+        self.votes(election_id).get(hash)
+    }
+
+    /// Returns an immutable version of delegates table of the election.
+    ///
+    /// Maps a voter to the key which is authorized to cast the vote on voter's behalf.
+    pub fn delegates(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.delegates", &election_id, self.view.as_ref())
+    }
+
+    /// Returns a delegate of the specific voter.
+    pub fn delegate(&self, election_id: u64, voter: &PublicKey) -> Option<PublicKey> {
+        self.delegates(election_id).get(voter)
+    }
+
+    /// Returns an immutable version of ballots table of the homomorphic election.
+    pub fn ballots(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, Hash, Ballot> {
+        ProofMapIndex::new_in_family("voteservice.ballots", &election_id, self.view.as_ref())
+    }
+
+    /// Returns a specific ballot data.
+    pub fn ballot(&self, election_id: u64, hash: &Hash) -> Option<Ballot> {
+        self.ballots(election_id).get(hash)
+    }
+
+    /// Returns an immutable version of encrypted totals table of the homomorphic election.
+    ///
+    /// Maps a candidate to the sum of ciphertexts of all ballots for the candidate.
+    pub fn encrypted_totals(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, Ciphertext> {
+        ProofMapIndex::new_in_family(
+            "voteservice.encrypted_totals",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns encrypted total of the specific candidate.
+    pub fn encrypted_total(&self, election_id: u64, candidate: &PublicKey) -> Option<Ciphertext> {
+        self.encrypted_totals(election_id).get(candidate)
+    }
+
+    /// Returns an immutable version of vote choices table of the election.
+    ///
+    /// Maps id of every vote of the encrypted mode to the proven choice of the vote,
+    /// as a ciphertext of the one-time key of the vote and the encrypted candidate point.
+    pub fn vote_choices(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, Hash, Ciphertext> {
+        ProofMapIndex::new_in_family("voteservice.vote_choices", &election_id, self.view.as_ref())
+    }
+
+    /// Returns the proven choice of the specific vote.
+    pub fn vote_choice(&self, election_id: u64, vote_id: &Hash) -> Option<Ciphertext> {
+        self.vote_choices(election_id).get(vote_id)
+    }
+
+    /// Returns ciphertexts which are decrypted on tally, with their one-time keys:
+    /// votes of the election, encrypted totals of candidates in homomorphic mode
+    /// or the mixed votes in mixnet mode.
+    ///
+    /// Order of the ciphertexts matters: decryption shares are published in the same order.
+    pub fn decryption_targets(&self, election: &Election) -> Vec<(Hash, PublicKey)> {
+        match election.election_mode() {
+            Some(ElectionMode::Homomorphic) => self
+                .encrypted_totals(election.id())
+                .iter()
+                .map(|(candidate, total)| (candidate.hash(), *total.a()))
+                .collect(),
+            Some(ElectionMode::Mixnet) => self
+                .mixed_votes(election.id())
+                .iter()
+                .map(|ciphertext| (ciphertext.hash(), *ciphertext.a()))
+                .collect(),
+            _ => self
+                .votes(election.id())
+                .iter()
+                .map(|(vote_id, vote)| (vote_id, *vote.pub_key()))
+                .collect(),
+        }
+    }
+
+    /// Returns an immutable version of decryption shares table of the election.
+    pub fn decryption_shares(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, DecryptionShare> {
+        ProofMapIndex::new_in_family(
+            "voteservice.decryption_shares",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns decryption shares of the specific validator.
+    pub fn decryption_share(
+        &self,
+        election_id: u64,
+        validator: &PublicKey,
+    ) -> Option<DecryptionShare> {
+        self.decryption_shares(election_id).get(validator)
+    }
+
+    /// Returns an immutable version of decrypted results table of the election.
+    ///
+    /// It is filled once the election is tallied.
+    pub fn decrypted_results(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, DecryptedCandidateResult> {
+        ProofMapIndex::new_in_family(
+            "voteservice.decrypted_results",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of token issuances table of the anonymous election.
+    pub fn token_issuances(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, TokenIssuance> {
+        ProofMapIndex::new_in_family(
+            "voteservice.token_issuances",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns token issuance of the specific voter.
+    pub fn token_issuance(&self, election_id: u64, voter: &PublicKey) -> Option<TokenIssuance> {
+        self.token_issuances(election_id).get(voter)
+    }
+
+    /// Returns an immutable version of spent tokens table of the anonymous election.
+    ///
+    /// Maps hash of the token commitment to the one-time key the vote was cast from.
+    pub fn spent_tokens(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, Hash, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.spent_tokens", &election_id, self.view.as_ref())
+    }
+
+    /// Returns the one-time key the specific token was spent by.
+    pub fn spent_token(&self, election_id: u64, commitment: &PublicKey) -> Option<PublicKey> {
+        self.spent_tokens(election_id).get(&commitment.hash())
+    }
+
+    /// Returns an immutable version of key images table of the ring signed election.
+    ///
+    /// Maps hash of the key image of the voter to the one-time key the vote was cast from.
+    pub fn key_images(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, Hash, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.key_images", &election_id, self.view.as_ref())
+    }
+
+    /// Returns the one-time key the vote with the specific key image was cast from.
+    pub fn key_image(&self, election_id: u64, key_image: &PublicKey) -> Option<PublicKey> {
+        self.key_images(election_id).get(&key_image.hash())
+    }
+
+    /// Returns an immutable version of mix votes table of the mixnet election.
+    ///
+    /// Maps hash of the voter id to the ciphertext of the vote.
+    pub fn mix_votes(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, Hash, Ciphertext> {
+        ProofMapIndex::new_in_family("voteservice.mix_votes", &election_id, self.view.as_ref())
+    }
+
+    /// Returns ciphertext of the vote of the specific voter.
+    pub fn mix_vote(&self, election_id: u64, voter: &PublicKey) -> Option<Ciphertext> {
+        self.mix_votes(election_id).get(&voter.hash())
+    }
+
+    /// Returns an immutable version of mixes list of the mixnet election.
+    ///
+    /// Every mix shuffles the output of the previous one, the first mix shuffles the votes.
+    pub fn mixes(&self, election_id: u64) -> ProofListIndex<&dyn Snapshot, Mix> {
+        ProofListIndex::new_in_family("voteservice.mixes", &election_id, self.view.as_ref())
+    }
+
+    /// Returns output of the last mix of the election, or the votes if there are no mixes.
+    pub fn mixed_votes(&self, election_id: u64) -> Vec<Ciphertext> {
+        match self.mixes(election_id).last() {
+            Some(mix) => mix.ciphertexts(),
+            None => self.mix_votes(election_id).values().collect(),
+        }
+    }
+
+    /// Returns whether enough validators shuffled votes of the election,
+    /// so they can be decrypted. Always true for elections not in mixnet mode.
+    ///
+    /// Threshold of mixes is required, so at least one of them is made by an honest
+    /// validator, unless enough validators collude to decrypt single votes anyway.
+    pub fn is_mixing_complete(&self, election: &Election) -> bool {
+        election.election_mode() != Some(ElectionMode::Mixnet)
+            || self.mixes(election.id()).len() >= u64::from(self.config().threshold)
+    }
+
+    /// Returns service key of the validator, which turn to shuffle votes of the mixnet
+    /// election starts at the specified height.
+    ///
+    /// After voting is over, validators which have not shuffled the votes yet take turns
+    /// of `MIX_TURN_BLOCKS` blocks, so they do not race for the same stage of mixing.
+    /// If a shuffle does not get into the blockchain in time, the next validator makes it.
+    pub fn mixer(&self, election: &Election, height: u64) -> Option<PublicKey> {
+        if election.phase(height) != ElectionPhase::Closed || self.is_mixing_complete(election) {
+            return None;
+        }
+
+        let blocks = height - election.end_height();
+        if blocks % MIX_TURN_BLOCKS != 0 {
+            return None;
+        }
+
+        let mixes = self.mixes(election.id());
+        let validators: Vec<PublicKey> = Schema::new(self.view.as_ref())
+            .actual_configuration()
+            .validator_keys
+            .iter()
+            .map(|keys| keys.service_key)
+            .filter(|key| !mixes.iter().any(|mix| mix.validator() == key))
+            .collect();
+        if validators.is_empty() {
+            return None;
+        }
+
+        let turn = (blocks / MIX_TURN_BLOCKS) as usize % validators.len();
+        Some(validators[turn])
+    }
+
+    /// Returns an immutable version of vote commitments table of the commit-reveal election.
+    pub fn commitments(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, PublicKey, Hash> {
+        ProofMapIndex::new_in_family("voteservice.commitments", &election_id, self.view.as_ref())
+    }
+
+    /// Returns vote commitment of the specific voter.
+    pub fn commitment(&self, election_id: u64, voter: &PublicKey) -> Option<Hash> {
+        self.commitments(election_id).get(voter)
+    }
+
+    /// Returns an immutable version of reveals table of the commit-reveal election.
+    pub fn reveals(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, PublicKey, Reveal> {
+        ProofMapIndex::new_in_family("voteservice.reveals", &election_id, self.view.as_ref())
+    }
+
+    /// Returns revealed vote of the specific voter.
+    pub fn reveal(&self, election_id: u64, voter: &PublicKey) -> Option<Reveal> {
+        self.reveals(election_id).get(voter)
+    }
+
+    /// Returns ids of the voters, which committed to their votes but did not reveal them.
+    pub fn unrevealed(&self, election_id: u64) -> Vec<PublicKey> {
+        let reveals = self.reveals(election_id);
+        self.commitments(election_id)
+            .keys()
+            .filter(|voter| !reveals.contains(voter))
+            .collect()
     }
 
-    /// Returns an immutable version of vote results table.
-    pub fn vote_results(&self) -> MapIndex<&dyn Snapshot, PublicKey, CandidateResult> {
-        MapIndex::new("voteservice.results", self.view.as_ref())
+    /// Returns an immutable version of instant-runoff rounds list of the ranked election.
+    ///
+    /// Rounds are stored on tally, the last round holds the winner.
+    pub fn rounds(&self, election_id: u64) -> ProofListIndex<&dyn Snapshot, Round> {
+        ProofListIndex::new_in_family("voteservice.rounds", &election_id, self.view.as_ref())
     }
 
-    /// Returns a specific candidate vote result data.
-    pub fn candidate_result(&self, pub_key: &PublicKey) -> Option<CandidateResult> {
-        self.vote_results().get(pub_key)
+    /// Returns an immutable version of single transferable vote rounds list of the election.
+    ///
+    /// Rounds are stored on tally, candidates elected in all rounds are the winners.
+    pub fn stv_rounds(&self, election_id: u64) -> ProofListIndex<&dyn Snapshot, StvRound> {
+        ProofListIndex::new_in_family("voteservice.stv_rounds", &election_id, self.view.as_ref())
+    }
+
+    /// Returns an immutable version of vote delegations table of the election.
+    ///
+    /// Maps a voter to another voter of the election, the vote is delegated to.
+    pub fn vote_delegations(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, PublicKey> {
+        ProofMapIndex::new_in_family(
+            "voteservice.vote_delegations",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns a voter, the vote of the specific voter is delegated to.
+    pub fn vote_delegation(&self, election_id: u64, voter: &PublicKey) -> Option<PublicKey> {
+        self.vote_delegations(election_id).get(voter)
+    }
+
+    /// Returns an immutable version of delegated weights table of the election.
+    ///
+    /// Delegation chains are resolved on tally.
+    pub fn delegated_weights(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, DelegatedWeight> {
+        ProofMapIndex::new_in_family(
+            "voteservice.delegated_weights",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of voter weight changes list of the election.
+    pub fn weight_changes(&self, election_id: u64) -> ProofListIndex<&dyn Snapshot, WeightChange> {
+        ProofListIndex::new_in_family(
+            "voteservice.weight_changes",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of replaced votes list of the election.
+    ///
+    /// Keeps every vote replaced by re-voting for audit.
+    pub fn replaced_votes(&self, election_id: u64) -> ProofListIndex<&dyn Snapshot, ReplacedVote> {
+        ProofListIndex::new_in_family(
+            "voteservice.replaced_votes",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of rejected votes list of the election.
+    ///
+    /// Ballots of ranked, approval, score and referendum elections are checked
+    /// against candidates only on tally, rejected ones are listed here.
+    pub fn rejected_votes(&self, election_id: u64) -> ProofListIndex<&dyn Snapshot, RejectedVote> {
+        ProofListIndex::new_in_family(
+            "voteservice.rejected_votes",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of approval results table of the election.
+    pub fn approval_results(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, ApprovalResult> {
+        ProofMapIndex::new_in_family(
+            "voteservice.approval_results",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of score results table of the election.
+    pub fn score_results(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, ScoreResult> {
+        ProofMapIndex::new_in_family("voteservice.score_results", &election_id, self.view.as_ref())
+    }
+
+    /// Returns an immutable version of pairwise results table of the election.
+    ///
+    /// Rows of the pairwise preferences matrix are stored on tally of ranked ballots,
+    /// in order of the candidates table.
+    pub fn pairwise_results(
+        &self,
+        election_id: u64,
+    ) -> ProofMapIndex<&dyn Snapshot, PublicKey, PairwiseResult> {
+        ProofMapIndex::new_in_family(
+            "voteservice.pairwise_results",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns an immutable version of referendum results list of the election.
+    ///
+    /// The list holds the only result once the referendum is tallied.
+    pub fn referendum_results(
+        &self,
+        election_id: u64,
+    ) -> ProofListIndex<&dyn Snapshot, ReferendumResult> {
+        ProofListIndex::new_in_family(
+            "voteservice.referendum_results",
+            &election_id,
+            self.view.as_ref(),
+        )
+    }
+
+    /// Returns tallied result of the specific referendum.
+    pub fn referendum_result(&self, election_id: u64) -> Option<ReferendumResult> {
+        self.referendum_results(election_id).get(0)
+    }
+
+    /// Returns an immutable version of vote keys table of the election.
+    ///
+    /// Maps one-time key of every encrypted vote, including replaced ones, to the vote id,
+    /// so a key can not be used twice.
+    pub fn vote_keys(&self, election_id: u64) -> ProofMapIndex<&dyn Snapshot, PublicKey, Hash> {
+        ProofMapIndex::new_in_family("voteservice.vote_keys", &election_id, self.view.as_ref())
+    }
+
+    /// Returns id of the vote cast with the specific one-time key.
+    pub fn vote_key(&self, election_id: u64, vote_key: &PublicKey) -> Option<Hash> {
+        self.vote_keys(election_id).get(vote_key)
+    }
+
+    /// Returns root hashes of the election tables.
+    ///
+    /// Order of the tables matters: it is used to check proofs of the election data.
+    pub fn election_state(&self, election_id: u64) -> Vec<Hash> {
+        vec![
+            self.candidates(election_id).merkle_root(),
+            self.voters(election_id).merkle_root(),
+            self.votes(election_id).merkle_root(),
+            self.delegates(election_id).merkle_root(),
+            self.decryption_shares(election_id).merkle_root(),
+            self.decrypted_results(election_id).merkle_root(),
+            self.ballots(election_id).merkle_root(),
+            self.encrypted_totals(election_id).merkle_root(),
+            self.token_issuances(election_id).merkle_root(),
+            self.spent_tokens(election_id).merkle_root(),
+            self.key_images(election_id).merkle_root(),
+            self.mix_votes(election_id).merkle_root(),
+            self.mixes(election_id).merkle_root(),
+            self.commitments(election_id).merkle_root(),
+            self.reveals(election_id).merkle_root(),
+            self.rounds(election_id).merkle_root(),
+            self.stv_rounds(election_id).merkle_root(),
+            self.approval_results(election_id).merkle_root(),
+            self.score_results(election_id).merkle_root(),
+            self.pairwise_results(election_id).merkle_root(),
+            self.weight_changes(election_id).merkle_root(),
+            self.vote_delegations(election_id).merkle_root(),
+            self.delegated_weights(election_id).merkle_root(),
+            self.replaced_votes(election_id).merkle_root(),
+            self.revoked_voters(election_id).merkle_root(),
+            self.rejected_votes(election_id).merkle_root(),
+            self.referendum_results(election_id).merkle_root(),
+            self.vote_keys(election_id).merkle_root(),
+            self.vote_choices(election_id).merkle_root(),
+        ]
+    }
+
+    /// Returns the state hash of vote service.
+    ///
+    /// Order of the tables matters: it defines table indexes in the blockchain state.
+    pub fn state_hash(&self) -> Vec<Hash> {
+        vec![
+            self.elections().merkle_root(),
+            self.election_hashes().merkle_root(),
+        ]
     }
 }
 
 /// A mutable version of schema.
 impl<'a> VoteServiceSchema<&'a mut Fork> {
-    /// Returns a mutable version of candidates table.
-    pub fn candidates_mut(&mut self) -> MapIndex<&mut Fork, PublicKey, Candidate> {
-        MapIndex::new("voteservice.candidates", &mut self.view)
+    /// Returns a mutable version of elections table.
+    pub fn elections_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, Election> {
+        ProofMapIndex::new("voteservice.elections", &mut self.view)
+    }
+
+    /// Returns a mutable version of election hashes table.
+    pub fn election_hashes_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, Hash> {
+        ProofMapIndex::new("voteservice.election_hashes", &mut self.view)
+    }
+
+    /// Returns a mutable version of candidates table of the election.
+    pub fn candidates_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, Candidate> {
+        ProofMapIndex::new_in_family("voteservice.candidates", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of voter table of the election.
+    pub fn voters_mut(&mut self, election_id: u64) -> ProofMapIndex<&mut Fork, PublicKey, Voter> {
+        ProofMapIndex::new_in_family("voteservice.voters", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of revoked voters table of the election.
+    pub fn revoked_voters_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, Voter> {
+        ProofMapIndex::new_in_family("voteservice.revoked_voters", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of votes table of the election.
+    pub fn votes_mut(&mut self, election_id: u64) -> ProofMapIndex<&mut Fork, Hash, EncryptedVote> {
+        ProofMapIndex::new_in_family("voteservice.votes", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of delegates table of the election.
+    pub fn delegates_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.delegates", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of ballots table of the homomorphic election.
+    pub fn ballots_mut(&mut self, election_id: u64) -> ProofMapIndex<&mut Fork, Hash, Ballot> {
+        ProofMapIndex::new_in_family("voteservice.ballots", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of encrypted totals table of the homomorphic election.
+    pub fn encrypted_totals_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, Ciphertext> {
+        ProofMapIndex::new_in_family("voteservice.encrypted_totals", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of vote choices table of the election.
+    pub fn vote_choices_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, Hash, Ciphertext> {
+        ProofMapIndex::new_in_family("voteservice.vote_choices", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of decryption shares table of the election.
+    pub fn decryption_shares_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, DecryptionShare> {
+        ProofMapIndex::new_in_family("voteservice.decryption_shares", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of decrypted results table of the election.
+    pub fn decrypted_results_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, DecryptedCandidateResult> {
+        ProofMapIndex::new_in_family("voteservice.decrypted_results", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of token issuances table of the anonymous election.
+    pub fn token_issuances_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, TokenIssuance> {
+        ProofMapIndex::new_in_family("voteservice.token_issuances", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of spent tokens table of the anonymous election.
+    pub fn spent_tokens_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, Hash, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.spent_tokens", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of key images table of the ring signed election.
+    pub fn key_images_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, Hash, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.key_images", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of mix votes table of the mixnet election.
+    pub fn mix_votes_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, Hash, Ciphertext> {
+        ProofMapIndex::new_in_family("voteservice.mix_votes", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of mixes list of the mixnet election.
+    pub fn mixes_mut(&mut self, election_id: u64) -> ProofListIndex<&mut Fork, Mix> {
+        ProofListIndex::new_in_family("voteservice.mixes", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of vote commitments table of the commit-reveal election.
+    pub fn commitments_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, Hash> {
+        ProofMapIndex::new_in_family("voteservice.commitments", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of reveals table of the commit-reveal election.
+    pub fn reveals_mut(&mut self, election_id: u64) -> ProofMapIndex<&mut Fork, PublicKey, Reveal> {
+        ProofMapIndex::new_in_family("voteservice.reveals", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of instant-runoff rounds list of the ranked election.
+    pub fn rounds_mut(&mut self, election_id: u64) -> ProofListIndex<&mut Fork, Round> {
+        ProofListIndex::new_in_family("voteservice.rounds", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of single transferable vote rounds list of the election.
+    pub fn stv_rounds_mut(&mut self, election_id: u64) -> ProofListIndex<&mut Fork, StvRound> {
+        ProofListIndex::new_in_family("voteservice.stv_rounds", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of vote delegations table of the election.
+    pub fn vote_delegations_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, PublicKey> {
+        ProofMapIndex::new_in_family("voteservice.vote_delegations", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of delegated weights table of the election.
+    pub fn delegated_weights_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, DelegatedWeight> {
+        ProofMapIndex::new_in_family("voteservice.delegated_weights", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of voter weight changes list of the election.
+    pub fn weight_changes_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofListIndex<&mut Fork, WeightChange> {
+        ProofListIndex::new_in_family("voteservice.weight_changes", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of replaced votes list of the election.
+    pub fn replaced_votes_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofListIndex<&mut Fork, ReplacedVote> {
+        ProofListIndex::new_in_family("voteservice.replaced_votes", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of rejected votes list of the election.
+    pub fn rejected_votes_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofListIndex<&mut Fork, RejectedVote> {
+        ProofListIndex::new_in_family("voteservice.rejected_votes", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of vote keys table of the election.
+    pub fn vote_keys_mut(&mut self, election_id: u64) -> ProofMapIndex<&mut Fork, PublicKey, Hash> {
+        ProofMapIndex::new_in_family("voteservice.vote_keys", &election_id, &mut self.view)
+    }
+
+    /// Returns a mutable version of referendum results list of the election.
+    pub fn referendum_results_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofListIndex<&mut Fork, ReferendumResult> {
+        ProofListIndex::new_in_family(
+            "voteservice.referendum_results",
+            &election_id,
+            &mut self.view,
+        )
+    }
+
+    /// Returns a mutable version of approval results table of the election.
+    pub fn approval_results_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, ApprovalResult> {
+        ProofMapIndex::new_in_family("voteservice.approval_results", &election_id, &mut self.view)
     }
 
-    /// Returns a mutable version of voter table.
-    pub fn voters_mut(&mut self) -> MapIndex<&mut Fork, PublicKey, Voter> {
-        MapIndex::new("voteservice.voters", &mut self.view)
+    /// Returns a mutable version of score results table of the election.
+    pub fn score_results_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, ScoreResult> {
+        ProofMapIndex::new_in_family("voteservice.score_results", &election_id, &mut self.view)
     }
 
-    /// Returns a mutable version of votes table.
-    pub fn votes_mut(&mut self) -> MapIndex<&mut Fork, Hash, EncryptedVote> {
-        MapIndex::new("voteservice.votes", &mut self.view)
+    /// Returns a mutable version of pairwise results table of the election.
+    pub fn pairwise_results_mut(
+        &mut self,
+        election_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, PairwiseResult> {
+        ProofMapIndex::new_in_family("voteservice.pairwise_results", &election_id, &mut self.view)
     }
 
-    /// Returns a mutable version of vote results table.
-    pub fn vote_results_mut(&mut self) -> MapIndex<&mut Fork, PublicKey, CandidateResult> {
-        MapIndex::new("voteservice.results", &mut self.view)
+    /// Recalculates aggregated hash of the election state.
+    ///
+    /// Must be called after any change of the election tables.
+    pub fn update_election_hash(&mut self, election_id: u64) {
+        let hash = election_hash(&self.election_state(election_id));
+        self.election_hashes_mut().put(&election_id.hash(), hash);
     }
 }
@@ -1,84 +1,164 @@
-use exonum::crypto::{self, PublicKey, SecretKey};
-use exonum::storage::StorageValue;
+use exonum::blockchain::{Blockchain, Schema, Transaction, TransactionErrorType};
+use exonum::crypto::{self, CryptoHash, Hash, PublicKey, SecretKey, Seed, SEED_LENGTH};
+use exonum::encoding::serialize::encode_hex;
+use exonum::helpers::Height;
+use exonum::messages::Message;
+use exonum::storage::{Fork, StorageValue};
 use exonum_testkit::{ApiKind, TestKit, TestKitBuilder};
 
+use curve25519_dalek::scalar::Scalar;
+use rand;
+use sha2::Sha512;
 use std::borrow::Cow;
 
-use agreement;
-use api::BlockQuery;
-use cipher::{self, Cipher};
+use api::{BlockQuery, ElectionQuery, VoteProof, VoterQuery};
+use blind::{self, Token};
+use errors::Error;
+use cipher;
+use cmd;
 use config::VoteServiceConfig;
+use elgamal;
+use group;
+use lsag;
+use mixnet;
+use proofs;
+use ranked;
+use roll;
 use schema::{
-    Candidate, DecryptedCandidateResult, EncryptedVote, Vote, VoteResult, VoteServiceSchema, Voter,
+    self, ApprovalResult, ApprovalVote, Ballot, Candidate, CandidateScore, Ciphertext,
+    DecryptedCandidateResult, DelegatedWeight, Election, ElectionMode, ElectionPhase,
+    EncryptedVote, Majority, PairwiseResult, RankedVote, ReferendumChoice, ReferendumResult,
+    ReferendumVote, RejectedVote, RejectionReason, ReplacedVote, Round, ScoreResult, ScoreVote,
+    StvRound, TokenIssuance, Vote, VoteServiceSchema, Voter, WeightChange,
 };
-use transactions::{TxAddVote, TxCreateCandidate, TxCreateVoter};
-use VoteService;
+use threshold::{self, DealtKeys};
+use transactions::{
+    TxAddAnonymousVote, TxAddApprovalVote, TxAddBallot, TxAddMixVote, TxAddRankedVote,
+    TxAddReferendumVote, TxAddRingVote, TxAddScoreVote, TxAddVote, TxAuthorizeDelegate,
+    TxCommitVote, TxCreateCandidate, TxCreateElection, TxCreateVoter, TxDecryptionShare,
+    TxIssueToken, TxRequestToken, TxRevealVote, TxRevokeVoter, TxSetVoterWeight, TxShuffle,
+    TxTallyElection, TxTokenCommitment, TxTransferVote, TxUpdateCandidate, TxWithdrawCandidate,
+};
+use zkp;
+use {VoteService, SERVICE_ID};
+
+const ELECTION_ID: u64 = 1;
+const START_HEIGHT: u64 = 10;
+const END_HEIGHT: u64 = 20;
+const REVEAL_END_HEIGHT: u64 = 30;
+const VALIDATORS: u16 = 3;
+const THRESHOLD: u16 = 2;
 
 #[test]
 fn test_create_candidate() {
     let (mut testkit, _) = init_testkit();
-    let (tx, _) = create_candidate(&mut testkit, "Alice", "Some info");
+    let (tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
 
-    let candidate = get_candidate(&testkit, tx.pub_key());
+    let candidate = get_candidate(&testkit, ELECTION_ID, tx.pub_key());
 
     assert_eq!(candidate.pub_key(), tx.pub_key());
     assert_eq!(candidate.name(), "Alice");
     assert_eq!(candidate.info(), "Some info");
+    assert!(!candidate.withdrawn());
 }
 
 #[test]
 fn test_create_voter() {
     let (mut testkit, _) = init_testkit();
-    let (tx, _) = create_voter(&mut testkit, "Bob");
+    let (tx, _) = create_voter(&mut testkit, ELECTION_ID, "Bob");
 
-    let voter = get_voter(&testkit, tx.pub_key());
+    let voter = get_voter(&testkit, ELECTION_ID, tx.pub_key());
 
     assert_eq!(voter.pub_key(), tx.pub_key());
     assert_eq!(voter.name(), "Bob");
+    assert_eq!(voter.weight(), 1);
 }
 
 #[test]
-fn test_get_results() {
-    let (mut testkit, key_pair) = init_testkit();
+fn test_unauthorized_registration() {
+    let (mut testkit, _) = init_testkit();
 
-    let (cand_tx, _) = create_candidate(&mut testkit, "Alice", "Some info");
-    let cand = get_candidate(&testkit, cand_tx.pub_key());
+    // Self-signed registration is not allowed any more.
+    let (public, secret) = crypto::gen_keypair();
+    let tx = TxCreateVoter::new(&public, &public, ELECTION_ID, "Bob", 1, &secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::Unauthorized);
+    assert!(try_get_voter(&testkit, ELECTION_ID, &public).is_none());
 
-    let (voter_tx, _) = create_voter(&mut testkit, "Bob");
-    let voter = get_voter(&testkit, voter_tx.pub_key());
+    let tx = TxCreateCandidate::new(&public, &public, ELECTION_ID, "Alice", "Info", &secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::Unauthorized);
+    assert!(try_get_candidate(&testkit, ELECTION_ID, &public).is_none());
+}
 
-    add_vote(&mut testkit, voter.pub_key(), cand.pub_key());
+#[test]
+fn test_unauthorized_election() {
+    let (mut testkit, _) = init_testkit();
 
-    let enc_result = get_vote_result(&testkit);
-    assert_eq!(enc_result.candidate_results().len(), 1);
+    let (public, secret) = crypto::gen_keypair();
+    let tx = TxCreateElection::new(
+        &public,
+        ELECTION_ID + 1,
+        "Election",
+        "Some description",
+        START_HEIGHT,
+        END_HEIGHT,
+        0,
+        ElectionMode::Encrypted as u8,
+        1,
+        0,
+        0,
+        0,
+        false,
+        &Hash::zero(),
+        &secret,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::Unauthorized);
+    assert!(VoteServiceSchema::new(&testkit.snapshot())
+        .election(ELECTION_ID + 1)
+        .is_none());
+}
 
-    let service_pub_key = enc_result.pub_key();
-    let ephemeral = agreement::generate_ephemeral(service_pub_key.as_ref(), key_pair.secret);
+#[test]
+fn test_no_results_before_tally() {
+    let (mut testkit, keys) = init_testkit();
 
-    let cand_res = &enc_result.candidate_results()[0];
-    assert_eq!(cand_res.candidate(), cand.pub_key());
-    assert_eq!(cand_res.votes().len(), 1);
-    assert_eq!(cand_res.vote_num(), 1);
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let cand = get_candidate(&testkit, ELECTION_ID, cand_tx.pub_key());
 
-    let enc_vote = &cand_res.votes()[0];
-    let dec_vote = decrypt_vote(&enc_vote, &ephemeral.ephemeral_key);
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let voter = get_voter(&testkit, ELECTION_ID, voter_tx.pub_key());
+
+    start_voting(&mut testkit);
+    let (tx, _) = add_vote(&mut testkit, ELECTION_ID, voter.pub_key(), &voter_key, cand.pub_key());
+
+    // Only the ciphertext is public, the candidate is known after tally.
+    assert!(get_vote_result_decrypted(&testkit, ELECTION_ID).is_empty());
+    let enc_vote = get_vote(&testkit, ELECTION_ID, voter.pub_key());
+    assert_eq!(enc_vote.data(), tx.vote_data());
+
+    let dec_vote = decrypt_vote(&keys, &enc_vote).unwrap();
     assert_eq!(dec_vote.from(), voter.pub_key());
     assert_eq!(dec_vote.to(), cand.pub_key());
 }
 
 #[test]
 fn test_get_decrypted_results() {
-    let (mut testkit, _) = init_testkit();
+    let (mut testkit, keys) = init_testkit();
 
-    let (cand_tx, _) = create_candidate(&mut testkit, "Alice", "Some info");
-    let cand = get_candidate(&testkit, cand_tx.pub_key());
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let cand = get_candidate(&testkit, ELECTION_ID, cand_tx.pub_key());
 
-    let (voter_tx, _) = create_voter(&mut testkit, "Bob");
-    let voter = get_voter(&testkit, voter_tx.pub_key());
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let voter = get_voter(&testkit, ELECTION_ID, voter_tx.pub_key());
 
-    add_vote(&mut testkit, voter.pub_key(), cand.pub_key());
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter.pub_key(), &voter_key, cand.pub_key());
 
-    let dec_result = get_vote_result_decrypted(&testkit);
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+    let dec_result = get_vote_result_decrypted(&testkit, ELECTION_ID);
 
     assert_eq!(dec_result.len(), 1);
 
@@ -93,132 +173,3284 @@ fn test_get_decrypted_results() {
 }
 
 #[test]
-fn test_get_block() {
-    let (mut testkit, _) = init_testkit();
+fn test_weighted_tally() {
+    let (mut testkit, keys) = init_testkit();
 
-    let (cand_tx, _) = create_candidate(&mut testkit, "Alice", "Some info");
-    let cand = get_candidate(&testkit, cand_tx.pub_key());
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, ELECTION_ID, "Carol", "Some info");
+    let (bob, bob_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Bob", 10);
+    let (dave, dave_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Dave", 30);
+    let (eve, eve_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Eve", 50);
 
-    let (voter_tx, _) = create_voter(&mut testkit, "Bob");
-    let voter = get_voter(&testkit, voter_tx.pub_key());
+    let (tx, _) = create_weighted_voter(&mut testkit, ELECTION_ID, "Frank", 0);
+    assert_tx_error(&testkit, &tx, Error::InvalidWeight);
 
-    let (_, height, _) = add_vote(&mut testkit, voter.pub_key(), cand.pub_key());
+    // Votes of other modes are not weighted.
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Approval);
+    let (tx, _) = create_weighted_voter(&mut testkit, election_id, "Frank", 2);
+    assert_tx_error(&testkit, &tx, Error::WeightNotSupported);
+    let (frank, _) = create_voter(&mut testkit, election_id, "Frank");
+    let tx = set_voter_weight(&mut testkit, election_id, frank.pub_key(), 2);
+    assert_tx_error(&testkit, &tx, Error::WeightNotSupported);
 
-    let block_height = get_block(&testkit, voter.pub_key());
+    // Weight changes are recorded until voting starts.
+    set_voter_weight(&mut testkit, ELECTION_ID, bob.pub_key(), 100);
+    assert_eq!(get_voter(&testkit, ELECTION_ID, bob.pub_key()).weight(), 100);
 
-    assert_eq!(height, block_height);
-}
+    start_voting(&mut testkit);
+    let tx = set_voter_weight(&mut testkit, ELECTION_ID, bob.pub_key(), 1);
+    assert_tx_error(&testkit, &tx, Error::RegistrationClosed);
 
-fn init_testkit() -> (TestKit, agreement::KeyPair) {
-    let author_key_pair = agreement::generate_key_pair();
+    add_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, alice.pub_key());
+    add_vote(&mut testkit, ELECTION_ID, dave.pub_key(), &dave_key, carol.pub_key());
+    add_vote(&mut testkit, ELECTION_ID, eve.pub_key(), &eve_key, carol.pub_key());
 
-    let cfg = VoteServiceConfig {
-        author_public_key: Some(PublicKey::from_slice(&author_key_pair.public.clone()).unwrap()),
+    let changes = get_weight_changes(&testkit, ELECTION_ID);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].voter(), bob.pub_key());
+    assert_eq!((changes[0].old_weight(), changes[0].new_weight()), (10, 100));
+
+    let weights = |results: Vec<(PublicKey, u64)>| {
+        let mut weights = results;
+        weights.sort_by_key(|&(candidate, _)| candidate != *alice.pub_key());
+        weights.into_iter().map(|(_, weight)| weight).collect::<Vec<_>>()
     };
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+    let dec_weights = get_vote_result_decrypted(&testkit, ELECTION_ID)
+        .iter()
+        .map(|res| (*res.candidate(), res.vote_num()))
+        .collect();
+    assert_eq!(weights(dec_weights), vec![100, 80]);
+}
+
+#[test]
+fn test_weight_overflow() {
+    let (mut testkit, keys) = init_testkit();
 
-    agreement::init_ephemeral(&author_key_pair.public.clone());
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (bob, bob_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Bob", u64::max_value());
+    let (dave, dave_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Dave", 1);
 
-    (
-        TestKitBuilder::validator()
-            .with_service(VoteService { config: cfg })
-            .create(),
-        author_key_pair,
-    )
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, alice.pub_key());
+    add_vote(&mut testkit, ELECTION_ID, dave.pub_key(), &dave_key, alice.pub_key());
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxTallyElection::new(&admin, ELECTION_ID, &admin_key);
+    assert_tx_error(&testkit, &tx, Error::WeightOverflow);
+    assert_eq!(get_phase(&testkit, ELECTION_ID), ElectionPhase::Closed);
 }
 
-fn create_candidate(
-    testkit: &mut TestKit,
-    name: &str,
-    info: &str,
-) -> (TxCreateCandidate, SecretKey) {
-    let (public, secret) = crypto::gen_keypair();
-    let tx = TxCreateCandidate::new(&public, name, info, &secret);
+#[test]
+fn test_liquid_delegation() {
+    let (mut testkit, keys) = init_testkit();
+
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, ELECTION_ID, "Carol", "Some info");
+    let (bob, bob_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Bob", 1);
+    let (dave, dave_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Dave", 2);
+    let (eve, eve_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Eve", 4);
+    let (frank, frank_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Frank", 8);
+    let (grace, grace_key) = create_weighted_voter(&mut testkit, ELECTION_ID, "Grace", 16);
+
+    start_voting(&mut testkit);
+    transfer_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, dave.pub_key());
+    transfer_vote(&mut testkit, ELECTION_ID, dave.pub_key(), &dave_key, eve.pub_key());
+    transfer_vote(&mut testkit, ELECTION_ID, frank.pub_key(), &frank_key, bob.pub_key());
+
+    let tx = transfer_vote(&mut testkit, ELECTION_ID, eve.pub_key(), &eve_key, frank.pub_key());
+    assert_tx_error(&testkit, &tx, Error::DelegationCycle);
+
+    // Transfer to the voter itself revokes the previous one.
+    transfer_vote(&mut testkit, ELECTION_ID, grace.pub_key(), &grace_key, eve.pub_key());
+    transfer_vote(&mut testkit, ELECTION_ID, grace.pub_key(), &grace_key, grace.pub_key());
+    assert_eq!(get_vote_proxies(&testkit, ELECTION_ID).len(), 3);
+
+    // Direct vote of Dave overrides the delegation, so Bob and Frank follow Dave.
+    add_vote(&mut testkit, ELECTION_ID, eve.pub_key(), &eve_key, alice.pub_key());
+    add_vote(&mut testkit, ELECTION_ID, dave.pub_key(), &dave_key, carol.pub_key());
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+
+    let mut weights: Vec<(PublicKey, u64)> = get_vote_result_decrypted(&testkit, ELECTION_ID)
+        .iter()
+        .map(|res| (*res.candidate(), res.vote_num()))
+        .collect();
+    weights.sort_by_key(|&(candidate, _)| candidate != *alice.pub_key());
+    assert_eq!(weights, vec![(*alice.pub_key(), 4), (*carol.pub_key(), 11)]);
+
+    let delegated = get_delegated_weights(&testkit, ELECTION_ID);
+    assert_eq!(delegated.len(), 1);
+    assert_eq!(delegated[0].delegate(), dave.pub_key());
+    assert_eq!(delegated[0].weight(), 9);
+
+    let mut delegators = delegated[0].delegators();
+    delegators.sort();
+    let mut expected = vec![*bob.pub_key(), *frank.pub_key()];
+    expected.sort();
+    assert_eq!(delegators, expected);
+}
+
+#[test]
+fn test_revoting() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_revoting_election(&mut testkit, election_id, ElectionMode::Encrypted);
+
+    let (tx, _) = create_revoting_election(&mut testkit, ELECTION_ID + 2, ElectionMode::Ranked);
+    assert_tx_error(&testkit, &tx, Error::InvalidRevoting);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (bob, bob_key) = create_weighted_voter(&mut testkit, election_id, "Bob", 3);
+    let (dave, dave_key) = create_voter(&mut testkit, election_id, "Dave");
+    let (eve, _) = create_candidate(&mut testkit, ELECTION_ID, "Eve", "Some info");
+    let (frank, frank_key) = create_voter(&mut testkit, ELECTION_ID, "Frank");
+
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, election_id, bob.pub_key(), &bob_key, alice.pub_key());
+    add_vote(&mut testkit, election_id, dave.pub_key(), &dave_key, alice.pub_key());
+    add_vote(&mut testkit, election_id, bob.pub_key(), &bob_key, carol.pub_key());
+    let (tx, height) =
+        add_vote(&mut testkit, election_id, bob.pub_key(), &bob_key, carol.pub_key());
+    assert_eq!(get_vote(&testkit, election_id, bob.pub_key()).data(), tx.vote_data());
+
+    // Re-voting is disabled by default.
+    add_vote(&mut testkit, ELECTION_ID, frank.pub_key(), &frank_key, eve.pub_key());
+    let (tx, _) = add_vote(&mut testkit, ELECTION_ID, frank.pub_key(), &frank_key, eve.pub_key());
+    assert_tx_error(&testkit, &tx, Error::VoteAlreadyExists);
+
+    // Replaced votes are kept encrypted.
+    let replaced = get_replaced_votes(&testkit, election_id);
+    assert_eq!(replaced.len(), 2);
+    assert!(replaced.iter().all(|vote| vote.voter() == bob.pub_key()));
+    assert_eq!(decrypt_vote(&keys, &replaced[0].vote()).unwrap().to(), alice.pub_key());
+    assert_eq!(decrypt_vote(&keys, &replaced[1].vote()).unwrap().to(), carol.pub_key());
+    assert_eq!(replaced[1].height(), height);
+
+    // Neither a replaced vote can be replayed, nor a vote of another voter can be copied.
+    let replay = replaced[0].vote();
+    let vote_data = replay.data();
+    let tx = TxAddVote::new(
+        bob.pub_key(),
+        election_id,
+        bob.pub_key(),
+        replay.pub_key(),
+        vote_data.clone(),
+        replay.pub_key(),
+        vec![],
+        0,
+        vec![],
+        &bob_key,
+    );
     testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::VoteKeyAlreadyUsed);
+    let tx = TxAddVote::new(
+        dave.pub_key(),
+        election_id,
+        dave.pub_key(),
+        replay.pub_key(),
+        vote_data,
+        replay.pub_key(),
+        vec![],
+        0,
+        vec![],
+        &dave_key,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::VoteKeyAlreadyUsed);
+    assert_eq!(get_replaced_votes(&testkit, election_id).len(), 2);
 
-    (tx, secret)
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    let mut dec_results: Vec<(PublicKey, u64)> = get_vote_result_decrypted(&testkit, election_id)
+        .iter()
+        .map(|res| (*res.candidate(), res.vote_num()))
+        .collect();
+    dec_results.sort_by_key(|&(candidate, _)| candidate != *alice.pub_key());
+    assert_eq!(dec_results, vec![(*alice.pub_key(), 1), (*carol.pub_key(), 3)]);
+}
+
+#[test]
+fn test_candidate_withdrawal() {
+    let (mut testkit, keys) = init_testkit();
+
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, ELECTION_ID, "Carol", "Some info");
+    let (mallory, _) = create_candidate(&mut testkit, ELECTION_ID, "Mallory", "Some info");
+    let (bob, bob_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (dave, dave_key) = create_voter(&mut testkit, ELECTION_ID, "Dave");
+
+    update_candidate(&mut testkit, ELECTION_ID, carol.pub_key(), "New info");
+    assert_eq!(get_candidate(&testkit, ELECTION_ID, carol.pub_key()).info(), "New info");
+
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, mallory.pub_key());
+    withdraw_candidate(&mut testkit, ELECTION_ID, mallory.pub_key());
+    assert!(get_candidate(&testkit, ELECTION_ID, mallory.pub_key()).withdrawn());
+
+    let tx = withdraw_candidate(&mut testkit, ELECTION_ID, mallory.pub_key());
+    assert_tx_error(&testkit, &tx, Error::CandidateWithdrawn);
+    let tx = update_candidate(&mut testkit, ELECTION_ID, mallory.pub_key(), "New info");
+    assert_tx_error(&testkit, &tx, Error::CandidateWithdrawn);
+
+    add_vote(&mut testkit, ELECTION_ID, dave.pub_key(), &dave_key, alice.pub_key());
+
+    close_voting(&mut testkit);
+    let tx = withdraw_candidate(&mut testkit, ELECTION_ID, carol.pub_key());
+    assert_tx_error(&testkit, &tx, Error::VotingClosed);
+
+    // The vote of Bob stays stored, but is void.
+    assert!(try_get_vote(&testkit, ELECTION_ID, bob.pub_key()).is_some());
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+    let mut results: Vec<(PublicKey, u64)> = get_vote_result_decrypted(&testkit, ELECTION_ID)
+        .iter()
+        .map(|res| (*res.candidate(), res.vote_num()))
+        .collect();
+    results.sort_by_key(|&(candidate, _)| candidate != *alice.pub_key());
+    assert_eq!(results, vec![(*alice.pub_key(), 1), (*carol.pub_key(), 0)]);
+    let rejected = get_rejected_votes(&testkit, ELECTION_ID);
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].vote_id(), &bob.pub_key().hash());
+    assert_eq!(rejected[0].reason(), RejectionReason::CandidateWithdrawn as u8);
+}
+
+#[test]
+fn test_ranked_withdrawal() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Ranked);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (mallory, _) = create_candidate(&mut testkit, election_id, "Mallory", "Some info");
+    let (a, c, m) = (*alice.pub_key(), *carol.pub_key(), *mallory.pub_key());
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    let rankings = vec![vec![m, a], vec![a], vec![c]];
+    for (&(ref voter, ref key), ranking) in voters.iter().zip(rankings) {
+        add_ranked_vote(&mut testkit, election_id, (voter, key), ranking);
+    }
+    withdraw_candidate(&mut testkit, election_id, &m);
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+
+    // The ballot ranking Mallory first passes to Alice.
+    let rounds = get_rounds(&testkit, election_id);
+    assert_eq!(rounds.len(), 1);
+    let mut tallies: Vec<(PublicKey, u64)> = rounds[0]
+        .tallies()
+        .iter()
+        .map(|tally| (*tally.candidate(), tally.votes()))
+        .collect();
+    tallies.sort_by_key(|&(candidate, _)| candidate != a);
+    assert_eq!(tallies, vec![(a, 2), (c, 1)]);
+
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|res| *res.candidate() != m));
+    assert!(get_pairwise_results(&testkit, election_id)
+        .iter()
+        .all(|res| *res.candidate() != m));
+}
+
+#[test]
+fn test_voter_revocation() {
+    let (mut testkit, _) = init_testkit();
+
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (bob, bob_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (dave, _) = create_voter(&mut testkit, ELECTION_ID, "Dave");
+    transfer_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, dave.pub_key());
+
+    revoke_voter(&mut testkit, ELECTION_ID, bob.pub_key());
+    assert!(try_get_voter(&testkit, ELECTION_ID, bob.pub_key()).is_none());
+    assert!(get_vote_proxies(&testkit, ELECTION_ID).is_empty());
+    let revoked = get_revoked_voters(&testkit, ELECTION_ID);
+    assert_eq!(revoked.len(), 1);
+    assert_eq!(revoked[0].pub_key(), bob.pub_key());
+
+    let tx = revoke_voter(&mut testkit, ELECTION_ID, bob.pub_key());
+    assert_tx_error(&testkit, &tx, Error::VoterRevoked);
+
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxCreateVoter::new(&admin, bob.pub_key(), ELECTION_ID, "Bob", 2, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::VoterRevoked);
+
+    // Transfers of votes to the revoked voter are removed as well.
+    let (eve, eve_key) = create_voter(&mut testkit, ELECTION_ID, "Eve");
+    let (frank, _) = create_voter(&mut testkit, ELECTION_ID, "Frank");
+    transfer_vote(&mut testkit, ELECTION_ID, eve.pub_key(), &eve_key, frank.pub_key());
+    assert_eq!(get_vote_proxies(&testkit, ELECTION_ID).len(), 1);
+    revoke_voter(&mut testkit, ELECTION_ID, frank.pub_key());
+    assert!(get_vote_proxies(&testkit, ELECTION_ID).is_empty());
+
+    start_voting(&mut testkit);
+    let tx = revoke_voter(&mut testkit, ELECTION_ID, dave.pub_key());
+    assert_tx_error(&testkit, &tx, Error::RegistrationClosed);
+
+    let (tx, _) = add_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, alice.pub_key());
+    assert_tx_error(&testkit, &tx, Error::VoterNotFound);
+}
+
+#[test]
+fn test_roll_paths() {
+    let keys: Vec<PublicKey> = (0..7).map(|_| crypto::gen_keypair().0).collect();
+    let (outsider, _) = crypto::gen_keypair();
+    assert_eq!(roll::root(&[]), Hash::zero());
+
+    for len in 1..keys.len() + 1 {
+        let roll = &keys[..len];
+        let root = roll::root(roll);
+        for (index, key) in roll.iter().enumerate() {
+            let path = roll::path(roll, index).unwrap();
+            assert!(roll::verify(&root, key, index as u64, &path));
+            assert!(!roll::verify(&root, key, index as u64 ^ 1, &path));
+            assert!(!roll::verify(&root, &outsider, index as u64, &path));
+        }
+        assert!(roll::path(roll, len).is_none());
+    }
+}
+
+#[test]
+fn test_eligibility_roll() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    let roll: Vec<(PublicKey, SecretKey)> = (0..5).map(|_| crypto::gen_keypair()).collect();
+    let roll_keys: Vec<PublicKey> = roll.iter().map(|&(public, _)| public).collect();
+    let root = roll::root(&roll_keys);
+    create_roll_election(&mut testkit, election_id, ElectionMode::Encrypted, &root);
+
+    let (tx, _) = create_roll_election(&mut testkit, ELECTION_ID + 2, ElectionMode::Ranked, &root);
+    assert_tx_error(&testkit, &tx, Error::InvalidRollRoot);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (dave, dave_key) = create_voter(&mut testkit, election_id, "Dave");
+
+    // Revoked registration of a voter applies to the roll.
+    let (admin, admin_key) = admin_keypair();
+    let (ref carol, ref carol_key) = roll[2];
+    let tx = TxCreateVoter::new(&admin, carol, election_id, "Carol", 1, &admin_key);
+    testkit.create_block_with_transaction(tx);
+    revoke_voter(&mut testkit, election_id, carol);
+
+    start_voting(&mut testkit);
+    let proof = (2, roll::path(&roll_keys, 2).unwrap());
+    let signer = (carol, carol_key);
+    let (tx, _) = add_roll_vote(&mut testkit, election_id, signer, carol, alice.pub_key(), proof);
+    assert_tx_error(&testkit, &tx, Error::VoterRevoked);
+
+    let (ref bob, ref bob_key) = roll[3];
+    let proof = (3, roll::path(&roll_keys, 3).unwrap());
+    add_roll_vote(&mut testkit, election_id, (bob, bob_key), bob, alice.pub_key(), proof.clone());
+    assert!(try_get_vote(&testkit, election_id, bob).is_some());
+
+    let (tx, _) =
+        add_roll_vote(&mut testkit, election_id, (bob, bob_key), bob, alice.pub_key(), proof);
+    assert_tx_error(&testkit, &tx, Error::VoteAlreadyExists);
+
+    // Path of another voter or with a wrong index does not prove eligibility.
+    let (ref eve, ref eve_key) = roll[1];
+    let proof = (2, roll::path(&roll_keys, 1).unwrap());
+    let signer = (eve, eve_key);
+    let (tx, _) = add_roll_vote(&mut testkit, election_id, signer, eve, alice.pub_key(), proof);
+    assert_tx_error(&testkit, &tx, Error::NotInRoll);
+
+    let (mallory, mallory_key) = crypto::gen_keypair();
+    let proof = (0, roll::path(&roll_keys, 0).unwrap());
+    let signer = (&mallory, &mallory_key);
+    let (tx, _) =
+        add_roll_vote(&mut testkit, election_id, signer, &mallory, alice.pub_key(), proof);
+    assert_tx_error(&testkit, &tx, Error::NotInRoll);
+
+    // Voters of the roll can not have delegates.
+    let (ref frank, _) = roll[4];
+    let proof = (4, roll::path(&roll_keys, 4).unwrap());
+    let (tx, _) = add_roll_vote(&mut testkit, election_id, signer, frank, alice.pub_key(), proof);
+    assert_tx_error(&testkit, &tx, Error::SignerNotVoter);
+
+    add_vote(&mut testkit, election_id, dave.pub_key(), &dave_key, alice.pub_key());
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].vote_num(), 2);
+}
+
+#[test]
+fn test_parse_roster() {
+    let (public, _) = crypto::gen_keypair();
+    let key_hex = encode_hex(&public);
+
+    let csv = format!("name,weight,pub_key\nBob,3,{}\n\nDave,,\n", key_hex);
+    let entries = cmd::parse_csv_roster(&csv).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!((entries[0].name.as_str(), entries[0].weight), ("Bob", 3));
+    assert_eq!(entries[0].pub_key, Some(public));
+    assert_eq!((entries[1].name.as_str(), entries[1].weight), ("Dave", 1));
+    assert_eq!(entries[1].pub_key, None);
+
+    assert!(cmd::parse_csv_roster("name,weight\nBob\n").is_err());
+    assert!(cmd::parse_csv_roster("info\nSome info\n").is_err());
+
+    let json = format!(
+        r#"[{{"name": "Alice", "info": "Some info", "pub_key": "{}"}}, {{"name": "Carol"}}]"#,
+        key_hex
+    );
+    let entries = cmd::parse_json_roster(&json).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].info, "Some info");
+    assert_eq!(entries[0].pub_key, Some(public));
+    assert_eq!((entries[1].info.as_str(), entries[1].weight), ("", 1));
+}
+
+#[test]
+fn test_error_codes() {
+    // Every code is either unused or names the error with the same code.
+    for code in 0..=255 {
+        if let Some(error) = Error::from_code(code) {
+            assert_eq!(error as u8, code);
+        }
+    }
+    assert_eq!(Error::from_code(5), Some(Error::VoterNotFound));
+    assert_eq!(Error::from_code(Error::VoteKeyAlreadyUsed as u8), Some(Error::VoteKeyAlreadyUsed));
+    assert_eq!(Error::from_code(4), None);
+}
+
+#[test]
+fn test_vote_stored_as_sent() {
+    let (mut testkit, keys) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+
+    start_voting(&mut testkit);
+    let (tx, _) = add_vote(
+        &mut testkit,
+        ELECTION_ID,
+        voter_tx.pub_key(),
+        &voter_key,
+        cand_tx.pub_key(),
+    );
+
+    // Validators only store the ciphertext, so every node gets the same state.
+    let enc_vote = get_vote(&testkit, ELECTION_ID, voter_tx.pub_key());
+    assert_eq!(enc_vote.pub_key(), tx.vote_key());
+    assert_eq!(enc_vote.data(), tx.vote_data());
+
+    let vote = decrypt_vote(&keys, &enc_vote).unwrap();
+    assert_eq!(vote.from(), voter_tx.pub_key());
+    assert_eq!(vote.to(), cand_tx.pub_key());
+}
+
+#[test]
+fn test_state_hash_in_block() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+
+    let snapshot = testkit.snapshot();
+    let state_hash = VoteServiceSchema::new(&snapshot).state_hash();
+    let aggregator = Schema::new(&snapshot).state_hash_aggregator();
+
+    for (idx, hash) in state_hash.iter().enumerate() {
+        let key = Blockchain::service_table_unique_key(SERVICE_ID, idx);
+        assert_eq!(aggregator.get(&key).as_ref(), Some(hash));
+    }
+}
+
+#[test]
+fn test_tampered_votes_change_state_hash() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+
+    let snapshot = testkit.snapshot();
+    let state_hash = VoteServiceSchema::new(&snapshot).state_hash();
+    let election_state = VoteServiceSchema::new(&snapshot).election_state(ELECTION_ID);
+
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut schema = VoteServiceSchema::new(&mut fork);
+        let vote = schema.vote(ELECTION_ID, &voter_tx.pub_key().hash()).unwrap();
+        let mut data = vote.data();
+        data[0] ^= 1;
+        let tampered = EncryptedVote::new(vote.pub_key(), data);
+        schema
+            .votes_mut(ELECTION_ID)
+            .put(&voter_tx.pub_key().hash(), tampered);
+    }
+    assert_tampered_election(&mut fork, &state_hash, &election_state, 2);
 }
 
-fn try_get_candidate(testkit: &TestKit, pub_key: &PublicKey) -> Option<Candidate> {
+#[test]
+fn test_tampered_results_change_state_hash() {
+    let (mut testkit, keys) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+
     let snapshot = testkit.snapshot();
-    VoteServiceSchema::new(&snapshot).candidate(pub_key)
+    let state_hash = VoteServiceSchema::new(&snapshot).state_hash();
+    let election_state = VoteServiceSchema::new(&snapshot).election_state(ELECTION_ID);
+
+    let mut fork = testkit.blockchain_mut().fork();
+    {
+        let mut schema = VoteServiceSchema::new(&mut fork);
+        let tampered = DecryptedCandidateResult::new(cand_tx.pub_key(), vec![], 0);
+        schema
+            .decrypted_results_mut(ELECTION_ID)
+            .put(cand_tx.pub_key(), tampered);
+    }
+    assert_tampered_election(&mut fork, &state_hash, &election_state, 5);
+}
+
+#[test]
+fn test_concurrent_elections() {
+    let (mut testkit, keys) = init_testkit();
+    let other_election = ELECTION_ID + 1;
+    create_election(&mut testkit, other_election, "Other election");
+
+    let (alice_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (carol_tx, _) = create_candidate(&mut testkit, other_election, "Carol", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (other_voter_tx, other_voter_key) = create_voter(&mut testkit, other_election, "Bob");
+
+    // Candidate of one election can not be voted for in another one.
+    assert!(try_get_candidate(&testkit, other_election, alice_tx.pub_key()).is_none());
+
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, alice_tx.pub_key());
+    add_vote(
+        &mut testkit,
+        other_election,
+        other_voter_tx.pub_key(),
+        &other_voter_key,
+        carol_tx.pub_key(),
+    );
+
+    let elections = get_elections(&testkit);
+    assert_eq!(elections.len(), 2);
+
+    let vote = decrypt_vote(&keys, &get_vote(&testkit, ELECTION_ID, voter_tx.pub_key())).unwrap();
+    assert_eq!(vote.to(), alice_tx.pub_key());
+    assert!(try_get_vote(&testkit, other_election, voter_tx.pub_key()).is_none());
+
+    let other_vote = get_vote(&testkit, other_election, other_voter_tx.pub_key());
+    let vote = decrypt_vote(&keys, &other_vote).unwrap();
+    assert_eq!(vote.from(), other_voter_tx.pub_key());
+    assert_eq!(vote.to(), carol_tx.pub_key());
+}
+
+#[test]
+#[should_panic(expected = "At least one administrator is required")]
+fn test_service_without_administrators() {
+    VoteService::new(VoteServiceConfig::default());
+}
+
+#[test]
+fn test_election_key_stored() {
+    let (mut testkit, keys) = init_testkit();
+    create_election_with_mode(&mut testkit, ELECTION_ID + 1, "Commit", ElectionMode::CommitReveal);
+
+    let election = get_election(&testkit, ELECTION_ID);
+    assert_eq!(election.election_key(), &group::point_to_key(&keys.public_key));
+    let verification_keys: Vec<PublicKey> =
+        keys.verification_keys.iter().map(group::point_to_key).collect();
+    assert_eq!(election.verification_keys(), verification_keys);
+
+    // Votes of a commit-reveal election are not encrypted.
+    let election = get_election(&testkit, ELECTION_ID + 1);
+    assert_eq!(election.election_key(), &PublicKey::zero());
+    assert!(election.verification_keys().is_empty());
+}
+
+#[test]
+fn test_vote_proof() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (other_tx, _) = create_voter(&mut testkit, ELECTION_ID, "Carol");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+
+    let validators = validator_keys(&testkit);
+    let expected = get_vote(&testkit, ELECTION_ID, voter_tx.pub_key());
+
+    let proof = get_vote_proof(&testkit, ELECTION_ID, voter_tx.pub_key());
+    let vote =
+        proofs::verify_vote_proof(proof, &validators, ELECTION_ID, voter_tx.pub_key()).unwrap();
+    assert_eq!(vote, Some(expected));
+
+    let proof = get_vote_proof(&testkit, ELECTION_ID, other_tx.pub_key());
+    let vote =
+        proofs::verify_vote_proof(proof, &validators, ELECTION_ID, other_tx.pub_key()).unwrap();
+    assert_eq!(vote, None);
 }
 
-fn get_candidate(testkit: &TestKit, pub_key: &PublicKey) -> Candidate {
-    try_get_candidate(testkit, pub_key).expect("Candidate not found")
+#[test]
+fn test_vote_proof_wrong_validators() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+
+    let (fake_validator, _) = crypto::gen_keypair();
+    let proof = get_vote_proof(&testkit, ELECTION_ID, voter_tx.pub_key());
+    let res = proofs::verify_vote_proof(proof, &[fake_validator], ELECTION_ID, voter_tx.pub_key());
+    assert!(res.is_err());
 }
 
-fn create_voter(testkit: &mut TestKit, name: &str) -> (TxCreateVoter, SecretKey) {
+#[test]
+fn test_election_phases() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    let (election_tx, author_key) = create_election(&mut testkit, election_id, "Election");
+    let author = election_tx.author();
+
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Registration);
+
+    start_voting(&mut testkit);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Voting);
+
+    // Results can not be tallied while voting is open.
+    let tx = TxTallyElection::new(author, election_id, &author_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::ElectionNotClosed);
+
+    close_voting(&mut testkit);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Closed);
+
+    // Only the author can tally results.
     let (public, secret) = crypto::gen_keypair();
-    let tx = TxCreateVoter::new(&public, name, &secret);
+    let tx = TxTallyElection::new(&public, election_id, &secret);
     testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::NotElectionAuthor);
 
-    (tx, secret)
+    // Results can not be tallied until threshold of validators published decryption shares.
+    let tx = TxTallyElection::new(author, election_id, &author_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::NotEnoughDecryptionShares);
+
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+    assert!(get_election(&testkit, election_id).tallied());
 }
 
-fn try_get_voter(testkit: &TestKit, pub_key: &PublicKey) -> Option<Voter> {
-    let snapshot = testkit.snapshot();
-    VoteServiceSchema::new(&snapshot).voter(pub_key)
+#[test]
+fn test_registration_after_voting_started() {
+    let (mut testkit, _) = init_testkit();
+    start_voting(&mut testkit);
+
+    let (tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    assert_tx_error(&testkit, &tx, Error::RegistrationClosed);
+    assert!(try_get_candidate(&testkit, ELECTION_ID, tx.pub_key()).is_none());
+
+    let (tx, _) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    assert_tx_error(&testkit, &tx, Error::RegistrationClosed);
 }
 
-fn get_voter(testkit: &TestKit, pub_key: &PublicKey) -> Voter {
-    try_get_voter(testkit, pub_key).expect("Voter not found")
+#[test]
+fn test_vote_outside_voting_period() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+
+    let (tx, _) = add_vote(
+        &mut testkit,
+        ELECTION_ID,
+        voter_tx.pub_key(),
+        &voter_key,
+        cand_tx.pub_key(),
+    );
+    assert_tx_error(&testkit, &tx, Error::VotingNotOpen);
+
+    close_voting(&mut testkit);
+    let (tx, _) = add_vote(
+        &mut testkit,
+        ELECTION_ID,
+        voter_tx.pub_key(),
+        &voter_key,
+        cand_tx.pub_key(),
+    );
+    assert_tx_error(&testkit, &tx, Error::VotingClosed);
+    assert!(try_get_vote(&testkit, ELECTION_ID, voter_tx.pub_key()).is_none());
 }
 
-fn add_vote(
-    testkit: &mut TestKit,
-    from: &PublicKey,
-    to: &PublicKey,
-) -> (TxAddVote, u64, SecretKey) {
+#[test]
+fn test_forged_vote_rejected() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, _) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+
+    // Valid signature, but of someone else than the voter.
     let (public, secret) = crypto::gen_keypair();
-    let tx = TxAddVote::new(&public, from, to, &secret);
-    let block = testkit.create_block_with_transaction(tx.clone());
+    let (tx, _) = add_vote_signed_by(
+        &mut testkit,
+        ELECTION_ID,
+        (&public, &secret),
+        voter_tx.pub_key(),
+        cand_tx.pub_key(),
+    );
+    assert_tx_error(&testkit, &tx, Error::SignerNotVoter);
 
-    (tx, block.height().0, secret)
+    let snapshot = testkit.snapshot();
+    let schema = VoteServiceSchema::new(&snapshot);
+    assert!(schema.vote(ELECTION_ID, &voter_tx.pub_key().hash()).is_none());
 }
 
-fn get_vote_result(testkit: &TestKit) -> VoteResult {
-    let api = testkit.api();
+#[test]
+fn test_forged_vote_of_other_voter_rejected() {
+    let (mut testkit, _) = init_testkit();
 
-    let entry: Option<VoteResult> = api
-        .public(ApiKind::Service("voteservice"))
-        .get("v1/results")
-        .unwrap();
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, _) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (other_tx, other_key) = create_voter(&mut testkit, ELECTION_ID, "Carol");
+    start_voting(&mut testkit);
 
-    entry.unwrap()
+    // Registered voter can not vote on behalf of another voter.
+    let (tx, _) = add_vote_signed_by(
+        &mut testkit,
+        ELECTION_ID,
+        (other_tx.pub_key(), &other_key),
+        voter_tx.pub_key(),
+        cand_tx.pub_key(),
+    );
+    assert_tx_error(&testkit, &tx, Error::SignerNotVoter);
 }
 
-fn get_vote_result_decrypted(testkit: &TestKit) -> Vec<DecryptedCandidateResult> {
-    let api = testkit.api();
+#[test]
+fn test_vote_by_delegate() {
+    let (mut testkit, _) = init_testkit();
 
-    let entry: Option<Vec<DecryptedCandidateResult>> = api
-        .public(ApiKind::Service("voteservice"))
-        .get("v1/results_dec")
-        .unwrap();
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
 
-    entry.unwrap()
+    let (delegate, delegate_key) = crypto::gen_keypair();
+    let tx = TxAuthorizeDelegate::new(voter_tx.pub_key(), ELECTION_ID, &delegate, &voter_key);
+    testkit.create_block_with_transaction(tx);
+
+    start_voting(&mut testkit);
+    add_vote_signed_by(
+        &mut testkit,
+        ELECTION_ID,
+        (&delegate, &delegate_key),
+        voter_tx.pub_key(),
+        cand_tx.pub_key(),
+    );
+
+    get_vote(&testkit, ELECTION_ID, voter_tx.pub_key());
 }
 
-fn get_block(testkit: &TestKit, pub_key: &PublicKey) -> u64 {
-    let api = testkit.api();
+#[test]
+fn test_threshold_decryption() {
+    let keys = threshold::deal(THRESHOLD, VALIDATORS);
+    let (from, _) = crypto::gen_keypair();
+    let (to, _) = crypto::gen_keypair();
+    let election_key = group::point_to_key(&keys.public_key);
+    let enc_vote = cipher::encrypt_vote(&Vote::new(&from, &to), &election_key);
+    let ephemeral = group::key_to_point(enc_vote.pub_key()).unwrap();
 
-    let entry: Option<u64> = api
+    // Any threshold of shares decrypts the vote.
+    for pair in &[[0, 1], [0, 2], [1, 2]] {
+        let shares: Vec<_> = pair
+            .iter()
+            .map(|&i| (keys.shares[i].index, keys.shares[i].secret * ephemeral))
+            .collect();
+        let vote = cipher::decrypt_vote(&enc_vote, &threshold::combine(&shares)).unwrap();
+        assert_eq!(vote.from(), &from);
+        assert_eq!(vote.to(), &to);
+    }
+
+    // Single share is not enough.
+    let share = (keys.shares[0].index, keys.shares[0].secret * ephemeral);
+    assert!(cipher::decrypt_vote(&enc_vote, &threshold::combine(&[share])).is_none());
+
+    // Shares are not computed at all if a ciphertext has an invalid one-time key.
+    let targets = vec![
+        (Hash::zero(), *enc_vote.pub_key()),
+        (Hash::zero(), PublicKey::new([0xff; 32])),
+    ];
+    assert!(threshold::partial_decryptions(&keys.shares[0], &targets).is_none());
+}
+
+#[test]
+fn test_results_not_decrypted_below_threshold() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+    close_voting(&mut testkit);
+
+    // The node publishes its own share only.
+    testkit.create_block();
+    assert_eq!(count_decryption_shares(&testkit, ELECTION_ID), 1);
+
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxTallyElection::new(&admin, ELECTION_ID, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::NotEnoughDecryptionShares);
+    assert_eq!(get_phase(&testkit, ELECTION_ID), ElectionPhase::Closed);
+
+    let api = testkit.api();
+    let res: Result<Vec<DecryptedCandidateResult>, _> = api
         .public(ApiKind::Service("voteservice"))
-        .query(&BlockQuery { pub_key: *pub_key })
-        .get("v1/block")
-        .unwrap();
+        .query(&ElectionQuery {
+            election_id: ELECTION_ID,
+        })
+        .get("v1/results_dec");
+    assert!(res.is_err());
+}
 
-    entry.unwrap()
+#[test]
+fn test_invalid_decryption_share_rejected() {
+    let (mut testkit, keys) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, voter_tx.pub_key(), &voter_key, cand_tx.pub_key());
+    close_voting(&mut testkit);
+
+    // Share of another validator does not match the verification key.
+    let tx = decryption_share_tx(&testkit, &keys, ELECTION_ID, 1, 2);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidDecryptionShare);
+
+    // Only validators can publish shares.
+    let targets = get_decryption_targets(&testkit, ELECTION_ID);
+    let (public, secret) = crypto::gen_keypair();
+    let partials = threshold::partial_decryptions(&keys.shares[1], &targets).unwrap();
+    let tx = TxDecryptionShare::new(&public, ELECTION_ID, partials, &secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::Unauthorized);
+
+    // Shares can be published only once.
+    let tx = decryption_share_tx(&testkit, &keys, ELECTION_ID, 1, 1);
+    testkit.create_block_with_transaction(tx);
+    let (public, secret) = testkit.network().validators()[1].service_keypair();
+    let tx = TxDecryptionShare::new(public, ELECTION_ID, vec![], secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::DecryptionShareAlreadyExists);
+}
+
+#[test]
+fn test_homomorphic_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Homomorphic);
+
+    create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    for (&(ref voter, ref key), &choice) in voters.iter().zip(&[0, 1, 1]) {
+        let tx = add_ballot(&mut testkit, election_id, (voter, key), voter, choice);
+        assert!(get_ballot(&testkit, election_id, tx.voter_id()).is_some());
+    }
+
+    // Running totals are stored instead of per-candidate lists of votes.
+    assert_eq!(get_decryption_targets(&testkit, election_id).len(), 2);
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    let candidates = get_candidates(&testkit, election_id);
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    assert_eq!(results.len(), 2);
+    for (result, expected) in results.iter().zip(&[1, 2]) {
+        assert_eq!(result.vote_num(), *expected);
+        // Individual ballots are never decrypted.
+        assert!(result.votes().is_empty());
+    }
+    assert_eq!(results[0].candidate(), candidates[0].pub_key());
 }
 
-fn decrypt_vote(vote: &EncryptedVote, key: &Vec<u8>) -> Vote {
-    let mut dec = cipher::CipherChaChaPoly::default();
-    dec.set(&key);
+#[test]
+fn test_invalid_ballot_rejected() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Homomorphic);
 
-    let mut dec_output = [0u8; 128];
-    let dec_size = dec.decrypt(0, &[], &vote.data(), &mut dec_output).unwrap();
-    let dec_output = &dec_output[..dec_size];
+    let (cand_tx, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, election_id, "Bob");
+    let (other_tx, other_key) = create_voter(&mut testkit, ELECTION_ID, "Dave");
+    start_voting(&mut testkit);
 
-    let vote = Vote::from_bytes(Cow::Borrowed(dec_output));
+    // Ballot must have a ciphertext for every candidate.
+    let election_key = get_election(&testkit, election_id).election_key().clone();
+    let ballot = zkp::encrypt_ballot(0, 1, &election_key, election_id, voter_tx.pub_key());
+    let tx = ballot_tx(election_id, (voter_tx.pub_key(), &voter_key), voter_tx.pub_key(), ballot);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallot);
+    assert!(get_ballot(&testkit, election_id, voter_tx.pub_key()).is_none());
+
+    // Plain encrypted votes are not accepted by homomorphic elections.
+    let (tx, _) = add_vote(
+        &mut testkit,
+        election_id,
+        voter_tx.pub_key(),
+        &voter_key,
+        cand_tx.pub_key(),
+    );
+    assert_tx_error(&testkit, &tx, Error::WrongElectionMode);
+
+    // And vice versa.
+    let tx = add_ballot(
+        &mut testkit,
+        ELECTION_ID,
+        (other_tx.pub_key(), &other_key),
+        other_tx.pub_key(),
+        0,
+    );
+    assert_tx_error(&testkit, &tx, Error::WrongElectionMode);
+
+    // Voter can cast only one ballot.
+    let signer = (voter_tx.pub_key(), &voter_key);
+    add_ballot(&mut testkit, election_id, signer, voter_tx.pub_key(), 0);
+    assert!(get_ballot(&testkit, election_id, voter_tx.pub_key()).is_some());
+    let tx = add_ballot(&mut testkit, election_id, signer, voter_tx.pub_key(), 1);
+    assert_tx_error(&testkit, &tx, Error::VoteAlreadyExists);
+}
+
+#[test]
+fn test_ballot_proofs() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Homomorphic);
+
+    create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, election_id, "Bob");
+    let (other_tx, other_key) = create_voter(&mut testkit, election_id, "Dave");
+    start_voting(&mut testkit);
+    let voter = (voter_tx.pub_key(), &voter_key);
+    let election_key = get_election(&testkit, election_id).election_key().clone();
+
+    // Several votes for a single candidate.
+    let mut ballot = zkp::encrypt_ballot(0, 2, &election_key, election_id, voter_tx.pub_key());
+    let key_point = group::key_to_point(&election_key).unwrap();
+    ballot.ciphertexts[0] = elgamal::encrypt(5, &key_point, &group::random_scalar());
+    let tx = ballot_tx(election_id, voter, voter_tx.pub_key(), ballot);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+
+    // Vote for nobody.
+    let ballot = zkp::encrypt_ballot(2, 2, &election_key, election_id, voter_tx.pub_key());
+    let tx = ballot_tx(election_id, voter, voter_tx.pub_key(), ballot);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+
+    // Ballot proven for another voter.
+    let ballot = zkp::encrypt_ballot(0, 2, &election_key, election_id, other_tx.pub_key());
+    let tx = ballot_tx(election_id, voter, voter_tx.pub_key(), ballot);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+
+    // Ballot proven for another election with the same key.
+    let ballot = zkp::encrypt_ballot(0, 2, &election_key, ELECTION_ID, voter_tx.pub_key());
+    let tx = ballot_tx(election_id, voter, voter_tx.pub_key(), ballot);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+    assert!(get_ballot(&testkit, election_id, voter_tx.pub_key()).is_none());
+
+    add_ballot(&mut testkit, election_id, (other_tx.pub_key(), &other_key), other_tx.pub_key(), 1);
+    assert!(get_ballot(&testkit, election_id, other_tx.pub_key()).is_some());
+}
+
+#[test]
+fn test_vote_choice_proofs() {
+    let (mut testkit, _) = init_testkit();
+
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    create_candidate(&mut testkit, ELECTION_ID, "Carol", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (other_tx, _) = create_voter(&mut testkit, ELECTION_ID, "Dave");
+    start_voting(&mut testkit);
+    let voter = (voter_tx.pub_key(), &voter_key);
+    let election_key = *get_election(&testkit, ELECTION_ID).election_key();
+    let candidates = get_candidate_keys(&testkit, ELECTION_ID);
+    let choice = candidates.iter().position(|key| key == alice.pub_key()).unwrap();
+    let vote = Vote::new(voter_tx.pub_key(), alice.pub_key());
+
+    // Vote for an unknown candidate.
+    let (unknown, _) = crypto::gen_keypair();
+    let (tx, _) = add_vote(&mut testkit, ELECTION_ID, voter.0, voter.1, &unknown);
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+
+    // Vote proven for another voter.
+    let proven = zkp::encrypt_vote(
+        &vote,
+        choice,
+        &candidates,
+        &election_key,
+        ELECTION_ID,
+        other_tx.pub_key(),
+    );
+    let tx = vote_tx(ELECTION_ID, voter, voter.0, proven, (0, vec![]));
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+
+    // Vote proven for a part of the candidates.
+    let proven =
+        zkp::encrypt_vote(&vote, 0, &[*alice.pub_key()], &election_key, ELECTION_ID, voter.0);
+    let tx = vote_tx(ELECTION_ID, voter, voter.0, proven, (0, vec![]));
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+
+    // Choice is bound to the one-time key of the vote.
+    let mut proven =
+        zkp::encrypt_vote(&vote, choice, &candidates, &election_key, ELECTION_ID, voter.0);
+    proven.vote = cipher::encrypt_vote(&vote, &election_key);
+    let tx = vote_tx(ELECTION_ID, voter, voter.0, proven, (0, vec![]));
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidBallotProof);
+    assert!(try_get_vote(&testkit, ELECTION_ID, voter.0).is_none());
+
+    let (tx, _) = add_vote(&mut testkit, ELECTION_ID, voter.0, voter.1, alice.pub_key());
+    let snapshot = testkit.snapshot();
+    let choice = VoteServiceSchema::new(&snapshot)
+        .vote_choice(ELECTION_ID, &voter.0.hash())
+        .unwrap();
+    assert_eq!(choice.a(), tx.vote_key());
+    assert_eq!(choice.b(), tx.choice());
+}
+
+#[test]
+fn test_anonymous_vote() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Anonymous);
+
+    let (cand_tx, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let mut voters = vec![];
+    for name in &["Bob", "Dave"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    // Tokens are issued during registration, votes are cast from fresh keys.
+    let mut ballot_keys = vec![];
+    for &(ref voter, ref key) in &voters {
+        let ballot_key = crypto::gen_keypair();
+        let token = issue_token(&mut testkit, election_id, (voter, key), &ballot_key.0);
+        ballot_keys.push((ballot_key, token));
+    }
+
+    start_voting(&mut testkit);
+    for &((ref public, ref secret), ref token) in &ballot_keys {
+        add_anonymous_vote(&mut testkit, election_id, (public, secret), cand_tx.pub_key(), token);
+        get_vote(&testkit, election_id, public);
+    }
+
+    // Votes are not linked to voters.
+    for &(ref voter, _) in &voters {
+        assert!(try_get_vote(&testkit, election_id, voter).is_none());
+    }
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    assert_eq!(results[0].vote_num(), 2);
+    for vote in results[0].votes() {
+        assert!(ballot_keys.iter().any(|key| (key.0).0 == *vote.from()));
+    }
+}
+
+#[test]
+fn test_anonymous_vote_tokens() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Anonymous);
+
+    let (cand_tx, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (voter_tx, voter_key) = create_voter(&mut testkit, election_id, "Bob");
+    let voter = (voter_tx.pub_key(), &voter_key);
+    let (ballot_key, ballot_secret) = crypto::gen_keypair();
+    let token = issue_token(&mut testkit, election_id, voter, &ballot_key);
+
+    // A voter gets a single token.
+    let tx = TxRequestToken::new(voter.0, election_id, voter.0, &Hash::zero(), voter.1);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::TokenAlreadyRequested);
+
+    start_voting(&mut testkit);
+
+    // Votes without a token of the issuer are rejected.
+    let (other_key, other_secret) = crypto::gen_keypair();
+    let tx = add_anonymous_vote(
+        &mut testkit,
+        election_id,
+        (&other_key, &other_secret),
+        cand_tx.pub_key(),
+        &token,
+    );
+    assert_tx_error(&testkit, &tx, Error::InvalidToken);
+
+    let forged = Token {
+        commitment: blind::issuer_key(&group::random_scalar()),
+        response: group::scalar_to_hash(&group::random_scalar()),
+    };
+    let tx = add_anonymous_vote(
+        &mut testkit,
+        election_id,
+        (&ballot_key, &ballot_secret),
+        cand_tx.pub_key(),
+        &forged,
+    );
+    assert_tx_error(&testkit, &tx, Error::InvalidToken);
+
+    // Tokens can be spent only once.
+    add_anonymous_vote(
+        &mut testkit,
+        election_id,
+        (&ballot_key, &ballot_secret),
+        cand_tx.pub_key(),
+        &token,
+    );
+    let tx = add_anonymous_vote(
+        &mut testkit,
+        election_id,
+        (&ballot_key, &ballot_secret),
+        cand_tx.pub_key(),
+        &token,
+    );
+    assert_tx_error(&testkit, &tx, Error::TokenAlreadySpent);
+    assert!(try_get_vote(&testkit, election_id, &ballot_key).is_some());
+
+    // Non-anonymous votes are not accepted.
+    let (tx, _) = add_vote(&mut testkit, election_id, voter.0, voter.1, cand_tx.pub_key());
+    assert_tx_error(&testkit, &tx, Error::WrongElectionMode);
+}
+
+#[test]
+fn test_invalid_token_response_rejected() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Anonymous);
+
+    let (voter_tx, voter_key) = create_voter(&mut testkit, election_id, "Bob");
+    let (admin, admin_key) = admin_keypair();
+    let commitment = blind::commit(&token_issuer_secret(), election_id, voter_tx.pub_key());
+
+    // Response is requested by the voter first.
+    let tx = TxIssueToken::new(&admin, election_id, voter_tx.pub_key(), &Hash::zero(), &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::TokenCommitmentNotFound);
+
+    let tx =
+        TxTokenCommitment::new(&admin, election_id, voter_tx.pub_key(), &commitment, &admin_key);
+    testkit.create_block_with_transaction(tx);
+
+    let response = group::scalar_to_hash(&Scalar::one());
+    let tx = TxIssueToken::new(&admin, election_id, voter_tx.pub_key(), &response, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::TokenNotRequested);
+
+    let (ballot_key, _) = crypto::gen_keypair();
+    let issuer_key = blind::issuer_key(&token_issuer_secret());
+    let (_, challenge) = blind::blind(&issuer_key, &commitment, election_id, &ballot_key).unwrap();
+    let tx = TxRequestToken::new(
+        voter_tx.pub_key(),
+        election_id,
+        voter_tx.pub_key(),
+        &challenge,
+        &voter_key,
+    );
+    testkit.create_block_with_transaction(tx);
+
+    // Response made with a different key does not match the issuer key.
+    let other_secret = group::random_scalar();
+    let response = blind::respond(&other_secret, election_id, voter_tx.pub_key(), &challenge);
+    let tx = TxIssueToken::new(
+        &admin,
+        election_id,
+        voter_tx.pub_key(),
+        &response.unwrap(),
+        &admin_key,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidTokenResponse);
+}
+
+#[test]
+fn test_token_sessions() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Anonymous);
+
+    let (alice_tx, alice_key) = create_voter(&mut testkit, election_id, "Alice");
+    let (bob_tx, bob_key) = create_voter(&mut testkit, election_id, "Bob");
+    let (carol_tx, carol_key) = create_voter(&mut testkit, election_id, "Carol");
+    let (admin, admin_key) = admin_keypair();
+    let issuer_secret = token_issuer_secret();
+    let issuer_key = blind::issuer_key(&issuer_secret);
+    let commitment_tx = |voter: &PublicKey| {
+        let commitment = blind::commit(&issuer_secret, election_id, voter);
+        TxTokenCommitment::new(&admin, election_id, voter, &commitment, &admin_key)
+    };
+    let request_tx = |testkit: &TestKit, voter: &PublicKey, secret: &SecretKey| {
+        let commitment = *get_token_issuance(testkit, election_id, voter).commitment();
+        let (ballot_key, _) = crypto::gen_keypair();
+        let (_, challenge) =
+            blind::blind(&issuer_key, &commitment, election_id, &ballot_key).unwrap();
+        TxRequestToken::new(voter, election_id, voter, &challenge, secret)
+    };
+
+    testkit.create_block_with_transaction(commitment_tx(alice_tx.pub_key()));
+
+    // Bob's session is not opened until Alice's token is issued.
+    let bob_commitment = commitment_tx(bob_tx.pub_key());
+    let alice_request = request_tx(&testkit, alice_tx.pub_key(), &alice_key);
+    testkit.create_block_with_transactions(vec![
+        Box::new(bob_commitment.clone()) as Box<Transaction>,
+        Box::new(alice_request),
+    ]);
+    assert_tx_error(&testkit, &bob_commitment, Error::TokenSessionOpen);
+
+    let challenge = *get_token_issuance(&testkit, election_id, alice_tx.pub_key()).challenge();
+    let response =
+        blind::respond(&issuer_secret, election_id, alice_tx.pub_key(), &challenge).unwrap();
+    let tx = TxIssueToken::new(&admin, election_id, alice_tx.pub_key(), &response, &admin_key);
+    testkit.create_block_with_transaction(tx);
+    assert!(get_token_issuance(&testkit, election_id, alice_tx.pub_key()).is_issued());
+
+    testkit.create_block_with_transaction(commitment_tx(bob_tx.pub_key()));
+    let issuance = get_token_issuance(&testkit, election_id, bob_tx.pub_key());
+
+    // Bob does not request the token in time, so the session is closed for him
+    // and Carol's session is opened.
+    let height = issuance.height() + schema::TOKEN_SESSION_BLOCKS;
+    testkit.create_blocks_until(Height(height - 1));
+    let bob_request = request_tx(&testkit, bob_tx.pub_key(), &bob_key);
+    let carol_commitment = commitment_tx(carol_tx.pub_key());
+    testkit.create_block_with_transactions(vec![
+        Box::new(bob_request.clone()) as Box<Transaction>,
+        Box::new(carol_commitment),
+    ]);
+    assert_tx_error(&testkit, &bob_request, Error::TokenSessionExpired);
+    assert!(!get_token_issuance(&testkit, election_id, bob_tx.pub_key()).is_requested());
+    assert!(!get_token_issuance(&testkit, election_id, carol_tx.pub_key()).is_requested());
+
+    // Bob gets a new commitment once Carol's session is over.
+    let bob_commitment = commitment_tx(bob_tx.pub_key());
+    testkit.create_block_with_transaction(bob_commitment.clone());
+    assert_tx_error(&testkit, &bob_commitment, Error::TokenSessionOpen);
+
+    let carol_request = request_tx(&testkit, carol_tx.pub_key(), &carol_key);
+    testkit.create_block_with_transaction(carol_request);
+    let challenge = *get_token_issuance(&testkit, election_id, carol_tx.pub_key()).challenge();
+    let response =
+        blind::respond(&issuer_secret, election_id, carol_tx.pub_key(), &challenge).unwrap();
+    let tx = TxIssueToken::new(&admin, election_id, carol_tx.pub_key(), &response, &admin_key);
+    testkit.create_block_with_transaction(tx);
+    assert!(get_token_issuance(&testkit, election_id, carol_tx.pub_key()).is_issued());
+
+    testkit.create_block_with_transaction(commitment_tx(bob_tx.pub_key()));
+    let recommitted = get_token_issuance(&testkit, election_id, bob_tx.pub_key());
+    assert!(recommitted.height() > issuance.height());
+    testkit.create_block_with_transaction(request_tx(&testkit, bob_tx.pub_key(), &bob_key));
+    assert!(get_token_issuance(&testkit, election_id, bob_tx.pub_key()).is_requested());
+
+    // Issued sessions are never reopened.
+    let tx = commitment_tx(alice_tx.pub_key());
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::TokenCommitmentExists);
+}
+
+#[test]
+fn test_ring_signed_vote() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::RingSigned);
+
+    let (cand_tx, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    for &(ref voter, ref key) in &voters[..2] {
+        let tx = add_ring_vote(&mut testkit, election_id, key, cand_tx.pub_key());
+        get_vote(&testkit, election_id, tx.pub_key());
+        // Votes are not linked to voters.
+        assert!(try_get_vote(&testkit, election_id, voter).is_none());
+    }
+
+    // The second vote of the voter has the same key image.
+    let tx = add_ring_vote(&mut testkit, election_id, &voters[0].1, cand_tx.pub_key());
+    assert_tx_error(&testkit, &tx, Error::KeyImageAlreadyUsed);
+
+    // Only registered voters can sign.
+    let ring = get_ring(&testkit, election_id);
+    let (public, secret) = crypto::gen_keypair();
+    let (outsider, outsider_key) = crypto::gen_keypair();
+    assert!(lsag::sign(&ring, &outsider_key, election_id, &public).is_none());
+    let mut outsider_ring = ring.clone();
+    outsider_ring.push(outsider);
+    let signature = lsag::sign(&outsider_ring, &outsider_key, election_id, &public).unwrap();
+    let signer = (&public, &secret);
+    let tx = ring_vote_tx(&testkit, election_id, signer, cand_tx.pub_key(), signature);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidRingSignature);
+
+    // Signature is bound to the one-time key.
+    let signature = lsag::sign(&ring, &voters[2].1, election_id, &public).unwrap();
+    let (other, other_secret) = crypto::gen_keypair();
+    let signer = (&other, &other_secret);
+    let tx = ring_vote_tx(&testkit, election_id, signer, cand_tx.pub_key(), signature);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidRingSignature);
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    assert_eq!(results[0].vote_num(), 2);
+}
+
+#[test]
+fn test_mixnet_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Mixnet);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    let choices = [alice.pub_key(), carol.pub_key(), carol.pub_key()];
+    for (&(ref voter, ref key), to) in voters.iter().zip(&choices) {
+        add_mix_vote(&mut testkit, election_id, (voter, key), to);
+    }
+    let cast = get_mixed_votes(&testkit, election_id);
+    assert_eq!(cast.len(), 3);
+
+    // The node shuffles the votes by itself, the second validator shuffle is added manually.
+    close_voting(&mut testkit);
+    testkit.create_block();
+    assert_eq!(count_mixes(&testkit, election_id), 1);
+    let tx = shuffle_tx(&testkit, election_id, 1);
+    testkit.create_block_with_transaction(tx);
+    assert_eq!(count_mixes(&testkit, election_id), THRESHOLD as usize);
+
+    // Mixed votes can not be matched with the cast ones.
+    let mixed = get_mixed_votes(&testkit, election_id);
+    assert_eq!(mixed.len(), 3);
+    assert!(mixed.iter().all(|vote| !cast.contains(vote)));
+
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    let alice_result = results.iter().find(|res| res.candidate() == alice.pub_key());
+    let carol_result = results.iter().find(|res| res.candidate() == carol.pub_key());
+    assert_eq!(alice_result.unwrap().vote_num(), 1);
+    assert_eq!(carol_result.unwrap().vote_num(), 2);
+    assert!(results.iter().all(|res| res.votes().is_empty()));
+}
+
+#[test]
+fn test_invalid_shuffle_rejected() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Mixnet);
+
+    let (cand_tx, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (bob, bob_key) = create_voter(&mut testkit, election_id, "Bob");
+    let (dave, dave_key) = create_voter(&mut testkit, election_id, "Dave");
+
+    start_voting(&mut testkit);
+    let tx = add_mix_vote(&mut testkit, election_id, (bob.pub_key(), &bob_key), cand_tx.pub_key());
+
+    // Vote copied from another voter has no valid proof.
+    let copied = TxAddMixVote::new(
+        dave.pub_key(),
+        election_id,
+        dave.pub_key(),
+        tx.ciphertext(),
+        tx.challenge(),
+        tx.response(),
+        &dave_key,
+    );
+    testkit.create_block_with_transaction(copied.clone());
+    assert_tx_error(&testkit, &copied, Error::InvalidBallotProof);
+
+    // Votes are shuffled only after voting is over.
+    let tx = shuffle_tx(&testkit, election_id, 1);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::ElectionNotClosed);
+
+    add_mix_vote(&mut testkit, election_id, (dave.pub_key(), &dave_key), cand_tx.pub_key());
+    close_voting(&mut testkit);
+
+    // Votes are not decrypted until they are mixed.
+    let tx = decryption_share_tx(&testkit, &keys, election_id, 1, 1);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::MixingNotComplete);
+
+    // Shuffle must follow the previous stage.
+    let input = get_mixed_votes(&testkit, election_id);
+    let election_key = keys.public_key;
+    let (public, secret) = {
+        let (public, secret) = testkit.network().validators()[1].service_keypair();
+        (*public, secret.clone())
+    };
+    let (ciphertexts, proof) = mixnet::shuffle(&election_key, election_id, 1, &input).unwrap();
+    let tx = TxShuffle::new(&public, election_id, 1, ciphertexts, proof, &secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::WrongMixStage);
+
+    // Proof of another shuffle does not fit.
+    let (ciphertexts, _) = mixnet::shuffle(&election_key, election_id, 0, &input).unwrap();
+    let (_, proof) = mixnet::shuffle(&election_key, election_id, 0, &input).unwrap();
+    let tx = TxShuffle::new(&public, election_id, 0, ciphertexts, proof, &secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::InvalidShuffle);
+
+    // Only validators can shuffle.
+    let (ciphertexts, proof) = mixnet::shuffle(&election_key, election_id, 0, &input).unwrap();
+    let (public, secret) = crypto::gen_keypair();
+    let tx = TxShuffle::new(&public, election_id, 0, ciphertexts, proof, &secret);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::Unauthorized);
+
+    // Every validator shuffles only once.
+    testkit.create_block();
+    assert_eq!(count_mixes(&testkit, election_id), 1);
+    let tx = shuffle_tx(&testkit, election_id, 0);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::MixAlreadyExists);
+}
+
+#[test]
+fn test_mixnet_turns() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Mixnet);
+    let validators: Vec<PublicKey> = testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|validator| *validator.service_keypair().0)
+        .collect();
+    let mixer = |testkit: &TestKit, height: u64| {
+        let snapshot = testkit.snapshot();
+        let schema = VoteServiceSchema::new(&snapshot);
+        let election = schema.election(election_id).unwrap();
+        schema.mixer(&election, height)
+    };
+
+    // Turns of validators start every `MIX_TURN_BLOCKS` after voting is over.
+    assert_eq!(mixer(&testkit, END_HEIGHT - 1), None);
+    assert_eq!(mixer(&testkit, END_HEIGHT), Some(validators[0]));
+    assert_eq!(mixer(&testkit, END_HEIGHT + 1), None);
+    assert_eq!(mixer(&testkit, END_HEIGHT + schema::MIX_TURN_BLOCKS), Some(validators[1]));
+
+    // The node shuffles the votes in its turn, then validators which shuffled are skipped.
+    close_voting(&mut testkit);
+    testkit.create_block();
+    assert_eq!(count_mixes(&testkit, election_id), 1);
+    assert_eq!(mixer(&testkit, END_HEIGHT + schema::MIX_TURN_BLOCKS), Some(validators[2]));
+    testkit.create_blocks_until(Height(END_HEIGHT + 3 * schema::MIX_TURN_BLOCKS));
+    assert_eq!(count_mixes(&testkit, election_id), 1);
+
+    // Mixing is over once threshold of validators shuffled the votes.
+    let tx = shuffle_tx(&testkit, election_id, 1);
+    testkit.create_block_with_transaction(tx);
+    assert_eq!(count_mixes(&testkit, election_id), THRESHOLD as usize);
+    assert_eq!(mixer(&testkit, END_HEIGHT + 4 * schema::MIX_TURN_BLOCKS), None);
+}
+
+#[test]
+fn test_commit_reveal_tally() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::CommitReveal);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve", "Frank", "Grace"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    let choices = [alice.pub_key(), carol.pub_key(), carol.pub_key()];
+    let mut salts = vec![];
+    for (&(ref voter, ref key), to) in voters.iter().zip(&choices) {
+        salts.push(commit_vote(&mut testkit, election_id, (voter, key), to));
+    }
+    assert_eq!(
+        get_commitment(&testkit, election_id, &voters[0].0),
+        schema::vote_commitment(election_id, &voters[0].0, alice.pub_key(), &salts[0])
+    );
+
+    // Commitment copied from another voter can not be revealed.
+    let (grace, grace_key) = (voters[4].0, &voters[4].1);
+    let commitment = get_commitment(&testkit, election_id, &voters[0].0);
+    let tx = TxCommitVote::new(&grace, election_id, &grace, &commitment, grace_key);
+    testkit.create_block_with_transaction(tx);
+
+    // Votes are revealed only after voting is over.
+    let (eve, eve_key) = (voters[2].0, &voters[2].1);
+    let tx = reveal_vote(&mut testkit, election_id, (&eve, eve_key), carol.pub_key(), &salts[2]);
+    assert_tx_error(&testkit, &tx, Error::ElectionNotClosed);
+
+    close_voting(&mut testkit);
+    let revealed = voters.iter().zip(&choices).zip(&salts).take(2);
+    for ((&(ref voter, ref key), to), salt) in revealed {
+        reveal_vote(&mut testkit, election_id, (voter, key), to, salt);
+    }
+
+    let (bob, bob_key) = (voters[0].0, &voters[0].1);
+    let tx = reveal_vote(&mut testkit, election_id, (&bob, bob_key), carol.pub_key(), &salts[0]);
+    assert_tx_error(&testkit, &tx, Error::VoteAlreadyRevealed);
+
+    // Choice must match the commitment.
+    let tx = reveal_vote(&mut testkit, election_id, (&eve, eve_key), alice.pub_key(), &salts[2]);
+    assert_tx_error(&testkit, &tx, Error::InvalidReveal);
+
+    // Voter who did not commit has nothing to reveal.
+    let (frank, frank_key) = (voters[3].0, &voters[3].1);
+    let tx = reveal_vote(
+        &mut testkit,
+        election_id,
+        (&frank, frank_key),
+        alice.pub_key(),
+        &salts[0],
+    );
+    assert_tx_error(&testkit, &tx, Error::CommitmentNotFound);
+
+    let tx = reveal_vote(
+        &mut testkit,
+        election_id,
+        (&grace, grace_key),
+        alice.pub_key(),
+        &salts[0],
+    );
+    assert_tx_error(&testkit, &tx, Error::InvalidReveal);
+
+    // Votes are not encrypted, so validators do not publish decryption shares.
+    testkit.create_block();
+    assert_eq!(count_decryption_shares(&testkit, election_id), 0);
+
+    // Votes are tallied only after the reveal period is over.
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Revealing);
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxTallyElection::new(&admin, election_id, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::ElectionNotClosed);
+
+    close_revealing(&mut testkit);
+    let tx = reveal_vote(&mut testkit, election_id, (&eve, eve_key), carol.pub_key(), &salts[2]);
+    assert_tx_error(&testkit, &tx, Error::RevealClosed);
+
+    let tx = TxTallyElection::new(&admin, election_id, &admin_key);
+    testkit.create_block_with_transaction(tx);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    let alice_result = results.iter().find(|res| res.candidate() == alice.pub_key());
+    let carol_result = results.iter().find(|res| res.candidate() == carol.pub_key());
+    assert_eq!(alice_result.unwrap().vote_num(), 1);
+    assert_eq!(carol_result.unwrap().vote_num(), 1);
+    assert_eq!(alice_result.unwrap().votes()[0].from(), &bob);
+
+    // Unrevealed commitments are reported separately.
+    let unrevealed = get_unrevealed(&testkit, election_id);
+    assert_eq!(unrevealed.len(), 2);
+    assert!(unrevealed.contains(&eve) && unrevealed.contains(&grace));
+}
+
+#[test]
+fn test_reveal_withdrawn_candidate() {
+    let (mut testkit, _) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::CommitReveal);
+
+    let (mallory, _) = create_candidate(&mut testkit, election_id, "Mallory", "Some info");
+    let (bob, bob_key) = create_voter(&mut testkit, election_id, "Bob");
+
+    start_voting(&mut testkit);
+    let voter = (bob.pub_key(), &bob_key);
+    let salt = commit_vote(&mut testkit, election_id, voter, mallory.pub_key());
+    withdraw_candidate(&mut testkit, election_id, mallory.pub_key());
+
+    // The vote for the withdrawn candidate is void, so it is not revealed.
+    close_voting(&mut testkit);
+    let tx = reveal_vote(&mut testkit, election_id, voter, mallory.pub_key(), &salt);
+    assert_tx_error(&testkit, &tx, Error::CandidateWithdrawn);
+    assert!(get_unrevealed(&testkit, election_id).contains(bob.pub_key()));
+}
+
+#[test]
+fn test_ranked_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Ranked);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (mallory, _) = create_candidate(&mut testkit, election_id, "Mallory", "Some info");
+    let (a, c, m) = (*alice.pub_key(), *carol.pub_key(), *mallory.pub_key());
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve", "Frank", "Grace", "Heidi", "Ivan"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    let rankings = vec![
+        vec![a],
+        vec![a],
+        vec![a],
+        vec![c, m],
+        vec![c, m],
+        vec![m, c],
+        // Ranking the same candidate twice spoils the ballot.
+        vec![m, m],
+    ];
+    for (&(ref voter, ref key), ranking) in voters.iter().zip(rankings) {
+        add_ranked_vote(&mut testkit, election_id, (voter, key), ranking);
+    }
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    // Mallory is eliminated first, then Carol loses the tie with Alice
+    // on the votes of the first round, ballots of their voters are exhausted.
+    let rounds = get_rounds(&testkit, election_id);
+    let tallies: Vec<Vec<(PublicKey, u64)>> = rounds
+        .iter()
+        .map(|round| {
+            let mut tallies: Vec<_> = round
+                .tallies()
+                .iter()
+                .map(|tally| (*tally.candidate(), tally.votes()))
+                .collect();
+            tallies.sort_by_key(|&(candidate, _)| [a, c, m].iter().position(|&x| x == candidate));
+            tallies
+        })
+        .collect();
+    assert_eq!(
+        tallies,
+        vec![
+            vec![(a, 3), (c, 2), (m, 1)],
+            vec![(a, 3), (c, 3)],
+            vec![(a, 3)],
+        ]
+    );
+    let eliminated: Vec<_> = rounds.iter().map(|round| round.eliminated()).collect();
+    assert_eq!(eliminated, vec![vec![m], vec![c], vec![]]);
+    assert_eq!(rounds[2].exhausted(), 3);
+
+    // Decrypted results hold first preferences.
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    let first_preferences: u64 = results.iter().map(|res| res.vote_num()).sum();
+    assert_eq!(first_preferences, 6);
+
+    // Alice and Carol are tied 3 to 3, and Carol beats Mallory 2 to 1.
+    let mut pairwise = get_pairwise_results(&testkit, election_id);
+    let order: Vec<PublicKey> = pairwise.iter().map(|res| *res.candidate()).collect();
+    pairwise.sort_by_key(|res| [a, c, m].iter().position(|x| x == res.candidate()));
+    let preference = |row: usize, other: &PublicKey| {
+        let pos = order.iter().position(|x| x == other).unwrap();
+        pairwise[row].preferences()[pos]
+    };
+    assert_eq!(preference(0, &c), 3);
+    assert_eq!(preference(1, &a), 3);
+    assert_eq!(preference(1, &m), 2);
+    assert_eq!(preference(2, &c), 1);
+    let winners: Vec<_> = pairwise
+        .iter()
+        .map(|res| (res.schulze_winner(), res.ranked_pairs_winner()))
+        .collect();
+    assert_eq!(winners, vec![(true, true), (true, true), (false, false)]);
+}
+
+#[test]
+fn test_corrupted_ballots_skipped() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Ranked);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (bob, bob_key) = create_voter(&mut testkit, election_id, "Bob");
+    let (dave, dave_key) = create_voter(&mut testkit, election_id, "Dave");
+
+    start_voting(&mut testkit);
+    add_ranked_vote(&mut testkit, election_id, (bob.pub_key(), &bob_key), vec![*alice.pub_key()]);
+
+    // Segment of the ranking claims more candidates than the ballot holds.
+    let election_key = *get_election(&testkit, election_id).election_key();
+    let secret = threshold::recover_secret(&keys.shares[..THRESHOLD as usize]);
+    let shared = |vote: &EncryptedVote| secret * group::key_to_point(vote.pub_key()).unwrap();
+    let mut raw = dave.pub_key().as_ref().to_vec();
+    raw.extend_from_slice(&[40, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+    let vote = RankedVote::from_bytes(Cow::Owned(raw.clone()));
+    let enc_vote = cipher::encrypt_ranked_vote(&vote, &election_key);
+    assert!(cipher::decrypt_ranked_vote(&enc_vote, &shared(&enc_vote)).is_none());
+    let tx = TxAddRankedVote::new(
+        dave.pub_key(),
+        election_id,
+        dave.pub_key(),
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        &dave_key,
+    );
+    testkit.create_block_with_transaction(tx);
+
+    // Other kinds of ballots are checked as well.
+    let vote = ApprovalVote::from_bytes(Cow::Owned(raw.clone()));
+    let enc_vote = cipher::encrypt_approval_vote(&vote, &election_key);
+    assert!(cipher::decrypt_approval_vote(&enc_vote, &shared(&enc_vote)).is_none());
+    raw.truncate(32);
+    raw.extend_from_slice(&[40, 0, 0, 0, 1, 0, 0, 0, 48, 0, 0, 0, 33, 0, 0, 0]);
+    let vote = ScoreVote::from_bytes(Cow::Owned(raw.clone()));
+    let enc_vote = cipher::encrypt_score_vote(&vote, &election_key);
+    assert!(cipher::decrypt_score_vote(&enc_vote, &shared(&enc_vote)).is_none());
+    raw.truncate(32);
+    let vote = ReferendumVote::from_bytes(Cow::Owned(raw.clone()));
+    let enc_vote = cipher::encrypt_referendum_vote(&vote, &election_key);
+    assert!(cipher::decrypt_referendum_vote(&enc_vote, &shared(&enc_vote)).is_none());
+    let vote = Vote::from_bytes(Cow::Owned(raw));
+    let enc_vote = cipher::encrypt_vote(&vote, &election_key);
+    assert!(cipher::decrypt_vote(&enc_vote, &shared(&enc_vote)).is_none());
+
+    // The corrupted ballot is skipped on tally.
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+    let rounds = get_rounds(&testkit, election_id);
+    assert_eq!(rounds[0].tallies()[0].votes(), 1);
+    let rejected = get_rejected_votes(&testkit, election_id);
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].vote_id(), &dave.pub_key().hash());
+    assert_eq!(rejected[0].reason(), RejectionReason::Malformed as u8);
+}
+
+#[test]
+fn test_rejected_plurality_votes() {
+    let (mut testkit, keys) = init_testkit();
+
+    let (alice, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, ELECTION_ID, "Carol", "Some info");
+    let (bob, bob_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let (dave, dave_key) = create_voter(&mut testkit, ELECTION_ID, "Dave");
+    let (eve, eve_key) = create_voter(&mut testkit, ELECTION_ID, "Eve");
+
+    start_voting(&mut testkit);
+    add_vote(&mut testkit, ELECTION_ID, bob.pub_key(), &bob_key, alice.pub_key());
+    let election_key = *get_election(&testkit, ELECTION_ID).election_key();
+    let candidates = get_candidate_keys(&testkit, ELECTION_ID);
+
+    // Dave proves a choice of Alice, but the encrypted vote names another candidate.
+    let vote = Vote::new(dave.pub_key(), carol.pub_key());
+    let alice_pos = candidates.iter().position(|key| key == alice.pub_key()).unwrap();
+    let (dave, dave_key) = (dave.pub_key(), &dave_key);
+    let proven = zkp::encrypt_vote(&vote, alice_pos, &candidates, &election_key, ELECTION_ID, dave);
+    let tx = vote_tx(ELECTION_ID, (dave, dave_key), dave, proven, (0, vec![]));
+    testkit.create_block_with_transaction(tx);
+
+    // Eve posts a ballot which names Bob as its voter.
+    let vote = Vote::new(bob.pub_key(), alice.pub_key());
+    let (eve, eve_key) = (eve.pub_key(), &eve_key);
+    let proven = zkp::encrypt_vote(&vote, alice_pos, &candidates, &election_key, ELECTION_ID, eve);
+    let tx = vote_tx(ELECTION_ID, (eve, eve_key), eve, proven, (0, vec![]));
+    testkit.create_block_with_transaction(tx);
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, ELECTION_ID);
+    let results = get_vote_result_decrypted(&testkit, ELECTION_ID);
+    let alice_result = results.iter().find(|res| res.candidate() == alice.pub_key());
+    assert_eq!(alice_result.unwrap().vote_num(), 1);
+    assert_eq!(results.iter().map(|res| res.vote_num()).sum::<u64>(), 1);
+
+    let mut rejected: Vec<(Hash, u8)> = get_rejected_votes(&testkit, ELECTION_ID)
+        .iter()
+        .map(|vote| (*vote.vote_id(), vote.reason()))
+        .collect();
+    rejected.sort();
+    let mut expected = vec![
+        (dave.hash(), RejectionReason::Malformed as u8),
+        (eve.hash(), RejectionReason::WrongVoter as u8),
+    ];
+    expected.sort();
+    assert_eq!(rejected, expected);
+}
+
+#[test]
+fn test_instant_runoff_tie_break() {
+    let (first, _) = crypto::gen_keypair();
+    let (second, _) = crypto::gen_keypair();
+
+    // Candidates tied in all rounds are eliminated in order of the candidates.
+    let rounds = ranked::instant_runoff(&[first, second], &[vec![first], vec![second]]);
+    assert_eq!(rounds.len(), 2);
+    assert_eq!(rounds[0].eliminated(), vec![first]);
+    assert_eq!(rounds[1].tallies()[0].candidate(), &second);
+
+    // Majority of the first round wins at once.
+    let ballots = vec![vec![first], vec![first, second], vec![second]];
+    let rounds = ranked::instant_runoff(&[first, second], &ballots);
+    assert_eq!(rounds.len(), 1);
+    assert!(rounds[0].eliminated().is_empty());
+
+    assert!(!ranked::is_valid_ranking(&[first, first], &[first, second]));
+    assert!(!ranked::is_valid_ranking(&[first, crypto::gen_keypair().0], &[first, second]));
+}
+
+#[test]
+fn test_stv_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    let mode = ElectionMode::SingleTransferable;
+    create_election_with_options(&mut testkit, election_id, "Election", mode, 2, 0);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (mallory, _) = create_candidate(&mut testkit, election_id, "Mallory", "Some info");
+    let (a, c, m) = (*alice.pub_key(), *carol.pub_key(), *mallory.pub_key());
+    let mut voters = vec![];
+    let names = ["Bob", "Dave", "Eve", "Frank", "Grace", "Heidi", "Ivan", "Judy", "Oscar"];
+    for name in &names {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+    let mut rankings = vec![vec![a, c]; 6];
+    rankings.extend(vec![vec![c], vec![m], vec![m]]);
+    for (&(ref voter, ref key), ranking) in voters.iter().zip(rankings) {
+        add_ranked_vote(&mut testkit, election_id, (voter, key), ranking);
+    }
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    // Quota is 9 / 3 + 1 = 4 votes. Alice is elected and her surplus of 2 votes passes
+    // on to Carol, then Mallory is eliminated and Carol takes the remaining seat.
+    let scale = ranked::STV_SCALE;
+    let rounds = get_stv_rounds(&testkit, election_id);
+    assert_eq!(rounds.len(), 3);
+    assert!(rounds.iter().all(|round| round.quota() == 4 * scale));
+    let elected: Vec<_> = rounds.iter().map(|round| round.elected()).collect();
+    assert_eq!(elected, vec![vec![a], vec![], vec![c]]);
+    let transferred: Vec<_> = rounds.iter().map(|round| round.transferred()).collect();
+    assert_eq!(transferred, vec![vec![a], vec![m], vec![]]);
+
+    // Every of 6 ballots of Alice passes on a third of a vote, the rest is dropped.
+    let transfers = rounds[0].transfers();
+    assert_eq!(transfers.len(), 1);
+    assert_eq!(transfers[0].candidate(), &c);
+    assert_eq!(transfers[0].votes(), 6 * (scale / 3));
+    assert!(rounds[1].transfers().is_empty());
+    assert_eq!(rounds[2].exhausted(), 2 * scale);
+
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    let first_preferences: u64 = results.iter().map(|res| res.vote_num()).sum();
+    assert_eq!(first_preferences, 9);
+}
+
+#[test]
+fn test_invalid_seats() {
+    let (mut testkit, _) = init_testkit();
+
+    let mode = ElectionMode::SingleTransferable;
+    let (tx, _) =
+        create_election_with_options(&mut testkit, ELECTION_ID + 1, "Election", mode, 0, 0);
+    assert_tx_error(&testkit, &tx, Error::InvalidSeats);
+
+    let mode = ElectionMode::Ranked;
+    let (tx, _) =
+        create_election_with_options(&mut testkit, ELECTION_ID + 2, "Election", mode, 2, 0);
+    assert_tx_error(&testkit, &tx, Error::InvalidSeats);
+}
+
+#[test]
+fn test_approval_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    create_election_with_mode(&mut testkit, election_id, "Election", ElectionMode::Approval);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (mallory, _) = create_candidate(&mut testkit, election_id, "Mallory", "Some info");
+    let (a, c, m) = (*alice.pub_key(), *carol.pub_key(), *mallory.pub_key());
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve", "Frank", "Grace", "Heidi"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+
+    // Ballot approving more candidates than the election has is rejected at once.
+    let (heidi, heidi_key) = voters.pop().unwrap();
+    let tx = add_approval_vote(&mut testkit, election_id, (&heidi, &heidi_key), vec![a, c, m, a]);
+    assert_tx_error(&testkit, &tx, Error::InvalidBallot);
+
+    let approvals = vec![
+        vec![a, c],
+        vec![a],
+        vec![c, m],
+        vec![],
+        // Approving the same candidate twice spoils the ballot.
+        vec![m, m],
+    ];
+    for (&(ref voter, ref key), approved) in voters.iter().zip(approvals) {
+        add_approval_vote(&mut testkit, election_id, (voter, key), approved);
+    }
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    let mut results: Vec<(PublicKey, u64)> = get_approval_results(&testkit, election_id)
+        .iter()
+        .map(|res| (*res.candidate(), res.approvals()))
+        .collect();
+    results.sort_by_key(|&(candidate, _)| [a, c, m].iter().position(|&x| x == candidate));
+    assert_eq!(results, vec![(a, 2), (c, 2), (m, 1)]);
+
+    let results = get_vote_result_decrypted(&testkit, election_id);
+    let approvals: u64 = results.iter().map(|res| res.vote_num()).sum();
+    assert_eq!(approvals, 5);
+
+    // The spoiled ballot is listed for its voter.
+    let rejected = get_rejected_votes(&testkit, election_id);
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].vote_id(), &voters[4].0.hash());
+    assert_eq!(rejected[0].reason(), RejectionReason::InvalidChoice as u8);
+}
+
+#[test]
+fn test_score_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let election_id = ELECTION_ID + 1;
+    let mode = ElectionMode::Score;
+    create_election_with_options(&mut testkit, election_id, "Election", mode, 1, 5);
+
+    let (alice, _) = create_candidate(&mut testkit, election_id, "Alice", "Some info");
+    let (carol, _) = create_candidate(&mut testkit, election_id, "Carol", "Some info");
+    let (mallory, _) = create_candidate(&mut testkit, election_id, "Mallory", "Some info");
+    let (a, c, m) = (*alice.pub_key(), *carol.pub_key(), *mallory.pub_key());
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve", "Frank", "Grace"] {
+        let (tx, key) = create_voter(&mut testkit, election_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+
+    start_voting(&mut testkit);
+
+    // Ballot scoring more candidates than the election has is rejected at once.
+    let (grace, grace_key) = voters.pop().unwrap();
+    let scores = vec![(a, 1), (c, 1), (m, 1), (a, 1)];
+    let tx = add_score_vote(&mut testkit, election_id, (&grace, &grace_key), scores);
+    assert_tx_error(&testkit, &tx, Error::InvalidBallot);
+
+    let scores = vec![
+        vec![(a, 5), (c, 3)],
+        vec![(a, 2), (m, 4)],
+        // Scores above the maximal one or scoring the same candidate twice spoil the ballot.
+        vec![(c, 6)],
+        vec![(a, 1), (a, 1)],
+    ];
+    for (&(ref voter, ref key), scores) in voters.iter().zip(scores) {
+        add_score_vote(&mut testkit, election_id, (voter, key), scores);
+    }
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, election_id);
+    assert_eq!(get_phase(&testkit, election_id), ElectionPhase::Tallied);
+
+    // Candidates missing from a ballot are scored zero.
+    let mut results: Vec<(PublicKey, u64, Vec<u64>)> = get_score_results(&testkit, election_id)
+        .iter()
+        .map(|res| (*res.candidate(), res.total(), res.distribution()))
+        .collect();
+    results.sort_by_key(|&(candidate, _, _)| [a, c, m].iter().position(|&x| x == candidate));
+    assert_eq!(
+        results,
+        vec![
+            (a, 7, vec![0, 0, 1, 0, 0, 1]),
+            (c, 3, vec![1, 0, 0, 1, 0, 0]),
+            (m, 4, vec![1, 0, 0, 0, 1, 0]),
+        ]
+    );
+
+    let rejected: Vec<(Hash, u8)> = get_rejected_votes(&testkit, election_id)
+        .iter()
+        .map(|vote| (*vote.vote_id(), vote.reason()))
+        .collect();
+    let reason = RejectionReason::InvalidChoice as u8;
+    let spoiled = vec![(voters[2].0.hash(), reason), (voters[3].0.hash(), reason)];
+    assert_eq!(rejected.len(), 2);
+    assert!(spoiled.iter().all(|vote| rejected.contains(vote)));
+}
+
+#[test]
+fn test_invalid_max_score() {
+    let (mut testkit, _) = init_testkit();
+
+    let mode = ElectionMode::Score;
+    let (tx, _) =
+        create_election_with_options(&mut testkit, ELECTION_ID + 1, "Election", mode, 1, 0);
+    assert_tx_error(&testkit, &tx, Error::InvalidMaxScore);
+
+    let mode = ElectionMode::Approval;
+    let (tx, _) =
+        create_election_with_options(&mut testkit, ELECTION_ID + 2, "Election", mode, 1, 5);
+    assert_tx_error(&testkit, &tx, Error::InvalidMaxScore);
+}
+
+#[test]
+fn test_condorcet_methods() {
+    let (a, _) = crypto::gen_keypair();
+    let (b, _) = crypto::gen_keypair();
+    let (c, _) = crypto::gen_keypair();
+    let candidates = [a, b, c];
+
+    // Preferences form a cycle: A beats B 6 to 3, B beats C 7 to 2, C beats A 5 to 4.
+    let mut ballots = vec![vec![a, b, c]; 4];
+    ballots.extend(vec![vec![b, c, a]; 3]);
+    ballots.extend(vec![vec![c, a, b]; 2]);
+    let matrix = ranked::pairwise_preferences(&candidates, &ballots);
+    assert_eq!(matrix, vec![vec![0, 6, 4], vec![3, 0, 7], vec![5, 2, 0]]);
+
+    // The weakest defeat of C over A is dropped by both methods.
+    assert_eq!(ranked::schulze_winners(&matrix), vec![0]);
+    assert_eq!(ranked::ranked_pairs_winners(&matrix), vec![0]);
+
+    // Unranked candidates lose to the ranked ones, tied candidates are both winners.
+    let matrix = ranked::pairwise_preferences(&candidates, &[vec![a], vec![b]]);
+    assert_eq!(matrix, vec![vec![0, 1, 1], vec![1, 0, 1], vec![0, 0, 0]]);
+    assert_eq!(ranked::schulze_winners(&matrix), vec![0, 1]);
+    assert_eq!(ranked::ranked_pairs_winners(&matrix), vec![0, 1]);
+}
+
+#[test]
+fn test_referendum_tally() {
+    let (mut testkit, keys) = init_testkit();
+    let passed_id = ELECTION_ID + 1;
+    let failed_id = ELECTION_ID + 2;
+    create_referendum(&mut testkit, passed_id, Majority::TwoThirds as u8, 60);
+    create_referendum(&mut testkit, failed_id, Majority::Simple as u8, 60);
+
+    // Options of a referendum are built-in.
+    let (tx, _) = create_candidate(&mut testkit, passed_id, "Yes", "Some info");
+    assert_tx_error(&testkit, &tx, Error::WrongElectionMode);
+
+    let mut voters = vec![];
+    for name in &["Bob", "Dave", "Eve", "Frank", "Grace"] {
+        let (tx, key) = create_voter(&mut testkit, passed_id, name);
+        voters.push((*tx.pub_key(), key));
+    }
+    let (bob_tx, bob_key) = create_voter(&mut testkit, failed_id, "Bob");
+    create_voter(&mut testkit, failed_id, "Dave");
+
+    start_voting(&mut testkit);
+    let choices = vec![
+        ReferendumChoice::Yes,
+        ReferendumChoice::Yes,
+        ReferendumChoice::No,
+        ReferendumChoice::Abstain,
+    ];
+    for (&(ref voter, ref key), choice) in voters.iter().zip(choices) {
+        add_referendum_vote(&mut testkit, passed_id, (voter, key), choice);
+    }
+    let bob = (bob_tx.pub_key(), &bob_key);
+    add_referendum_vote(&mut testkit, failed_id, bob, ReferendumChoice::Yes);
+
+    close_voting(&mut testkit);
+    tally_election(&mut testkit, &keys, passed_id);
+    tally_election(&mut testkit, &keys, failed_id);
+
+    // Two thirds of the votes are `Yes` ones and 4 of 5 voters voted.
+    let result = get_referendum_result(&testkit, passed_id);
+    assert_eq!(
+        (result.yes(), result.no(), result.abstain(), result.registered()),
+        (2, 1, 1, 5)
+    );
+    assert!(result.quorum_reached());
+    assert!(result.passed());
+
+    // Only 1 of 2 voters voted, so the motion fails despite the majority.
+    let result = get_referendum_result(&testkit, failed_id);
+    assert_eq!((result.yes(), result.registered()), (1, 2));
+    assert!(!result.quorum_reached());
+    assert!(!result.passed());
+
+    // Results are kept per referendum and are a part of its election hash.
+    let snapshot = testkit.snapshot();
+    let schema = VoteServiceSchema::new(&snapshot);
+    let election_state = schema.election_state(passed_id);
+    let results_root = schema.referendum_results(passed_id).merkle_root();
+    assert_eq!(schema.referendum_results(passed_id).len(), 1);
+    assert!(election_state.contains(&results_root));
+    assert_ne!(results_root, schema.referendum_results(failed_id).merkle_root());
+    let election_hash = schema.election_hashes().get(&passed_id.hash());
+    assert_eq!(election_hash, Some(schema::election_hash(&election_state)));
+}
+
+#[test]
+fn test_referendum_pass_rules() {
+    assert!(Majority::Simple.is_reached(2, 1));
+    assert!(!Majority::Simple.is_reached(1, 1));
+    assert!(Majority::ThreeFifths.is_reached(3, 2));
+    assert!(!Majority::TwoThirds.is_reached(3, 2));
+    assert!(Majority::TwoThirds.is_reached(2, 1));
+    assert!(!Majority::TwoThirds.is_reached(0, 0));
+
+    let (mut testkit, _) = init_testkit();
+    let (tx, _) = create_referendum(&mut testkit, ELECTION_ID + 1, 3, 50);
+    assert_tx_error(&testkit, &tx, Error::InvalidPassRule);
+
+    let (tx, _) = create_referendum(&mut testkit, ELECTION_ID + 2, Majority::Simple as u8, 101);
+    assert_tx_error(&testkit, &tx, Error::InvalidPassRule);
+}
+
+#[test]
+fn test_unknown_election_mode() {
+    let (mut testkit, _) = init_testkit();
+
+    let (public, secret) = admin_keypair();
+    let tx = TxCreateElection::new(
+        &public,
+        ELECTION_ID + 1,
+        "Election",
+        "Some description",
+        START_HEIGHT,
+        END_HEIGHT,
+        0,
+        42,
+        1,
+        0,
+        0,
+        0,
+        false,
+        &Hash::zero(),
+        &secret,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+    assert_tx_error(&testkit, &tx, Error::UnknownElectionMode);
+}
+
+#[test]
+fn test_invalid_reveal_period() {
+    let (mut testkit, _) = init_testkit();
+    let (public, secret) = admin_keypair();
+
+    // Reveal period follows voting in commit-reveal mode and is absent in other modes.
+    let cases = [
+        (ElectionMode::CommitReveal, 0),
+        (ElectionMode::CommitReveal, END_HEIGHT),
+        (ElectionMode::Encrypted, REVEAL_END_HEIGHT),
+    ];
+    for (idx, &(mode, reveal_end_height)) in cases.iter().enumerate() {
+        let tx = TxCreateElection::new(
+            &public,
+            ELECTION_ID + 1 + idx as u64,
+            "Election",
+            "Some description",
+            START_HEIGHT,
+            END_HEIGHT,
+            reveal_end_height,
+            mode as u8,
+            1,
+            0,
+            0,
+            0,
+            false,
+            &Hash::zero(),
+            &secret,
+        );
+        testkit.create_block_with_transaction(tx.clone());
+        assert_tx_error(&testkit, &tx, Error::InvalidRevealPeriod);
+    }
+}
+
+#[test]
+fn test_get_block() {
+    let (mut testkit, _) = init_testkit();
+
+    let (cand_tx, _) = create_candidate(&mut testkit, ELECTION_ID, "Alice", "Some info");
+    let cand = get_candidate(&testkit, ELECTION_ID, cand_tx.pub_key());
+
+    let (voter_tx, voter_key) = create_voter(&mut testkit, ELECTION_ID, "Bob");
+    let voter = get_voter(&testkit, ELECTION_ID, voter_tx.pub_key());
+
+    start_voting(&mut testkit);
+    let (_, height) = add_vote(
+        &mut testkit,
+        ELECTION_ID,
+        voter.pub_key(),
+        &voter_key,
+        cand.pub_key(),
+    );
+
+    let block_height = get_block(&testkit, ELECTION_ID, voter.pub_key());
+
+    assert_eq!(height, block_height);
+}
+
+fn init_testkit() -> (TestKit, DealtKeys) {
+    let keys = threshold::deal(THRESHOLD, VALIDATORS);
+
+    let (admin, _) = admin_keypair();
+    let cfg = VoteServiceConfig {
+        election_public_key: Some(group::point_to_key(&keys.public_key)),
+        threshold: THRESHOLD,
+        verification_keys: keys.verification_keys.iter().map(group::point_to_key).collect(),
+        administrators: vec![admin],
+        token_issuer_key: Some(blind::issuer_key(&token_issuer_secret())),
+        key_shares: Vec::new(),
+    };
+
+    // The testkit node is the first validator, other validators are simulated.
+    // The node also holds a share of a previous election key, which must not be used.
+    let mut testkit = TestKitBuilder::validator()
+        .with_validators(VALIDATORS)
+        .with_service(VoteService {
+            config: cfg,
+            key_shares: vec![group::random_scalar(), keys.shares[0].secret],
+        })
+        .create();
+    create_election(&mut testkit, ELECTION_ID, "Election");
+
+    (testkit, keys)
+}
+
+/// Decrypts the vote with the election secret key recovered from threshold of key shares.
+fn decrypt_vote(keys: &DealtKeys, vote: &EncryptedVote) -> Option<Vote> {
+    let secret = threshold::recover_secret(&keys.shares[..THRESHOLD as usize]);
+    let ephemeral = group::key_to_point(vote.pub_key())?;
+    cipher::decrypt_vote(vote, &(secret * ephemeral))
+}
+
+/// Creates decryption shares transaction signed by the validator with the specified index,
+/// using key share with the specified index.
+fn decryption_share_tx(
+    testkit: &TestKit,
+    keys: &DealtKeys,
+    election_id: u64,
+    validator: usize,
+    share: usize,
+) -> TxDecryptionShare {
+    let targets = get_decryption_targets(testkit, election_id);
+    let partials = threshold::partial_decryptions(&keys.shares[share], &targets).unwrap();
+
+    let (public, secret) = testkit.network().validators()[validator].service_keypair();
+    TxDecryptionShare::new(public, election_id, partials, secret)
+}
+
+/// Tallies the closed election: the node publishes its decryption shares by itself,
+/// the second validator shares are added manually, then the author tallies results.
+fn tally_election(testkit: &mut TestKit, keys: &DealtKeys, election_id: u64) {
+    testkit.create_block();
+    let tx = decryption_share_tx(testkit, keys, election_id, 1, 1);
+    testkit.create_block_with_transaction(tx);
+    assert_eq!(count_decryption_shares(testkit, election_id), THRESHOLD as usize);
+
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxTallyElection::new(&admin, election_id, &admin_key);
+    testkit.create_block_with_transaction(tx);
+}
+
+fn get_decryption_targets(testkit: &TestKit, election_id: u64) -> Vec<(Hash, PublicKey)> {
+    let snapshot = testkit.snapshot();
+    let schema = VoteServiceSchema::new(&snapshot);
+    let election = schema.election(election_id).expect("Election not found");
+    schema.decryption_targets(&election)
+}
+
+fn count_decryption_shares(testkit: &TestKit, election_id: u64) -> usize {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot)
+        .decryption_shares(election_id)
+        .values()
+        .count()
+}
+
+/// Returns key pair of the service administrator.
+fn admin_keypair() -> (PublicKey, SecretKey) {
+    crypto::gen_keypair_from_seed(&Seed::new([42; SEED_LENGTH]))
+}
+
+fn create_election(testkit: &mut TestKit, id: u64, title: &str) -> (TxCreateElection, SecretKey) {
+    create_election_with_mode(testkit, id, title, ElectionMode::Encrypted)
+}
+
+fn create_election_with_mode(
+    testkit: &mut TestKit,
+    id: u64,
+    title: &str,
+    mode: ElectionMode,
+) -> (TxCreateElection, SecretKey) {
+    create_election_with_options(testkit, id, title, mode, 1, 0)
+}
+
+fn create_election_with_options(
+    testkit: &mut TestKit,
+    id: u64,
+    title: &str,
+    mode: ElectionMode,
+    seats: u16,
+    max_score: u8,
+) -> (TxCreateElection, SecretKey) {
+    let (public, secret) = admin_keypair();
+    let reveal_end_height = if mode == ElectionMode::CommitReveal {
+        REVEAL_END_HEIGHT
+    } else {
+        0
+    };
+    let tx = TxCreateElection::new(
+        &public,
+        id,
+        title,
+        "Some description",
+        START_HEIGHT,
+        END_HEIGHT,
+        reveal_end_height,
+        mode as u8,
+        seats,
+        max_score,
+        0,
+        0,
+        false,
+        &Hash::zero(),
+        &secret,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    (tx, secret)
+}
+
+fn create_referendum(
+    testkit: &mut TestKit,
+    id: u64,
+    majority: u8,
+    quorum: u8,
+) -> (TxCreateElection, SecretKey) {
+    let (public, secret) = admin_keypair();
+    let tx = TxCreateElection::new(
+        &public,
+        id,
+        "Referendum",
+        "Some motion",
+        START_HEIGHT,
+        END_HEIGHT,
+        0,
+        ElectionMode::Referendum as u8,
+        1,
+        0,
+        majority,
+        quorum,
+        false,
+        &Hash::zero(),
+        &secret,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    (tx, secret)
+}
+
+fn create_revoting_election(
+    testkit: &mut TestKit,
+    id: u64,
+    mode: ElectionMode,
+) -> (TxCreateElection, SecretKey) {
+    let (public, secret) = admin_keypair();
+    let tx = TxCreateElection::new(
+        &public,
+        id,
+        "Election",
+        "Some description",
+        START_HEIGHT,
+        END_HEIGHT,
+        0,
+        mode as u8,
+        1,
+        0,
+        0,
+        0,
+        true,
+        &Hash::zero(),
+        &secret,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    (tx, secret)
+}
+
+fn create_roll_election(
+    testkit: &mut TestKit,
+    id: u64,
+    mode: ElectionMode,
+    roll_root: &Hash,
+) -> (TxCreateElection, SecretKey) {
+    let (public, secret) = admin_keypair();
+    let tx = TxCreateElection::new(
+        &public,
+        id,
+        "Election",
+        "Some description",
+        START_HEIGHT,
+        END_HEIGHT,
+        0,
+        mode as u8,
+        1,
+        0,
+        0,
+        0,
+        false,
+        roll_root,
+        &secret,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    (tx, secret)
+}
+
+/// Creates empty blocks, so the next block is the first one of the voting period.
+fn start_voting(testkit: &mut TestKit) {
+    testkit.create_blocks_until(Height(START_HEIGHT - 1));
+}
+
+/// Creates empty blocks, so the next block is the first one after the voting period.
+fn close_voting(testkit: &mut TestKit) {
+    testkit.create_blocks_until(Height(END_HEIGHT - 1));
+}
+
+/// Creates empty blocks, so the next block is the first one after the reveal period.
+fn close_revealing(testkit: &mut TestKit) {
+    testkit.create_blocks_until(Height(REVEAL_END_HEIGHT - 1));
+}
+
+fn get_election(testkit: &TestKit, id: u64) -> Election {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot)
+        .election(id)
+        .expect("Election not found")
+}
+
+fn get_phase(testkit: &TestKit, election_id: u64) -> ElectionPhase {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/election/phase")
+        .unwrap()
+}
+
+fn assert_tx_error<T: Message>(testkit: &TestKit, tx: &T, error: Error) {
+    let snapshot = testkit.snapshot();
+    let result = Schema::new(&snapshot)
+        .transaction_results()
+        .get(&Message::hash(tx))
+        .expect("Transaction not found");
+    let err = result.0.expect_err("Transaction succeeded");
+    assert_eq!(err.error_type(), TransactionErrorType::Code(error as u8));
+}
+
+fn get_elections(testkit: &TestKit) -> Vec<Election> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .get("v1/elections")
+        .unwrap()
+}
+
+fn create_candidate(
+    testkit: &mut TestKit,
+    election_id: u64,
+    name: &str,
+    info: &str,
+) -> (TxCreateCandidate, SecretKey) {
+    let (admin, admin_key) = admin_keypair();
+    let (public, secret) = crypto::gen_keypair();
+    let tx = TxCreateCandidate::new(&admin, &public, election_id, name, info, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    (tx, secret)
+}
+
+fn try_get_candidate(
+    testkit: &TestKit,
+    election_id: u64,
+    pub_key: &PublicKey,
+) -> Option<Candidate> {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot).candidate(election_id, pub_key)
+}
+
+fn get_candidate(testkit: &TestKit, election_id: u64, pub_key: &PublicKey) -> Candidate {
+    try_get_candidate(testkit, election_id, pub_key).expect("Candidate not found")
+}
+
+fn withdraw_candidate(
+    testkit: &mut TestKit,
+    election_id: u64,
+    candidate: &PublicKey,
+) -> TxWithdrawCandidate {
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxWithdrawCandidate::new(&admin, election_id, candidate, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn update_candidate(
+    testkit: &mut TestKit,
+    election_id: u64,
+    candidate: &PublicKey,
+    info: &str,
+) -> TxUpdateCandidate {
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxUpdateCandidate::new(&admin, election_id, candidate, info, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn create_voter(testkit: &mut TestKit, election_id: u64, name: &str) -> (TxCreateVoter, SecretKey) {
+    create_weighted_voter(testkit, election_id, name, 1)
+}
+
+fn create_weighted_voter(
+    testkit: &mut TestKit,
+    election_id: u64,
+    name: &str,
+    weight: u64,
+) -> (TxCreateVoter, SecretKey) {
+    let (admin, admin_key) = admin_keypair();
+    let (public, secret) = crypto::gen_keypair();
+    let tx = TxCreateVoter::new(&admin, &public, election_id, name, weight, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    (tx, secret)
+}
+
+fn set_voter_weight(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: &PublicKey,
+    weight: u64,
+) -> TxSetVoterWeight {
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxSetVoterWeight::new(&admin, election_id, voter, weight, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn get_weight_changes(testkit: &TestKit, election_id: u64) -> Vec<WeightChange> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/voters/weight_changes")
+        .unwrap()
+}
+
+fn transfer_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: &PublicKey,
+    voter_key: &SecretKey,
+    proxy: &PublicKey,
+) -> TxTransferVote {
+    let tx = TxTransferVote::new(voter, election_id, proxy, voter_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn get_vote_proxies(testkit: &TestKit, election_id: u64) -> Vec<(PublicKey, PublicKey)> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/voters/proxies")
+        .unwrap()
+}
+
+fn get_delegated_weights(testkit: &TestKit, election_id: u64) -> Vec<DelegatedWeight> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/delegated")
+        .unwrap()
+}
+
+fn revoke_voter(testkit: &mut TestKit, election_id: u64, voter: &PublicKey) -> TxRevokeVoter {
+    let (admin, admin_key) = admin_keypair();
+    let tx = TxRevokeVoter::new(&admin, election_id, voter, &admin_key);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn get_revoked_voters(testkit: &TestKit, election_id: u64) -> Vec<Voter> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/voters/revoked")
+        .unwrap()
+}
+
+fn get_replaced_votes(testkit: &TestKit, election_id: u64) -> Vec<ReplacedVote> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/votes/replaced")
+        .unwrap()
+}
+
+fn get_rejected_votes(testkit: &TestKit, election_id: u64) -> Vec<RejectedVote> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/votes/rejected")
+        .unwrap()
+}
+
+fn try_get_voter(testkit: &TestKit, election_id: u64, pub_key: &PublicKey) -> Option<Voter> {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot).voter(election_id, pub_key)
+}
+
+fn get_voter(testkit: &TestKit, election_id: u64, pub_key: &PublicKey) -> Voter {
+    try_get_voter(testkit, election_id, pub_key).expect("Voter not found")
+}
+
+fn add_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    from: &PublicKey,
+    from_key: &SecretKey,
+    to: &PublicKey,
+) -> (TxAddVote, u64) {
+    add_vote_signed_by(testkit, election_id, (from, from_key), from, to)
+}
+
+fn add_vote_signed_by(
+    testkit: &mut TestKit,
+    election_id: u64,
+    signer: (&PublicKey, &SecretKey),
+    from: &PublicKey,
+    to: &PublicKey,
+) -> (TxAddVote, u64) {
+    add_roll_vote(testkit, election_id, signer, from, to, (0, vec![]))
+}
+
+fn add_roll_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    signer: (&PublicKey, &SecretKey),
+    from: &PublicKey,
+    to: &PublicKey,
+    roll_proof: (u64, Vec<Hash>),
+) -> (TxAddVote, u64) {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let candidates = get_candidate_keys(testkit, election_id);
+    let vote = Vote::new(from, to);
+    let proven = match candidates.iter().position(|candidate| candidate == to) {
+        Some(choice) => {
+            zkp::encrypt_vote(&vote, choice, &candidates, &election_key, election_id, from)
+        }
+        // Vote for an unknown candidate can not be proven.
+        None => {
+            let enc_vote = cipher::encrypt_vote(&vote, &election_key);
+            let choice = *enc_vote.pub_key();
+            zkp::ProvenVote {
+                vote: enc_vote,
+                choice,
+                proofs: vec![],
+            }
+        }
+    };
+
+    let tx = vote_tx(election_id, signer, from, proven, roll_proof);
+    let block = testkit.create_block_with_transaction(tx.clone());
+
+    (tx, block.height().0)
+}
+
+fn vote_tx(
+    election_id: u64,
+    signer: (&PublicKey, &SecretKey),
+    from: &PublicKey,
+    proven: zkp::ProvenVote,
+    roll_proof: (u64, Vec<Hash>),
+) -> TxAddVote {
+    TxAddVote::new(
+        signer.0,
+        election_id,
+        from,
+        proven.vote.pub_key(),
+        proven.vote.data(),
+        &proven.choice,
+        proven.proofs,
+        roll_proof.0,
+        roll_proof.1,
+        signer.1,
+    )
+}
+
+fn add_ballot(
+    testkit: &mut TestKit,
+    election_id: u64,
+    signer: (&PublicKey, &SecretKey),
+    from: &PublicKey,
+    choice: usize,
+) -> TxAddBallot {
+    let election_key = *get_election(testkit, election_id).election_key();
+    let candidates = get_candidates(testkit, election_id).len();
+    let ballot = zkp::encrypt_ballot(choice, candidates, &election_key, election_id, from);
+
+    let tx = ballot_tx(election_id, signer, from, ballot);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn ballot_tx(
+    election_id: u64,
+    signer: (&PublicKey, &SecretKey),
+    from: &PublicKey,
+    ballot: zkp::EncryptedBallot,
+) -> TxAddBallot {
+    TxAddBallot::new(
+        signer.0,
+        election_id,
+        from,
+        ballot.ciphertexts,
+        ballot.proofs,
+        &ballot.sum_challenge,
+        &ballot.sum_response,
+        signer.1,
+    )
+}
+
+/// Returns secret key of the token issuer.
+fn token_issuer_secret() -> Scalar {
+    Scalar::hash_from_bytes::<Sha512>(b"token issuer")
+}
+
+/// Issues a blind-signed voting token for the voter, bound to the one-time key.
+fn issue_token(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    ballot_key: &PublicKey,
+) -> Token {
+    let (admin, admin_key) = admin_keypair();
+    let issuer_secret = token_issuer_secret();
+
+    let commitment = blind::commit(&issuer_secret, election_id, voter.0);
+    let tx = TxTokenCommitment::new(&admin, election_id, voter.0, &commitment, &admin_key);
+    testkit.create_block_with_transaction(tx);
+
+    let issuer_key = blind::issuer_key(&issuer_secret);
+    let (blinding, challenge) =
+        blind::blind(&issuer_key, &commitment, election_id, ballot_key).unwrap();
+    let tx = TxRequestToken::new(voter.0, election_id, voter.0, &challenge, voter.1);
+    testkit.create_block_with_transaction(tx);
+
+    // The issuer signs the challenge stored on the blockchain.
+    let challenge = *get_token_issuance(testkit, election_id, voter.0).challenge();
+    let response = blind::respond(&issuer_secret, election_id, voter.0, &challenge).unwrap();
+    let tx = TxIssueToken::new(&admin, election_id, voter.0, &response, &admin_key);
+    testkit.create_block_with_transaction(tx);
+
+    let issuance = get_token_issuance(testkit, election_id, voter.0);
+    assert!(issuance.is_issued());
+    blind::unblind(&blinding, issuance.response()).unwrap()
+}
+
+fn get_token_issuance(testkit: &TestKit, election_id: u64, voter: &PublicKey) -> TokenIssuance {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&VoterQuery {
+            election_id,
+            pub_key: *voter,
+        })
+        .get("v1/token")
+        .unwrap()
+}
+
+fn add_anonymous_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    ballot_key: (&PublicKey, &SecretKey),
+    to: &PublicKey,
+    token: &Token,
+) -> TxAddAnonymousVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let enc_vote = cipher::encrypt_vote(&Vote::new(ballot_key.0, to), &election_key);
+
+    let tx = TxAddAnonymousVote::new(
+        ballot_key.0,
+        election_id,
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        &token.commitment,
+        &token.response,
+        ballot_key.1,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+/// Returns keys of the voters of the election, in order of the voters table.
+fn get_ring(testkit: &TestKit, election_id: u64) -> Vec<PublicKey> {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot)
+        .voters(election_id)
+        .keys()
+        .collect()
+}
+
+/// Casts the vote of the voter from a fresh one-time key with a ring signature.
+fn add_ring_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter_key: &SecretKey,
+    to: &PublicKey,
+) -> TxAddRingVote {
+    let (public, secret) = crypto::gen_keypair();
+    let ring = get_ring(testkit, election_id);
+    let signature = lsag::sign(&ring, voter_key, election_id, &public).unwrap();
+
+    let tx = ring_vote_tx(testkit, election_id, (&public, &secret), to, signature);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn ring_vote_tx(
+    testkit: &TestKit,
+    election_id: u64,
+    signer: (&PublicKey, &SecretKey),
+    to: &PublicKey,
+    signature: lsag::RingSignature,
+) -> TxAddRingVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let enc_vote = cipher::encrypt_vote(&Vote::new(signer.0, to), &election_key);
+
+    TxAddRingVote::new(
+        signer.0,
+        election_id,
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        &signature.key_image,
+        &signature.challenge,
+        signature.responses,
+        signer.1,
+    )
+}
+
+fn add_mix_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    to: &PublicKey,
+) -> TxAddMixVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let vote = mixnet::encrypt_vote(to, &election_key, voter.0);
+
+    let tx = TxAddMixVote::new(
+        voter.0,
+        election_id,
+        voter.0,
+        vote.ciphertext,
+        &vote.challenge,
+        &vote.response,
+        voter.1,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+/// Creates transaction with a shuffle of the current mixed votes, signed by the validator
+/// with the specified index.
+fn shuffle_tx(testkit: &TestKit, election_id: u64, validator: usize) -> TxShuffle {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let election_key = group::key_to_point(&election_key).unwrap();
+    let stage = count_mixes(testkit, election_id) as u16;
+    let input = get_mixed_votes(testkit, election_id);
+    let (ciphertexts, proof) = mixnet::shuffle(&election_key, election_id, stage, &input).unwrap();
+
+    let (public, secret) = testkit.network().validators()[validator].service_keypair();
+    TxShuffle::new(public, election_id, stage, ciphertexts, proof, secret)
+}
+
+fn get_mixed_votes(testkit: &TestKit, election_id: u64) -> Vec<Ciphertext> {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot).mixed_votes(election_id)
+}
+
+fn count_mixes(testkit: &TestKit, election_id: u64) -> usize {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot).mixes(election_id).len() as usize
+}
+
+/// Commits to the vote with a random salt, returns the salt.
+fn commit_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    to: &PublicKey,
+) -> Hash {
+    let salt = Hash::new(rand::random());
+    let commitment = schema::vote_commitment(election_id, voter.0, to, &salt);
+
+    let tx = TxCommitVote::new(voter.0, election_id, voter.0, &commitment, voter.1);
+    testkit.create_block_with_transaction(tx);
+
+    salt
+}
+
+fn reveal_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    to: &PublicKey,
+    salt: &Hash,
+) -> TxRevealVote {
+    let tx = TxRevealVote::new(voter.0, election_id, voter.0, to, salt, voter.1);
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn get_commitment(testkit: &TestKit, election_id: u64, voter: &PublicKey) -> Hash {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&VoterQuery {
+            election_id,
+            pub_key: *voter,
+        })
+        .get("v1/commitment")
+        .unwrap()
+}
+
+fn get_unrevealed(testkit: &TestKit, election_id: u64) -> Vec<PublicKey> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/unrevealed")
+        .unwrap()
+}
+
+fn add_ranked_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    ranking: Vec<PublicKey>,
+) -> TxAddRankedVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let vote = RankedVote::new(voter.0, ranking);
+    let enc_vote = cipher::encrypt_ranked_vote(&vote, &election_key);
+
+    let tx = TxAddRankedVote::new(
+        voter.0,
+        election_id,
+        voter.0,
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        voter.1,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn add_approval_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    approved: Vec<PublicKey>,
+) -> TxAddApprovalVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let vote = ApprovalVote::new(voter.0, approved);
+    let enc_vote = cipher::encrypt_approval_vote(&vote, &election_key);
+
+    let tx = TxAddApprovalVote::new(
+        voter.0,
+        election_id,
+        voter.0,
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        voter.1,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn add_score_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    scores: Vec<(PublicKey, u8)>,
+) -> TxAddScoreVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let scores = scores
+        .iter()
+        .map(|&(ref candidate, score)| CandidateScore::new(candidate, score))
+        .collect();
+    let vote = ScoreVote::new(voter.0, scores);
+    let enc_vote = cipher::encrypt_score_vote(&vote, &election_key);
+
+    let tx = TxAddScoreVote::new(
+        voter.0,
+        election_id,
+        voter.0,
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        voter.1,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn get_approval_results(testkit: &TestKit, election_id: u64) -> Vec<ApprovalResult> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/approval")
+        .unwrap()
+}
+
+fn get_score_results(testkit: &TestKit, election_id: u64) -> Vec<ScoreResult> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/score")
+        .unwrap()
+}
+
+fn get_pairwise_results(testkit: &TestKit, election_id: u64) -> Vec<PairwiseResult> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/pairwise")
+        .unwrap()
+}
+
+fn add_referendum_vote(
+    testkit: &mut TestKit,
+    election_id: u64,
+    voter: (&PublicKey, &SecretKey),
+    choice: ReferendumChoice,
+) -> TxAddReferendumVote {
+    let election_key = get_election(testkit, election_id).election_key().clone();
+    let vote = ReferendumVote::new(voter.0, choice as u8);
+    let enc_vote = cipher::encrypt_referendum_vote(&vote, &election_key);
+
+    let tx = TxAddReferendumVote::new(
+        voter.0,
+        election_id,
+        voter.0,
+        enc_vote.pub_key(),
+        enc_vote.data(),
+        voter.1,
+    );
+    testkit.create_block_with_transaction(tx.clone());
+
+    tx
+}
+
+fn get_referendum_result(testkit: &TestKit, election_id: u64) -> ReferendumResult {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/referendum")
+        .unwrap()
+}
+
+fn get_rounds(testkit: &TestKit, election_id: u64) -> Vec<Round> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/rounds")
+        .unwrap()
+}
+
+fn get_stv_rounds(testkit: &TestKit, election_id: u64) -> Vec<StvRound> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results/stv")
+        .unwrap()
+}
+
+fn get_ballot(testkit: &TestKit, election_id: u64, voter: &PublicKey) -> Option<Ballot> {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot).ballot(election_id, &voter.hash())
+}
+
+fn get_candidates(testkit: &TestKit, election_id: u64) -> Vec<Candidate> {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/candidates")
+        .unwrap()
+}
+
+fn get_candidate_keys(testkit: &TestKit, election_id: u64) -> Vec<PublicKey> {
+    get_candidates(testkit, election_id)
+        .iter()
+        .map(|candidate| *candidate.pub_key())
+        .collect()
+}
+
+fn try_get_vote(testkit: &TestKit, election_id: u64, voter: &PublicKey) -> Option<EncryptedVote> {
+    let snapshot = testkit.snapshot();
+    VoteServiceSchema::new(&snapshot).vote(election_id, &voter.hash())
+}
+
+fn get_vote(testkit: &TestKit, election_id: u64, voter: &PublicKey) -> EncryptedVote {
+    try_get_vote(testkit, election_id, voter).expect("Vote not found")
+}
+
+fn get_vote_result_decrypted(testkit: &TestKit, election_id: u64) -> Vec<DecryptedCandidateResult> {
+    let api = testkit.api();
+
+    let entry: Option<Vec<DecryptedCandidateResult>> = api
+        .public(ApiKind::Service("voteservice"))
+        .query(&ElectionQuery { election_id })
+        .get("v1/results_dec")
+        .unwrap();
+
+    entry.unwrap()
+}
+
+fn get_block(testkit: &TestKit, election_id: u64, pub_key: &PublicKey) -> u64 {
+    let api = testkit.api();
+
+    let entry: Option<u64> = api
+        .public(ApiKind::Service("voteservice"))
+        .query(&BlockQuery {
+            election_id,
+            pub_key: *pub_key,
+        })
+        .get("v1/block")
+        .unwrap();
+
+    entry.unwrap()
+}
+
+fn get_vote_proof(testkit: &TestKit, election_id: u64, voter: &PublicKey) -> VoteProof {
+    let api = testkit.api();
+
+    api.public(ApiKind::Service("voteservice"))
+        .query(&VoterQuery {
+            election_id,
+            pub_key: *voter,
+        })
+        .get("v1/votes/proof")
+        .unwrap()
+}
+
+fn validator_keys(testkit: &TestKit) -> Vec<PublicKey> {
+    testkit
+        .network()
+        .validators()
+        .iter()
+        .map(|v| v.public_keys().consensus_key)
+        .collect()
+}
+
+/// Checks that tampering of the election table with the specified index is detected:
+/// the election state no longer matches the stored hash, and the state hash changes
+/// as soon as the election hash is recalculated.
+fn assert_tampered_election(
+    fork: &mut Fork,
+    state_hash: &[Hash],
+    election_state: &[Hash],
+    table_idx: usize,
+) {
+    let tampered_state = VoteServiceSchema::new(&*fork).election_state(ELECTION_ID);
+    for (idx, (before, after)) in election_state.iter().zip(&tampered_state).enumerate() {
+        assert_eq!(before == after, idx != table_idx);
+    }
+
+    let stored_hash = VoteServiceSchema::new(&*fork)
+        .election_hashes()
+        .get(&ELECTION_ID.hash())
+        .unwrap();
+    assert_ne!(schema::election_hash(&tampered_state), stored_hash);
 
-    vote
+    VoteServiceSchema::new(&mut *fork).update_election_hash(ELECTION_ID);
+    let tampered_hash = VoteServiceSchema::new(&*fork).state_hash();
+    assert_eq!(state_hash[0], tampered_hash[0]);
+    assert_ne!(state_hash[1], tampered_hash[1]);
 }
@@ -0,0 +1,204 @@
+use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use exonum::crypto::{Hash, PublicKey};
+use group;
+use schema::PartialDecryption;
+use sha2::Sha512;
+
+/// Threshold decryption.
+///
+/// The election secret key is split between validators with Shamir's scheme,
+/// so any `threshold` of them can decrypt ciphertexts together, while fewer can not.
+/// Every decryption share carries a Chaum-Pedersen proof that it matches
+/// the verification key of the validator.
+///
+/// Keys are generated by a trusted dealer, see `deal`. This is an assumption
+/// of the scheme: the dealer knows the election secret key and could decrypt every vote,
+/// so it must forget the key and the shares after handing them out. Distributed key
+/// generation would remove the dealer, but is not implemented.
+
+/// Domain separation tag of decryption share proofs.
+const PROOF_TAG: &[u8] = b"voteservice.decryption_share";
+
+/// Key share of a validator.
+#[derive(Clone, Copy, Debug)]
+pub struct KeyShare {
+    /// Index of the share, starting from 1. Validator `i` holds share `i + 1`.
+    pub index: u16,
+    /// Secret part of the share.
+    pub secret: Scalar,
+}
+
+/// Keys produced by the dealer.
+#[derive(Clone, Debug)]
+pub struct DealtKeys {
+    /// Joint election public key, clients encrypt their votes against it.
+    pub public_key: RistrettoPoint,
+    /// Public parts of the shares, in the same order as shares.
+    pub verification_keys: Vec<RistrettoPoint>,
+    /// Key shares, to be handed out to validators.
+    pub shares: Vec<KeyShare>,
+}
+
+/// Splits a fresh election secret key into `participants` shares,
+/// any `threshold` of which are enough to decrypt ciphertexts.
+///
+/// The dealer must forget the secret key and the shares after handing them out.
+pub fn deal(threshold: u16, participants: u16) -> DealtKeys {
+    if threshold == 0 || threshold > participants {
+        panic!("threshold::deal: invalid threshold");
+    }
+
+    let coefficients: Vec<Scalar> = (0..threshold).map(|_| group::random_scalar()).collect();
+    let shares: Vec<KeyShare> = (1..=participants)
+        .map(|index| KeyShare {
+            index,
+            secret: evaluate(&coefficients, index),
+        })
+        .collect();
+
+    DealtKeys {
+        public_key: &coefficients[0] * &RISTRETTO_BASEPOINT_TABLE,
+        verification_keys: shares
+            .iter()
+            .map(|share| &share.secret * &RISTRETTO_BASEPOINT_TABLE)
+            .collect(),
+        shares,
+    }
+}
+
+/// Recovers the election secret key from `threshold` key shares.
+pub fn recover_secret(shares: &[KeyShare]) -> Scalar {
+    let indices: Vec<u16> = shares.iter().map(|share| share.index).collect();
+    shares.iter().fold(Scalar::zero(), |acc, share| {
+        acc + lagrange_coefficient(share.index, &indices) * share.secret
+    })
+}
+
+/// Computes decryption share of the ciphertext with the specified one-time key.
+///
+/// Returns `None` if the one-time key is not a valid group element.
+pub fn partial_decryption(
+    share: &KeyShare,
+    ciphertext_id: &Hash,
+    ephemeral: &PublicKey,
+) -> Option<PartialDecryption> {
+    let ephemeral = group::key_to_point(ephemeral)?;
+    let verification_key = &share.secret * &RISTRETTO_BASEPOINT_TABLE;
+    let decryption = share.secret * ephemeral;
+
+    // Nonce is derived deterministically, so a share which is sent again
+    // results in the same transaction.
+    let mut nonce_bytes = share.secret.to_bytes().to_vec();
+    nonce_bytes.extend_from_slice(ephemeral.compress().as_bytes());
+    nonce_bytes.extend_from_slice(ciphertext_id.as_ref());
+    let nonce = Scalar::hash_from_bytes::<Sha512>(&nonce_bytes);
+
+    let commitment_base = &nonce * &RISTRETTO_BASEPOINT_TABLE;
+    let commitment_ephemeral = nonce * ephemeral;
+    let challenge = group::challenge(
+        PROOF_TAG,
+        &[
+            &RISTRETTO_BASEPOINT_POINT,
+            &verification_key,
+            &ephemeral,
+            &decryption,
+            &commitment_base,
+            &commitment_ephemeral,
+        ],
+    );
+    let response = nonce + challenge * share.secret;
+
+    Some(PartialDecryption::new(
+        ciphertext_id,
+        &group::point_to_key(&decryption),
+        &group::scalar_to_hash(&challenge),
+        &group::scalar_to_hash(&response),
+    ))
+}
+
+/// Computes decryption shares of the ciphertexts, given with their ids and one-time keys,
+/// see `VoteServiceSchema::decryption_targets`.
+///
+/// Returns `None` if a one-time key is not a valid group element: shares are matched
+/// with ciphertexts by position, so none of them can be skipped.
+pub fn partial_decryptions(
+    share: &KeyShare,
+    ciphertexts: &[(Hash, PublicKey)],
+) -> Option<Vec<PartialDecryption>> {
+    ciphertexts
+        .iter()
+        .map(|(ciphertext_id, ephemeral)| partial_decryption(share, ciphertext_id, ephemeral))
+        .collect()
+}
+
+/// Checks the proof of the decryption share against the verification key of the validator.
+pub fn verify_partial_decryption(
+    verification_key: &RistrettoPoint,
+    ephemeral: &PublicKey,
+    partial: &PartialDecryption,
+) -> bool {
+    let ephemeral = group::key_to_point(ephemeral);
+    let decryption = group::key_to_point(partial.share());
+    let challenge = group::hash_to_scalar(partial.challenge());
+    let response = group::hash_to_scalar(partial.response());
+
+    let (ephemeral, decryption, challenge, response) =
+        match (ephemeral, decryption, challenge, response) {
+            (Some(e), Some(d), Some(c), Some(r)) => (e, d, c, r),
+            _ => return false,
+        };
+
+    let commitment_base = &response * &RISTRETTO_BASEPOINT_TABLE - challenge * verification_key;
+    let commitment_ephemeral = response * ephemeral - challenge * decryption;
+    let expected = group::challenge(
+        PROOF_TAG,
+        &[
+            &RISTRETTO_BASEPOINT_POINT,
+            verification_key,
+            &ephemeral,
+            &decryption,
+            &commitment_base,
+            &commitment_ephemeral,
+        ],
+    );
+
+    expected == challenge
+}
+
+/// Combines `threshold` decryption shares of the ciphertext, given with indices of key shares.
+///
+/// The result is the one-time key of the ciphertext multiplied by the election secret key,
+/// see `cipher::decrypt_vote`.
+pub fn combine(shares: &[(u16, RistrettoPoint)]) -> RistrettoPoint {
+    let indices: Vec<u16> = shares.iter().map(|share| share.0).collect();
+    shares
+        .iter()
+        .fold(RistrettoPoint::identity(), |acc, (index, point)| {
+            acc + lagrange_coefficient(*index, &indices) * point
+        })
+}
+
+/// Computes Lagrange coefficient of the share at zero.
+fn lagrange_coefficient(index: u16, indices: &[u16]) -> Scalar {
+    let x = Scalar::from(u64::from(index));
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for &other in indices.iter().filter(|&&other| other != index) {
+        let other = Scalar::from(u64::from(other));
+        numerator *= other;
+        denominator *= other - x;
+    }
+    numerator * denominator.invert()
+}
+
+/// Evaluates the polynomial with the specified coefficients at the share index.
+fn evaluate(coefficients: &[Scalar], index: u16) -> Scalar {
+    let x = Scalar::from(u64::from(index));
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |acc, coefficient| acc * x + coefficient)
+}
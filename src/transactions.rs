@@ -1,4 +1,5 @@
-use exonum::crypto::PublicKey;
+use exonum::crypto::{Hash, PublicKey};
+use schema::{BitProof, ChoiceProof, Ciphertext, PartialDecryption, ShuffleProof};
 
 /// Transactions.
 transactions! {
@@ -6,31 +7,379 @@ transactions! {
     pub VoteTransactions {
         const SERVICE_ID = super::SERVICE_ID;
 
+        /// Transaction type for creating new election.
+        struct TxCreateElection {
+            /// Public key of the election author, must be a service administrator.
+            author: &PublicKey,
+            /// Id of the election.
+            id: u64,
+            /// Title of the election.
+            title: &str,
+            /// Description of the election.
+            description: &str,
+            /// Height of the block, since which voting is open.
+            start_height: u64,
+            /// Height of the block, since which voting is closed.
+            end_height: u64,
+            /// Height of the block, since which votes of a commit-reveal election
+            /// can not be revealed, zero in other modes.
+            reveal_end_height: u64,
+            /// Ballot mode of the election, see `ElectionMode`.
+            mode: u8,
+            /// Number of seats to fill: one, or more in single transferable vote mode.
+            seats: u16,
+            /// Highest score of a candidate in score mode, zero in other modes.
+            max_score: u8,
+            /// Majority required to pass the motion in referendum mode, see `Majority`.
+            majority: u8,
+            /// Percentage of registered voters, which must vote to pass the motion
+            /// in referendum mode, zero in other modes.
+            quorum: u8,
+            /// Whether voters may cast votes again until voting is over,
+            /// only in encrypted mode.
+            revoting: bool,
+            /// Merkle root of the eligibility roll, zero hash if voters are registered
+            /// one by one. Only in encrypted mode.
+            roll_root: &Hash,
+        }
+
+        /// Transaction type for decrypting and tallying election results.
+        struct TxTallyElection {
+            /// Public key of the election author.
+            author: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+        }
+
+        /// Transaction type for publishing decryption shares of a validator.
+        struct TxDecryptionShare {
+            /// Service key of the validator.
+            validator: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Decryption shares of all votes of the election, in order of the votes table.
+            partials: Vec<PartialDecryption>,
+        }
+
         /// Transaction type for creating new candidate.
         struct TxCreateCandidate {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
             /// Id of the candidate.
             pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
             /// Name of the candidate.
             name: &str,
             /// Info about the candidate.
             info: &str,
         }
 
+        /// Transaction type for withdrawing a candidate from the election.
+        struct TxWithdrawCandidate {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the candidate.
+            candidate_id: &PublicKey,
+        }
+
+        /// Transaction type for updating info about a candidate.
+        struct TxUpdateCandidate {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the candidate.
+            candidate_id: &PublicKey,
+            /// New info about the candidate.
+            info: &str,
+        }
+
         /// Transaction type for creating new voter.
         struct TxCreateVoter {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
             /// Id of the voter.
             pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
             /// Name of the voter.
             name: &str,
+            /// Weight of the vote of the voter, e.g. number of shares.
+            weight: u64,
+        }
+
+        /// Transaction type for changing weight of a registered voter.
+        struct TxSetVoterWeight {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// New weight of the voter.
+            weight: u64,
+        }
+
+        /// Transaction type for revoking registration of a voter.
+        struct TxRevokeVoter {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+        }
+
+        /// Transaction type for authorizing a delegate to cast the vote on voter's behalf.
+        struct TxAuthorizeDelegate {
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Public key of the delegate.
+            delegate: &PublicKey,
+        }
+
+        /// Transaction type for transferring the vote to a proxy: another voter
+        /// of the election, who votes on the voter's behalf in liquid democracy.
+        ///
+        /// Unlike the delegate of `TxAuthorizeDelegate`, the proxy does not sign
+        /// the voter's vote, but casts its own one, which counts with the voter's weight.
+        struct TxTransferVote {
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter, the vote is transferred to. Id of the voter itself
+            /// revokes the transfer.
+            proxy_id: &PublicKey,
         }
 
         /// Transaction type for creating new vote.
         struct TxAddVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Vote encrypted on the client side against the election public key.
+            vote_data: Vec<u8>,
+            /// Point of the chosen candidate, encrypted with the one-time key of the vote.
+            choice: &PublicKey,
+            /// Proofs that the choice encrypts the point of a candidate, see `zkp::encrypt_vote`.
+            choice_proofs: Vec<ChoiceProof>,
+            /// Index of the voter key in the eligibility roll, if the voter is not registered.
+            roll_index: u64,
+            /// Merkle path of the voter key in the eligibility roll, see `roll::path`.
+            roll_path: Vec<Hash>,
+        }
+
+        /// Transaction type for adding a ballot to the homomorphic election.
+        struct TxAddBallot {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Ciphertexts of the ballot, in order of the candidates table.
+            ciphertexts: Vec<Ciphertext>,
+            /// Proofs that ciphertexts encrypt 0 or 1.
+            proofs: Vec<BitProof>,
+            /// Challenge of the proof that the sum of ciphertexts encrypts 1.
+            sum_challenge: &Hash,
+            /// Response of the proof that the sum of ciphertexts encrypts 1.
+            sum_response: &Hash,
+        }
+
+        /// Transaction type for publishing a commitment of the token issuer for a voter.
+        struct TxTokenCommitment {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Commitment of the token issuer, see `blind::commit`.
+            commitment: &PublicKey,
+        }
+
+        /// Transaction type for requesting a blind-signed voting token.
+        struct TxRequestToken {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Blinded challenge, see `blind::blind`.
+            challenge: &Hash,
+        }
+
+        /// Transaction type for signing the blinded challenge of a voter.
+        struct TxIssueToken {
+            /// Public key of the signer: the election author or a service administrator.
+            authority: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Response of the token issuer, see `blind::respond`.
+            response: &Hash,
+        }
+
+        /// Transaction type for casting a vote with a voting token from a one-time key.
+        struct TxAddAnonymousVote {
+            /// One-time public key of the signer, the token is bound to it.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Vote encrypted on the client side against the election public key.
+            vote_data: Vec<u8>,
+            /// Blinded commitment of the token.
+            token_commitment: &PublicKey,
+            /// Unblinded response of the token.
+            token_response: &Hash,
+        }
+
+        /// Transaction type for casting a vote with a linkable ring signature from a one-time key.
+        struct TxAddRingVote {
+            /// One-time public key of the signer, the ring signature is bound to it.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Vote encrypted on the client side against the election public key.
+            vote_data: Vec<u8>,
+            /// Key image of the voter, see `lsag::key_image`.
+            key_image: &PublicKey,
+            /// Challenge of the first voter of the ring.
+            ring_challenge: &Hash,
+            /// Responses of all voters of the ring, in order of the voters table.
+            ring_responses: Vec<Hash>,
+        }
+
+        /// Transaction type for adding a vote to the mixnet election.
+        struct TxAddMixVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Ciphertext of the candidate point, see `mixnet::encrypt_vote`.
+            ciphertext: Ciphertext,
+            /// Challenge of the proof of knowledge of the ciphertext randomness.
+            challenge: &Hash,
+            /// Response of the proof of knowledge of the ciphertext randomness.
+            response: &Hash,
+        }
+
+        /// Transaction type for publishing a shuffle of the votes made by a validator.
+        struct TxShuffle {
+            /// Service key of the validator.
+            validator: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Number of the mix, i.e. the number of mixes made before it.
+            stage: u16,
+            /// Re-encrypted and permuted ciphertexts.
+            ciphertexts: Vec<Ciphertext>,
+            /// Proof of the shuffle, see `mixnet::shuffle`.
+            proof: ShuffleProof,
+        }
+
+        /// Transaction type for committing to a vote in the commit-reveal election.
+        struct TxCommitVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// Commitment to the vote, see `schema::vote_commitment`.
+            commitment: &Hash,
+        }
+
+        /// Transaction type for revealing a committed vote after voting is over,
+        /// until the reveal period is over.
+        struct TxRevealVote {
+            /// Public key of the signer: the voter or the voter's delegate.
             pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
             /// Id of the voter.
             voter_id: &PublicKey,
             /// Id of the candidate.
             candidate_id: &PublicKey,
+            /// Salt of the commitment.
+            salt: &Hash,
+        }
+
+        /// Transaction type for adding a ranked vote to the ranked election.
+        struct TxAddRankedVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Ranked vote encrypted on the client side, see `cipher::encrypt_ranked_vote`.
+            vote_data: Vec<u8>,
+        }
+
+        /// Transaction type for adding an approval vote to the approval election.
+        struct TxAddApprovalVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Approval vote encrypted on the client side, see `cipher::encrypt_approval_vote`.
+            vote_data: Vec<u8>,
+        }
+
+        /// Transaction type for adding a score vote to the score election.
+        struct TxAddScoreVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Score vote encrypted on the client side, see `cipher::encrypt_score_vote`.
+            vote_data: Vec<u8>,
+        }
+
+        /// Transaction type for adding a vote to the referendum.
+        struct TxAddReferendumVote {
+            /// Public key of the signer: the voter or the voter's delegate.
+            pub_key: &PublicKey,
+            /// Id of the election.
+            election_id: u64,
+            /// Id of the voter.
+            voter_id: &PublicKey,
+            /// One-time public key used to encrypt the vote.
+            vote_key: &PublicKey,
+            /// Referendum vote encrypted on the client side,
+            /// see `cipher::encrypt_referendum_vote`.
+            vote_data: Vec<u8>,
         }
     }
 }
@@ -0,0 +1,418 @@
+use agreement;
+use cipher;
+use curve25519_dalek::constants::{RISTRETTO_BASEPOINT_POINT, RISTRETTO_BASEPOINT_TABLE};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use elgamal;
+use exonum::crypto::{CryptoHash, Hash, PublicKey};
+use group;
+use schema::{BitProof, ChoiceProof, Ciphertext, EncryptedVote, Vote};
+use sha2::Sha512;
+
+/// Zero-knowledge proofs of ballot validity.
+///
+/// Every ciphertext of a homomorphic ballot carries a disjunctive Chaum-Pedersen proof
+/// that it encrypts 0 or 1, and the ballot carries a Chaum-Pedersen proof that the sum
+/// of its ciphertexts encrypts 1, i.e. the ballot is a vote for exactly one candidate.
+/// Proofs are bound to the election and the voter, so a ballot copied from another voter
+/// or from another election is rejected.
+///
+/// A vote of the encrypted mode carries, besides the encrypted `Vote`, an ElGamal encryption
+/// of the point of the chosen candidate under the one-time key of the vote and
+/// a disjunctive Chaum-Pedersen proof that it encrypts the point of one of the candidates.
+/// On tally the decrypted point must match the candidate of the decrypted `Vote`.
+
+/// Domain separation tag of proofs of ciphertexts.
+const BIT_PROOF_TAG: &[u8] = b"voteservice.ballot.bit";
+
+/// Domain separation tag of proofs of ballot sums.
+const SUM_PROOF_TAG: &[u8] = b"voteservice.ballot.sum";
+
+/// Domain separation tag of proofs of vote choices.
+const CHOICE_PROOF_TAG: &[u8] = b"voteservice.vote.choice";
+
+/// Domain separation tag of points of candidates.
+const CANDIDATE_POINT_TAG: &[u8] = b"voteservice.candidate";
+
+/// Encrypted ballot with validity proofs, ready to be sent in `TxAddBallot`.
+#[derive(Clone, Debug)]
+pub struct EncryptedBallot {
+    /// Ciphertexts of the ballot, in order of the candidates table.
+    pub ciphertexts: Vec<Ciphertext>,
+    /// Proofs that ciphertexts encrypt 0 or 1.
+    pub proofs: Vec<BitProof>,
+    /// Challenge of the proof that the sum of ciphertexts encrypts 1.
+    pub sum_challenge: Hash,
+    /// Response of the proof that the sum of ciphertexts encrypts 1.
+    pub sum_response: Hash,
+}
+
+/// Encrypted vote with the proof of its choice, ready to be sent in `TxAddVote`.
+#[derive(Clone, Debug)]
+pub struct ProvenVote {
+    /// Encrypted vote, its one-time key encrypts the choice as well.
+    pub vote: EncryptedVote,
+    /// Point of the chosen candidate plus the one-time key multiplied by the election key.
+    pub choice: PublicKey,
+    /// Proofs that the choice encrypts the point of a candidate, one per candidate.
+    pub proofs: Vec<ChoiceProof>,
+}
+
+/// Encrypts a ballot of the voter for the candidate with the specified position in
+/// the candidates table (see `v1/candidates`) and proves its validity: the ballot has
+/// a ciphertext of 1 for the chosen candidate and a ciphertext of 0 for every other one.
+///
+/// Runs on the client side.
+pub fn encrypt_ballot(
+    choice: usize,
+    candidates: usize,
+    election_key: &PublicKey,
+    election_id: u64,
+    voter_id: &PublicKey,
+) -> EncryptedBallot {
+    let election_key =
+        group::key_to_point(election_key).expect("zkp::encrypt_ballot: invalid election key");
+    let bit_tag = tag(BIT_PROOF_TAG, election_id, voter_id);
+
+    let mut ciphertexts = Vec::with_capacity(candidates);
+    let mut proofs = Vec::with_capacity(candidates);
+    let mut randomness_sum = Scalar::zero();
+    for pos in 0..candidates {
+        let message = if pos == choice { 1 } else { 0 };
+        let randomness = group::random_scalar();
+        let ciphertext = elgamal::encrypt(message, &election_key, &randomness);
+        proofs.push(prove_bit(
+            &election_key,
+            &bit_tag,
+            &ciphertext,
+            message,
+            &randomness,
+        ));
+        ciphertexts.push(ciphertext);
+        randomness_sum += randomness;
+    }
+
+    let (sum_a, sum_b) = sum(&ciphertexts).expect("zkp::encrypt_ballot: invalid ciphertext");
+    let nonce = group::random_scalar();
+    let sum_challenge = group::challenge(
+        &tag(SUM_PROOF_TAG, election_id, voter_id),
+        &[
+            &election_key,
+            &sum_a,
+            &(sum_b - RISTRETTO_BASEPOINT_POINT),
+            &(&nonce * &RISTRETTO_BASEPOINT_TABLE),
+            &(nonce * election_key),
+        ],
+    );
+    let sum_response = nonce + sum_challenge * randomness_sum;
+
+    EncryptedBallot {
+        ciphertexts,
+        proofs,
+        sum_challenge: group::scalar_to_hash(&sum_challenge),
+        sum_response: group::scalar_to_hash(&sum_response),
+    }
+}
+
+/// Checks validity proofs of the ballot of the voter in the election.
+pub fn verify_ballot(
+    election_key: &RistrettoPoint,
+    election_id: u64,
+    voter_id: &PublicKey,
+    ciphertexts: &[Ciphertext],
+    proofs: &[BitProof],
+    sum_challenge: &Hash,
+    sum_response: &Hash,
+) -> bool {
+    if ciphertexts.len() != proofs.len() {
+        return false;
+    }
+
+    let bit_tag = tag(BIT_PROOF_TAG, election_id, voter_id);
+    let bits_valid = ciphertexts
+        .iter()
+        .zip(proofs)
+        .all(|(ciphertext, proof)| verify_bit(election_key, &bit_tag, ciphertext, proof));
+    if !bits_valid {
+        return false;
+    }
+
+    let (sum_a, sum_b, challenge, response) = match (
+        sum(ciphertexts),
+        group::hash_to_scalar(sum_challenge),
+        group::hash_to_scalar(sum_response),
+    ) {
+        (Some((a, b)), Some(c), Some(s)) => (a, b, c, s),
+        _ => return false,
+    };
+
+    let sum_b = sum_b - RISTRETTO_BASEPOINT_POINT;
+    let expected = group::challenge(
+        &tag(SUM_PROOF_TAG, election_id, voter_id),
+        &[
+            election_key,
+            &sum_a,
+            &sum_b,
+            &(&response * &RISTRETTO_BASEPOINT_TABLE - challenge * sum_a),
+            &(response * election_key - challenge * sum_b),
+        ],
+    );
+
+    expected == challenge
+}
+
+/// Encrypts the vote of the voter and proves that it chooses the candidate with
+/// the specified position in the candidates table (see `v1/candidates`),
+/// which is expected to be the candidate of the vote.
+///
+/// Runs on the client side.
+pub fn encrypt_vote(
+    vote: &Vote,
+    choice: usize,
+    candidates: &[PublicKey],
+    election_key: &PublicKey,
+    election_id: u64,
+    voter_id: &PublicKey,
+) -> ProvenVote {
+    let key_pair = agreement::generate_key_pair();
+    let election_point =
+        group::key_to_point(election_key).expect("zkp::encrypt_vote: invalid election key");
+    let points: Vec<RistrettoPoint> = candidates.iter().map(candidate_point).collect();
+    let choice_point = points[choice] + key_pair.secret * election_point;
+    let proof_tag = tag(CHOICE_PROOF_TAG, election_id, voter_id);
+
+    let mut challenges = vec![Scalar::zero(); points.len()];
+    let mut responses = vec![Scalar::zero(); points.len()];
+    let mut commitments = Vec::with_capacity(points.len());
+    let nonce = group::random_scalar();
+    for (pos, point) in points.iter().enumerate() {
+        if pos == choice {
+            commitments.push((&nonce * &RISTRETTO_BASEPOINT_TABLE, nonce * election_point));
+            continue;
+        }
+        challenges[pos] = group::random_scalar();
+        responses[pos] = group::random_scalar();
+        commitments.push((
+            &responses[pos] * &RISTRETTO_BASEPOINT_TABLE - challenges[pos] * key_pair.public,
+            responses[pos] * election_point - challenges[pos] * (choice_point - point),
+        ));
+    }
+
+    let challenge = choice_challenge(
+        &proof_tag,
+        &election_point,
+        &key_pair.public,
+        &choice_point,
+        &points,
+        &commitments,
+    );
+    let simulated = challenges
+        .iter()
+        .fold(Scalar::zero(), |sum, challenge| sum + challenge);
+    challenges[choice] = challenge - simulated;
+    responses[choice] = nonce + challenges[choice] * key_pair.secret;
+
+    ProvenVote {
+        vote: cipher::encrypt_vote_with_key(vote, &key_pair, &election_point),
+        choice: group::point_to_key(&choice_point),
+        proofs: challenges
+            .iter()
+            .zip(&responses)
+            .map(|(challenge, response)| {
+                ChoiceProof::new(
+                    &group::scalar_to_hash(challenge),
+                    &group::scalar_to_hash(response),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Checks the proof that the choice of the vote of the voter in the election,
+/// encrypted under the one-time key of the vote, is the point of one of the candidates.
+pub fn verify_vote(
+    election_key: &RistrettoPoint,
+    election_id: u64,
+    voter_id: &PublicKey,
+    candidates: &[PublicKey],
+    vote_key: &PublicKey,
+    choice: &PublicKey,
+    proofs: &[ChoiceProof],
+) -> bool {
+    if candidates.len() != proofs.len() {
+        return false;
+    }
+    let points = (group::key_to_point(vote_key), group::key_to_point(choice));
+    let (vote_point, choice_point) = match points {
+        (Some(vote_point), Some(choice_point)) => (vote_point, choice_point),
+        _ => return false,
+    };
+
+    let points: Vec<RistrettoPoint> = candidates.iter().map(candidate_point).collect();
+    let mut challenge_sum = Scalar::zero();
+    let mut commitments = Vec::with_capacity(points.len());
+    for (point, proof) in points.iter().zip(proofs) {
+        let (challenge, response) = match (
+            group::hash_to_scalar(proof.challenge()),
+            group::hash_to_scalar(proof.response()),
+        ) {
+            (Some(challenge), Some(response)) => (challenge, response),
+            _ => return false,
+        };
+        challenge_sum += challenge;
+        commitments.push((
+            &response * &RISTRETTO_BASEPOINT_TABLE - challenge * vote_point,
+            response * election_key - challenge * (choice_point - point),
+        ));
+    }
+
+    let expected = choice_challenge(
+        &tag(CHOICE_PROOF_TAG, election_id, voter_id),
+        election_key,
+        &vote_point,
+        &choice_point,
+        &points,
+        &commitments,
+    );
+    expected == challenge_sum
+}
+
+/// Returns the point, which encodes the candidate in the choice of a vote.
+pub fn candidate_point(candidate: &PublicKey) -> RistrettoPoint {
+    let mut bytes = CANDIDATE_POINT_TAG.to_vec();
+    bytes.extend_from_slice(candidate.as_ref());
+    RistrettoPoint::hash_from_bytes::<Sha512>(&bytes)
+}
+
+/// Returns the Fiat-Shamir challenge of the proof of a vote choice.
+fn choice_challenge(
+    proof_tag: &[u8],
+    election_key: &RistrettoPoint,
+    vote_point: &RistrettoPoint,
+    choice_point: &RistrettoPoint,
+    points: &[RistrettoPoint],
+    commitments: &[(RistrettoPoint, RistrettoPoint)],
+) -> Scalar {
+    let mut elements = vec![election_key, vote_point, choice_point];
+    elements.extend(points);
+    for &(ref first, ref second) in commitments {
+        elements.push(first);
+        elements.push(second);
+    }
+    group::challenge(proof_tag, &elements)
+}
+
+/// Proves that the ciphertext encrypts the message, which is 0 or 1.
+///
+/// The proof for the other message is simulated, so the verifier can not tell them apart.
+fn prove_bit(
+    election_key: &RistrettoPoint,
+    bit_tag: &[u8],
+    ciphertext: &Ciphertext,
+    message: u64,
+    randomness: &Scalar,
+) -> BitProof {
+    let a = group::key_to_point(ciphertext.a()).expect("zkp::prove_bit: invalid ciphertext");
+    let b = group::key_to_point(ciphertext.b()).expect("zkp::prove_bit: invalid ciphertext");
+    let real = message as usize;
+    let messages = [b, b - RISTRETTO_BASEPOINT_POINT];
+
+    let mut challenges = [Scalar::zero(); 2];
+    let mut responses = [Scalar::zero(); 2];
+    let mut commitments = [(RistrettoPoint::identity(), RistrettoPoint::identity()); 2];
+
+    let simulated = 1 - real;
+    challenges[simulated] = group::random_scalar();
+    responses[simulated] = group::random_scalar();
+    commitments[simulated] = (
+        &responses[simulated] * &RISTRETTO_BASEPOINT_TABLE - challenges[simulated] * a,
+        responses[simulated] * election_key - challenges[simulated] * messages[simulated],
+    );
+
+    let nonce = group::random_scalar();
+    commitments[real] = (
+        &nonce * &RISTRETTO_BASEPOINT_TABLE,
+        nonce * election_key,
+    );
+
+    let challenge = group::challenge(
+        bit_tag,
+        &[
+            election_key,
+            &a,
+            &b,
+            &commitments[0].0,
+            &commitments[0].1,
+            &commitments[1].0,
+            &commitments[1].1,
+        ],
+    );
+    challenges[real] = challenge - challenges[simulated];
+    responses[real] = nonce + challenges[real] * randomness;
+
+    BitProof::new(
+        &group::scalar_to_hash(&challenges[0]),
+        &group::scalar_to_hash(&challenges[1]),
+        &group::scalar_to_hash(&responses[0]),
+        &group::scalar_to_hash(&responses[1]),
+    )
+}
+
+/// Checks the proof that the ciphertext encrypts 0 or 1.
+fn verify_bit(
+    election_key: &RistrettoPoint,
+    bit_tag: &[u8],
+    ciphertext: &Ciphertext,
+    proof: &BitProof,
+) -> bool {
+    let values = (
+        group::key_to_point(ciphertext.a()),
+        group::key_to_point(ciphertext.b()),
+        group::hash_to_scalar(proof.challenge_zero()),
+        group::hash_to_scalar(proof.challenge_one()),
+        group::hash_to_scalar(proof.response_zero()),
+        group::hash_to_scalar(proof.response_one()),
+    );
+    let (a, b, c0, c1, s0, s1) = match values {
+        (Some(a), Some(b), Some(c0), Some(c1), Some(s0), Some(s1)) => (a, b, c0, c1, s0, s1),
+        _ => return false,
+    };
+
+    let b1 = b - RISTRETTO_BASEPOINT_POINT;
+    let expected = group::challenge(
+        bit_tag,
+        &[
+            election_key,
+            &a,
+            &b,
+            &(&s0 * &RISTRETTO_BASEPOINT_TABLE - c0 * a),
+            &(s0 * election_key - c0 * b),
+            &(&s1 * &RISTRETTO_BASEPOINT_TABLE - c1 * a),
+            &(s1 * election_key - c1 * b1),
+        ],
+    );
+
+    expected == c0 + c1
+}
+
+/// Adds ciphertexts component-wise.
+///
+/// Returns `None` if any of the ciphertexts is malformed.
+fn sum(ciphertexts: &[Ciphertext]) -> Option<(RistrettoPoint, RistrettoPoint)> {
+    let mut sum_a = RistrettoPoint::identity();
+    let mut sum_b = RistrettoPoint::identity();
+    for ciphertext in ciphertexts {
+        sum_a += group::key_to_point(ciphertext.a())?;
+        sum_b += group::key_to_point(ciphertext.b())?;
+    }
+    Some((sum_a, sum_b))
+}
+
+/// Returns domain separation tag bound to the election and the voter.
+fn tag(prefix: &[u8], election_id: u64, voter_id: &PublicKey) -> Vec<u8> {
+    let mut tag = prefix.to_vec();
+    tag.extend_from_slice(election_id.hash().as_ref());
+    tag.extend_from_slice(voter_id.as_ref());
+    tag
+}